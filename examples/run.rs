@@ -4,7 +4,7 @@ use crazy_train::{
 };
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 struct StepOne {}
 
 impl StepTrait for StepOne {
@@ -31,7 +31,7 @@ impl StepTrait for StepOne {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 struct StepTwo {}
 
 impl StepTrait for StepTwo {
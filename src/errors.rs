@@ -5,17 +5,27 @@
 //!
 use crate::{executer::Output, step};
 
+/// The number of characters of the partial stdout and stderr shown when displaying an
+/// [`Error::Timeout`]; the full output stays available in the variant.
+const MAX_DISPLAYED_OUTPUT: usize = 1_000;
+
 /// Represents errors that can occur in the Crazy Train library.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     /// An error indicating that a specific step in the execution process has failed.
-    #[error("Step failed: {:?}.\ndescription: {description}.\nstatus code: {:?}.\nstdout: {}.\nstderr: {}", kind, command_output.status_code, command_output.stdout, command_output.stderr)]
+    #[error("Step failed: {:?}.\ndescription: {description}.\ncommand: {}.\nstatus code: {:?}.\nstdout: {}.\nstderr: {}", kind, command_output.invocation, command_output.status_code, command_output.stdout, command_output.stderr)]
     StepError {
         kind: step::Kind,
         description: String,
         command_output: Output,
     },
 
+    /// An error indicating that a step kept failing after being retried, see
+    /// [`StepTrait::retries`](crate::step::StepTrait::retries). The source is the error of the
+    /// last attempt.
+    #[error("step failed after {attempts} attempts: {source}")]
+    RetriesExhausted { attempts: u32, source: Box<Self> },
+
     /// An error indicating a failure in input/output operations.
     #[error(transparent)]
     IO(#[from] std::io::Error),
@@ -24,9 +34,189 @@ pub enum Error {
     #[error("UTF-8 conversion error: {0}")]
     Utf8(#[from] std::str::Utf8Error),
 
+    /// An error indicating that string generation kept producing reserved words.
+    #[error("could not generate a string that is not a reserved word after {attempts} attempts")]
+    ReservedWordsExhausted { attempts: u32 },
+
+    /// An error indicating that a string definition has no character it can generate, e.g.
+    /// because all of them are excluded.
+    #[error("no character available to generate the string from")]
+    NoCharactersAvailable,
+
+    /// An error indicating that strings must contain every enabled character class, but may be
+    /// shorter than the number of classes.
+    #[error(
+        "string length {length} is shorter than the {classes} character classes it must contain"
+    )]
+    LengthTooShortForClasses { length: u32, classes: usize },
+
+    /// An error indicating that strings cannot be generated from a regular expression, see
+    /// [`Randomizer::from_regex`](crate::Randomizer::from_regex).
+    #[error("cannot generate strings matching {pattern:?}: {reason}")]
+    InvalidPattern { pattern: String, reason: String },
+
+    /// An error indicating that a normal distribution was given a negative or non-finite
+    /// standard deviation, see [`Randomizer::normal`](crate::Randomizer::normal).
+    #[error("standard deviation must be finite and non-negative, got {std_dev}")]
+    InvalidStdDev { std_dev: f64 },
+
+    /// An error indicating that the working directory grew beyond the configured disk budget.
+    #[error("disk budget exceeded: working directory uses {used} bytes, limit is {limit} bytes")]
+    DiskBudgetExceeded { used: u64, limit: u64 },
+
+    /// An error indicating that the command of a plan is longer than the configured maximum.
+    #[error("command of {id} is {length} bytes long, limit is {limit} bytes")]
+    CommandTooLong {
+        id: String,
+        length: usize,
+        limit: usize,
+    },
+
+    /// An error indicating that a weighted selection had no item with a positive score or weight.
+    #[error("none of the {items} items has a positive score")]
+    NoPositiveScore { items: usize },
+
+    /// An error indicating that a weighted selection was given an infinite or `NaN` score.
+    #[error("the score of item {index} is not finite: {score}")]
+    NonFiniteScore { index: usize, score: f64 },
+
+    /// An error indicating that a step does not produce the commands of a saved plan.
+    #[error("step {index} does not produce the commands of the saved plan")]
+    PlanMismatch { index: usize },
+
+    /// An error indicating that a fixture file could not be read.
+    #[error("could not read fixture file {}: {source}", path.display())]
+    FixtureRead {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+
+    /// An error indicating that a fixture file has no lines to pick from.
+    #[error("fixture file {} has no lines", path.display())]
+    EmptyFixture { path: std::path::PathBuf },
+
+    /// An error indicating that the working directory of a command does not exist.
+    #[error("working directory {} does not exist", path.display())]
+    WorkingDirectoryNotFound { path: std::path::PathBuf },
+
+    /// An error indicating that a command wrote to a path outside of the paths allowed by its
+    /// step.
+    #[error("command wrote outside of its allowed paths: {}", path.display())]
+    AccessOutsideScope {
+        path: std::path::PathBuf,
+        command_output: Output,
+    },
+
+    /// An error indicating that a command was killed by a signal its resource limits explain,
+    /// see [`RLimits`](crate::executer::RLimits) for how the signals are attributed.
+    #[cfg(unix)]
+    #[error("command killed by signal {signal} under resource limits {limits:?}")]
+    ResourceLimitExceeded {
+        limits: crate::executer::RLimits,
+        signal: i32,
+        command_output: Output,
+    },
+
+    /// An error indicating that a command of a plan with several commands failed, see
+    /// [`Plan::execute_all`](crate::step::Plan::execute_all). The outputs hold every executed
+    /// command, the failed one last; the commands after it were not run.
+    #[error("command {index} exited with status code {:?}", outputs.last().and_then(|output| output.status_code))]
+    CommandFailed { index: usize, outputs: Vec<Output> },
+
+    /// An error indicating that a command ran past its timeout and was killed. The partial
+    /// output holds whatever the command wrote before it was killed; only its last 1000
+    /// characters are displayed.
+    #[error("command timed out after {elapsed:?}: {command}.\npartial stdout: {}.\npartial stderr: {}", tail(&partial_output.stdout), tail(&partial_output.stderr))]
+    Timeout {
+        command: String,
+        elapsed: std::time::Duration,
+        partial_output: Output,
+    },
+
+    /// An error for YAML serialization failures.
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    /// An error for JSON serialization failures.
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// An error for invalid regular expressions.
+    #[error("invalid regular expression: {0}")]
+    Regex(#[from] regex::Error),
+
     /// A generic error type that captures any string error.
     #[error("{0}")]
     Any(String),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Returns the last [`MAX_DISPLAYED_OUTPUT`] characters of `text`, marking the truncation.
+fn tail(text: &str) -> std::borrow::Cow<'_, str> {
+    match text.char_indices().rev().nth(MAX_DISPLAYED_OUTPUT - 1) {
+        Some((start, _)) if start > 0 => format!("...{}", &text[start..]).into(),
+        _ => text.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn timeout_describes_partial_output() {
+        let err = Error::Timeout {
+            command: "sleep 10".to_string(),
+            elapsed: Duration::from_millis(1500),
+            partial_output: Output {
+                stdout: "started".to_string(),
+                ..Default::default()
+            },
+        };
+
+        assert_eq!(
+            err.to_string(),
+            "command timed out after 1.5s: sleep 10.\npartial stdout: started.\npartial stderr: "
+        );
+        let Error::Timeout {
+            elapsed,
+            partial_output,
+            ..
+        } = err
+        else {
+            panic!("expected a timeout");
+        };
+        assert_eq!(elapsed, Duration::from_millis(1500));
+        assert_eq!(partial_output.stdout, "started");
+    }
+
+    #[test]
+    fn timeout_truncates_displayed_output() {
+        let stdout = format!("{}end", "é".repeat(2 * MAX_DISPLAYED_OUTPUT));
+        let err = Error::Timeout {
+            command: "yes".to_string(),
+            elapsed: Duration::from_secs(1),
+            partial_output: Output {
+                stdout: stdout.clone(),
+                ..Default::default()
+            },
+        };
+
+        let message = err.to_string();
+        let shown = message
+            .split("partial stdout: ...")
+            .nth(1)
+            .and_then(|rest| rest.split(".\npartial stderr").next())
+            .unwrap();
+        assert_eq!(shown.chars().count(), MAX_DISPLAYED_OUTPUT);
+        assert!(shown.ends_with("end"));
+        let Error::Timeout { partial_output, .. } = err else {
+            panic!("expected a timeout");
+        };
+        assert_eq!(partial_output.stdout, stdout);
+    }
+}
@@ -1,12 +1,61 @@
 //! This module provides functionality for executing shell commands and capturing their outputs.
 //!
 //! The [`Output`] struct represents the output of a shell command, including the status code,
-//! standard output (stdout), and standard error (stderr).
+//! standard output (stdout), and standard error (stderr). Volatile parts of an output can be
+//! masked with [`Output::masked`] to compare outputs on their stable content, and two outputs
+//! can be compared line by line with [`Output::diff`]. The
+//! [`ExecOptions`] struct controls the environment and working directory a command runs with.
+//!
+//! With the `trace-paths` feature on Linux, [`run_sh_traced`] additionally records the paths a
+//! command writes to, using `strace`.
+
+// The errors built here carry the full command output, which is larger than clippy's
+// threshold for error variants; it stays unboxed so the public error fields are plain `Output`s.
+#![allow(clippy::result_large_err)]
+
+use std::{
+    collections::HashMap,
+    hash::BuildHasher,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use regex::Regex;
+use serde::Serialize;
+use similar::TextDiff;
 
 use crate::errors::Result;
 
+/// How often a command running under a timeout is polled for completion.
+const TIMEOUT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How far below the CPU time limit the measured CPU time of a killed command may be and still
+/// count as reaching it, since the CPU time is accounted at a coarser granularity than the
+/// limit is enforced.
+#[cfg(unix)]
+const CPU_TIME_TOLERANCE: Duration = Duration::from_millis(100);
+
+/// The placeholder masked regions of an output are replaced with.
+pub const MASK_PLACEHOLDER: &str = "<masked>";
+
+/// The patterns of [`Output::default_masks`].
+const DEFAULT_MASKS: [&str; 6] = [
+    // ISO 8601 timestamps, e.g. `2024-01-01T10:00:00.123Z`.
+    r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:?\d{2})?",
+    // Times of day, e.g. `10:00:00`.
+    r"\b\d{2}:\d{2}:\d{2}(\.\d+)?\b",
+    // UUIDs.
+    r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+    // Memory addresses, e.g. `0x7ffd5e8c`.
+    r"\b0x[0-9a-fA-F]+\b",
+    // Process ids, e.g. `pid 1234` or `PID=1234`.
+    r"\b(pid|PID)[ =:]*\d+",
+    // Paths in temporary directories.
+    r#"(/tmp|/var/folders)/[^\s'"]*"#,
+];
+
 /// Represents the output of a shell command execution.
-#[derive(Debug)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
 pub struct Output {
     /// The exit status code of the command. It is optional to accommodate commands that may not
     /// return a status code.
@@ -15,6 +64,350 @@ pub struct Output {
     pub stdout: String,
     /// The standard error output produced by the command.
     pub stderr: String,
+    /// The full command line that was run, including the working directory, the environment
+    /// variables and the shell wrapper, e.g. `cd '/tmp' && FOO='bar' sh -c 'echo $FOO'`.
+    pub invocation: String,
+    /// The PID of the spawned process. Commands are run through a shell (`sh -c` or
+    /// `cmd.exe /C`), so this is the PID of the shell, and the command itself may run in a
+    /// child process of it.
+    pub pid: Option<u32>,
+    /// How long the command took, from spawning the shell until it exited and its output was
+    /// fully captured.
+    pub duration: Duration,
+}
+
+impl Output {
+    /// Returns a copy of the output with every region of stdout and stderr matching one of the
+    /// masks replaced by [`MASK_PLACEHOLDER`], so comparisons focus on the stable content. The
+    /// PID and the duration are volatile as well and are cleared.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::executer::Output;
+    /// let output = Output {
+    ///     stdout: "started at 2024-01-01T10:00:00Z".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let masked = output.masked(&Output::default_masks());
+    /// assert_eq!(masked.stdout, "started at <masked>");
+    /// ```
+    #[must_use]
+    pub fn masked(&self, masks: &[Regex]) -> Self {
+        let mask = |text: &str| {
+            masks.iter().fold(text.to_string(), |text, mask| {
+                mask.replace_all(&text, MASK_PLACEHOLDER).into_owned()
+            })
+        };
+
+        Self {
+            stdout: mask(&self.stdout),
+            stderr: mask(&self.stderr),
+            pid: None,
+            duration: Duration::ZERO,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a unified diff of the stdout and the stderr of this output against `other`,
+    /// with `-` lines from this output and `+` lines from `other`. Streams that are equal are
+    /// left out, so the diff is empty when both outputs printed the same.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::executer::Output;
+    /// let expected = Output {
+    ///     stdout: "a\nb\n".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let actual = Output {
+    ///     stdout: "a\nc\n".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// assert!(expected.diff(&actual).contains("-b\n+c\n"));
+    /// ```
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> String {
+        [
+            ("stdout", &self.stdout, &other.stdout),
+            ("stderr", &self.stderr, &other.stderr),
+        ]
+        .into_iter()
+        .filter(|(_, a, b)| a != b)
+        .map(|(stream, a, b)| {
+            TextDiff::from_lines(a, b)
+                .unified_diff()
+                .context_radius(3)
+                .header(&format!("a/{stream}"), &format!("b/{stream}"))
+                .to_string()
+        })
+        .collect()
+    }
+
+    /// Returns whether the command exited with status code 0. A command without a status
+    /// code, e.g. killed by a signal, did not succeed.
+    ///
+    /// Together with the other assertion helpers, this keeps [`StepTrait::is_success`]
+    /// implementations short.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::executer::Output;
+    /// let output = Output {
+    ///     status_code: Some(0),
+    ///     stdout: "migration done\n".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// assert!(output.succeeded() && output.stdout_contains("done"));
+    /// ```
+    ///
+    /// [`StepTrait::is_success`]: crate::step::StepTrait::is_success
+    #[must_use]
+    pub const fn succeeded(&self) -> bool {
+        matches!(self.status_code, Some(0))
+    }
+
+    /// Returns whether stdout contains `needle`.
+    #[must_use]
+    pub fn stdout_contains(&self, needle: &str) -> bool {
+        self.stdout.contains(needle)
+    }
+
+    /// Returns whether stderr contains `needle`.
+    #[must_use]
+    pub fn stderr_contains(&self, needle: &str) -> bool {
+        self.stderr.contains(needle)
+    }
+
+    /// Returns whether `re` matches somewhere in stdout.
+    #[must_use]
+    pub fn stdout_matches(&self, re: &Regex) -> bool {
+        re.is_match(&self.stdout)
+    }
+
+    /// Returns whether `re` matches somewhere in stderr.
+    #[must_use]
+    pub fn stderr_matches(&self, re: &Regex) -> bool {
+        re.is_match(&self.stderr)
+    }
+
+    /// Returns masks for common volatile content: timestamps, times of day, UUIDs, memory
+    /// addresses, process ids and paths in temporary directories.
+    ///
+    /// # Panics
+    ///
+    /// Never, the default patterns are valid.
+    #[must_use]
+    pub fn default_masks() -> Vec<Regex> {
+        DEFAULT_MASKS
+            .iter()
+            .map(|pattern| Regex::new(pattern).expect("valid default mask"))
+            .collect()
+    }
+}
+
+/// Options controlling how a shell command is executed.
+#[derive(Debug, Default, Clone)]
+pub struct ExecOptions {
+    /// Environment variables set for the command, overriding inherited ones.
+    pub env: HashMap<String, String>,
+    /// The directory the command runs in. Defaults to the current directory.
+    pub cwd: Option<PathBuf>,
+    /// Data written to the stdin of the command. Defaults to inheriting the stdin of the
+    /// current process.
+    pub stdin: Option<String>,
+    /// Resource limits applied to the command.
+    #[cfg(unix)]
+    pub limits: Option<RLimits>,
+    /// The CPUs the command is pinned to with `sched_setaffinity`.
+    #[cfg(target_os = "linux")]
+    pub cpu_affinity: Option<Vec<usize>>,
+    /// The maximum time the command may run before it is killed, together with every process
+    /// it spawned on Unix.
+    pub timeout: Option<Duration>,
+    /// The shell the command is run with. Defaults to `/bin/sh` on Unix and `%COMSPEC%` on
+    /// Windows.
+    pub shell: Option<Shell>,
+    /// Whether stderr is merged into stdout, like `2>&1`, so [`Output::stdout`] holds both
+    /// streams in the order they were written and [`Output::stderr`] is empty. The exact
+    /// interleaving depends on how the command buffers each stream.
+    pub combine_output: bool,
+}
+
+/// A shell commands can be run with, see [`run_with_shell`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    /// The POSIX shell, `sh -c`.
+    Sh,
+    /// Bash, `bash -c`.
+    Bash,
+    /// The Windows command prompt, `cmd.exe /Q /C`.
+    Cmd,
+    /// PowerShell 7, `pwsh -NoProfile -NonInteractive -Command`.
+    PowerShell,
+}
+
+impl Default for Shell {
+    /// [`Shell::Cmd`] on Windows and [`Shell::Sh`] elsewhere.
+    fn default() -> Self {
+        if cfg!(windows) {
+            Self::Cmd
+        } else {
+            Self::Sh
+        }
+    }
+}
+
+impl Shell {
+    /// Returns the program and the arguments preceding the command.
+    const fn argv(self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            Self::Sh => ("sh", &["-c"]),
+            Self::Bash => ("bash", &["-c"]),
+            Self::Cmd => ("cmd.exe", &["/Q", "/C"]),
+            Self::PowerShell => ("pwsh", &["-NoProfile", "-NonInteractive", "-Command"]),
+        }
+    }
+
+    /// Builds the expression running `command` with the shell.
+    fn expression(self, command: &str) -> duct::Expression {
+        let (program, args) = self.argv();
+        let mut args: Vec<&str> = args.to_vec();
+        args.push(command);
+        duct::cmd(program, args)
+    }
+
+    /// Renders the command line running `command` with the shell.
+    fn render(self, command: &str) -> String {
+        let (program, args) = self.argv();
+        let command = if self == Self::Cmd {
+            command.to_string()
+        } else {
+            quote(command)
+        };
+        format!("{program} {} {command}", args.join(" "))
+    }
+}
+
+/// Resource limits applied with `setrlimit` in the child process before the command is
+/// executed. The limits are inherited by every process the command spawns.
+///
+/// A command killed by `SIGXCPU`, or by `SIGKILL` once its CPU time reached the limit, exceeded
+/// the CPU time limit. A command exceeding the address space limit sees its allocations fail
+/// and usually dies by `SIGSEGV`, `SIGABRT` or `SIGBUS`; since a genuine crash dies the same
+/// way, any of these signals is reported as a breach while the address space is limited.
+#[cfg(unix)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RLimits {
+    /// The maximum size of the address space of each process, in bytes (`RLIMIT_AS`).
+    pub address_space: Option<u64>,
+    /// The maximum CPU time of each process, in seconds (`RLIMIT_CPU`).
+    pub cpu_seconds: Option<u64>,
+}
+
+#[cfg(unix)]
+impl RLimits {
+    /// Applies the limits to the current process.
+    fn apply(self) -> std::io::Result<()> {
+        let limits = [
+            (libc::RLIMIT_AS, self.address_space),
+            (libc::RLIMIT_CPU, self.cpu_seconds),
+        ];
+
+        for (resource, value) in limits {
+            let Some(value) = value else {
+                continue;
+            };
+            let limit = libc::rlimit {
+                rlim_cur: value,
+                rlim_max: value,
+            };
+            // SAFETY: `limit` is a valid `rlimit` that outlives the call.
+            if unsafe { libc::setrlimit(resource, std::ptr::addr_of!(limit)) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether a command killed by `signal` after using `cpu_time` was most likely
+    /// killed for exceeding the limits.
+    ///
+    /// A `SIGKILL` is attributed to the CPU time limit only when the CPU time reached it, so a
+    /// kill from the OOM killer or another process is not. Exceeding the address space makes
+    /// allocations fail, which a command usually dies from with `SIGSEGV`, `SIGABRT` or
+    /// `SIGBUS`; these signals are attributed to the address space limit whenever it is set, so
+    /// a genuine crash of a command running under it is reported as a limit breach as well.
+    fn explain(self, signal: i32, cpu_time: Duration) -> bool {
+        match signal {
+            libc::SIGXCPU => self.cpu_seconds.is_some(),
+            libc::SIGKILL => self.cpu_seconds.is_some_and(|seconds| {
+                cpu_time + CPU_TIME_TOLERANCE >= Duration::from_secs(seconds)
+            }),
+            libc::SIGSEGV | libc::SIGABRT | libc::SIGBUS => self.address_space.is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// Returns the CPU time used by the terminated and waited for children of the current
+/// process.
+#[cfg(unix)]
+fn children_cpu_time() -> Duration {
+    // SAFETY: `rusage` is plain data for which all zeros is a valid value.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: `usage` is a valid `rusage` that outlives the call.
+    if unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, std::ptr::addr_of_mut!(usage)) } != 0 {
+        return Duration::ZERO;
+    }
+    [usage.ru_utime, usage.ru_stime]
+        .iter()
+        .map(|time| {
+            Duration::from_secs(u64::try_from(time.tv_sec).unwrap_or_default())
+                + Duration::from_micros(u64::try_from(time.tv_usec).unwrap_or_default())
+        })
+        .sum()
+}
+
+/// Returns the CPUs the current process is allowed to run on.
+///
+/// # Errors
+///
+/// When the affinity of the current process can not be read.
+#[cfg(target_os = "linux")]
+pub fn available_cpus() -> std::io::Result<Vec<usize>> {
+    // SAFETY: an all-zero `cpu_set_t` is a valid, empty set.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    // SAFETY: `set` is a valid `cpu_set_t` of the given size that outlives the call.
+    let result = unsafe {
+        libc::sched_getaffinity(
+            0,
+            std::mem::size_of::<libc::cpu_set_t>(),
+            std::ptr::addr_of_mut!(set),
+        )
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok((0..libc::CPU_SETSIZE as usize)
+        // SAFETY: `cpu` is below `CPU_SETSIZE`.
+        .filter(|cpu| unsafe { libc::CPU_ISSET(*cpu, &set) })
+        .collect())
+}
+
+/// Builds the CPU set pinning a process to the given CPUs.
+#[cfg(target_os = "linux")]
+fn cpu_set(cpus: &[usize]) -> libc::cpu_set_t {
+    // SAFETY: an all-zero `cpu_set_t` is a valid, empty set.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for cpu in cpus {
+        // SAFETY: `CPU_SET` ignores CPUs beyond `CPU_SETSIZE`.
+        unsafe { libc::CPU_SET(*cpu, &mut set) };
+    }
+    set
 }
 
 /// Executes a shell command and returns its output.
@@ -25,15 +418,718 @@ pub struct Output {
 /// - The command fails to execute.
 /// - There is an error capturing the output or converting it to a UTF-8 string.
 pub fn run_sh(command: &str) -> Result<Output> {
-    let output = duct_sh::sh_dangerous(command)
-        .stderr_capture()
-        .stderr_capture()
-        .unchecked()
-        .run()?;
+    run_sh_with(command, &ExecOptions::default())
+}
+
+/// Executes a shell command with the given environment variables, overriding inherited ones,
+/// and returns its output. The environment of the current process is left untouched.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The command fails to execute.
+/// - There is an error capturing the output or converting it to a UTF-8 string.
+pub fn run_sh_with_env<S: BuildHasher>(
+    command: &str,
+    env: &HashMap<String, String, S>,
+) -> Result<Output> {
+    run_sh_with(
+        command,
+        &ExecOptions {
+            env: env
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            ..Default::default()
+        },
+    )
+}
+
+/// Executes a shell command in the given working directory and returns its output.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The working directory does not exist, which is reported as
+///   [`Error::WorkingDirectoryNotFound`](crate::Error::WorkingDirectoryNotFound).
+/// - The command fails to execute.
+/// - There is an error capturing the output or converting it to a UTF-8 string.
+pub fn run_sh_in(command: &str, cwd: &Path) -> Result<Output> {
+    run_sh_with(
+        command,
+        &ExecOptions {
+            cwd: Some(cwd.to_path_buf()),
+            ..Default::default()
+        },
+    )
+}
+
+/// Executes a command with the given shell and returns its output, e.g. with
+/// [`Shell::PowerShell`] for steps written for Windows. [`Shell::default`] picks the shell of
+/// the current platform.
+///
+/// # Example
+///
+/// ```rust
+/// use crazy_train::executer::{run_with_shell, Shell};
+/// let output = run_with_shell("echo hello", Shell::default()).unwrap();
+/// assert_eq!(output.stdout.trim_end(), "hello");
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The shell can not be found or the command fails to execute.
+/// - There is an error capturing the output or converting it to a UTF-8 string.
+pub fn run_with_shell(command: &str, shell: Shell) -> Result<Output> {
+    run_sh_with(
+        command,
+        &ExecOptions {
+            shell: Some(shell),
+            ..Default::default()
+        },
+    )
+}
+
+/// Executes a shell command with stderr merged into stdout, see
+/// [`ExecOptions::combine_output`], and returns its output. [`Output::stdout`] holds both
+/// streams in the order they were written, which shows whether an error was printed before or
+/// after a given line. The exact interleaving depends on how the command buffers each stream,
+/// e.g. a program that fully buffers stdout when it is not a terminal writes it last.
+///
+/// # Example
+///
+/// ```rust
+/// use crazy_train::executer::run_sh_combined;
+/// # #[cfg(unix)]
+/// # {
+/// let output = run_sh_combined("echo start; echo failed >&2; echo end").unwrap();
+/// assert_eq!(output.stdout, "start\nfailed\nend\n");
+/// assert!(output.stderr.is_empty());
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The command fails to execute.
+/// - There is an error capturing the output or converting it to a UTF-8 string.
+pub fn run_sh_combined(command: &str) -> Result<Output> {
+    run_sh_with(
+        command,
+        &ExecOptions {
+            combine_output: true,
+            ..Default::default()
+        },
+    )
+}
+
+/// Executes a shell command with the given options and returns its output.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The working directory does not exist, which is reported as
+///   [`Error::WorkingDirectoryNotFound`](crate::Error::WorkingDirectoryNotFound).
+/// - The command fails to execute.
+/// - There is an error capturing the output or converting it to a UTF-8 string.
+/// - Resource limits are set and the command was killed by a signal they explain, see
+///   [`RLimits`], which is reported as
+///   [`Error::ResourceLimitExceeded`](crate::Error::ResourceLimitExceeded).
+/// - A timeout is set and the command ran past it, which is reported as
+///   [`Error::Timeout`](crate::Error::Timeout) with the output captured before the kill.
+pub fn run_sh_with(command: &str, options: &ExecOptions) -> Result<Output> {
+    let expression = options.shell.map_or_else(
+        || duct_sh::sh_dangerous(command),
+        |shell| shell.expression(command),
+    );
+    execute(expression, command, invocation(command, options), options)
+}
+
+/// Executes `program` directly with the arguments `args`, without a shell, and returns its
+/// output. Each argument reaches the program verbatim, whatever spaces, quotes, globs or `;`
+/// it contains.
+///
+/// # Example
+///
+/// ```rust
+/// use crazy_train::executer::run_argv;
+/// # #[cfg(unix)]
+/// # {
+/// let output = run_argv("printf", &["[%s]".to_string(), "a b; *".to_string()]).unwrap();
+/// assert_eq!(output.stdout, "[a b; *]");
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The program can not be found or fails to execute.
+/// - There is an error capturing the output or converting it to a UTF-8 string.
+pub fn run_argv(program: &str, args: &[String]) -> Result<Output> {
+    run_argv_with(program, args, &ExecOptions::default())
+}
+
+/// Executes `program` directly with the arguments `args` and the given options, see
+/// [`run_argv`]. The shell of the options is ignored.
+///
+/// # Errors
+///
+/// The same as [`run_sh_with`], and when the program can not be found.
+pub fn run_argv_with(program: &str, args: &[String], options: &ExecOptions) -> Result<Output> {
+    let command = render_argv(program, args);
+    let invocation = invocation_line(command.clone(), options);
+    execute(duct::cmd(program, args), &command, invocation, options)
+}
+
+/// Executes the expression of `command` with the given options and returns its output.
+fn execute(
+    expression: duct::Expression,
+    command: &str,
+    invocation: String,
+    options: &ExecOptions,
+) -> Result<Output> {
+    let mut expression = configure(expression, options)?;
+    #[cfg(unix)]
+    if let Some(limits) = options.limits {
+        expression = expression.before_spawn(move |command| {
+            use std::os::unix::process::CommandExt;
+            // SAFETY: the hook only calls `setrlimit`, which is async-signal-safe.
+            unsafe { command.pre_exec(move || limits.apply()) };
+            Ok(())
+        });
+    }
 
-    Ok(Output {
+    #[cfg(target_os = "linux")]
+    if let Some(cpus) = &options.cpu_affinity {
+        let set = cpu_set(cpus);
+        expression = expression.before_spawn(move |command| {
+            use std::os::unix::process::CommandExt;
+            // SAFETY: the hook only calls `sched_setaffinity`, which is async-signal-safe.
+            unsafe {
+                command.pre_exec(move || {
+                    let size = std::mem::size_of::<libc::cpu_set_t>();
+                    if libc::sched_setaffinity(0, size, std::ptr::addr_of!(set)) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            };
+            Ok(())
+        });
+    }
+
+    #[cfg(unix)]
+    if options.timeout.is_some() {
+        expression = expression.before_spawn(|command| {
+            use std::os::unix::process::CommandExt;
+            // Own process group, so the whole tree of the command is killed on timeout.
+            command.process_group(0);
+            Ok(())
+        });
+    }
+
+    // Stderr is joined to stdout inside the capture, so both go to the same pipe.
+    let expression = if options.combine_output {
+        expression.stderr_to_stdout()
+    } else {
+        expression.stderr_capture()
+    };
+
+    #[cfg(unix)]
+    let cpu_time_before = children_cpu_time();
+    let started = Instant::now();
+    let handle = expression.stdout_capture().unchecked().start()?;
+    let pid = handle.pids().first().copied();
+
+    let mut timed_out = None;
+    while let Some(timeout) = options.timeout {
+        if handle.try_wait()?.is_some() {
+            break;
+        }
+        let elapsed = started.elapsed();
+        if elapsed >= timeout {
+            #[cfg(unix)]
+            if let Some(group) = pid.and_then(|pid| i32::try_from(pid).ok()) {
+                // SAFETY: `kill` has no memory safety requirements.
+                unsafe { libc::kill(-group, libc::SIGKILL) };
+            }
+            handle.kill()?;
+            timed_out = Some(elapsed);
+            break;
+        }
+        std::thread::sleep(TIMEOUT_POLL_INTERVAL.min(timeout.saturating_sub(elapsed)));
+    }
+    let output = handle.into_output()?;
+
+    let command_output = Output {
         status_code: output.status.code(),
         stdout: std::str::from_utf8(&output.stdout)?.to_string(),
         stderr: std::str::from_utf8(&output.stderr)?.to_string(),
-    })
+        invocation,
+        pid,
+        duration: started.elapsed(),
+    };
+
+    if let Some(elapsed) = timed_out {
+        return Err(crate::Error::Timeout {
+            command: command.to_string(),
+            elapsed,
+            partial_output: command_output,
+        });
+    }
+
+    // Commands running concurrently in other threads may add to the CPU time of the children.
+    #[cfg(unix)]
+    if let (Some(limits), Some(signal)) = (
+        options.limits,
+        std::os::unix::process::ExitStatusExt::signal(&output.status),
+    ) {
+        if limits.explain(signal, children_cpu_time().saturating_sub(cpu_time_before)) {
+            return Err(crate::Error::ResourceLimitExceeded {
+                limits,
+                signal,
+                command_output,
+            });
+        }
+    }
+
+    Ok(command_output)
+}
+
+/// Applies the environment variables, the working directory and the stdin of the options to
+/// the expression.
+fn configure(mut expression: duct::Expression, options: &ExecOptions) -> Result<duct::Expression> {
+    for (key, value) in &options.env {
+        expression = expression.env(key, value);
+    }
+    if let Some(cwd) = &options.cwd {
+        if !cwd.is_dir() {
+            return Err(crate::Error::WorkingDirectoryNotFound { path: cwd.clone() });
+        }
+        expression = expression.dir(cwd);
+    }
+    if let Some(stdin) = &options.stdin {
+        expression = expression.stdin_bytes(stdin.as_bytes());
+    }
+    Ok(expression)
+}
+
+/// Executes a shell command under `strace` and returns its output together with the paths it
+/// wrote to, i.e. files opened for writing, created, removed or renamed, including the
+/// destination of a rename, and directories created or removed. Relative paths are resolved
+/// against the directory file descriptor of the syscall or the working directory of the
+/// process, following `cd`, and every path is normalized, so `..` components never hide a path
+/// outside of a directory.
+///
+/// Returns `None` for the paths when `strace` could not trace the command, e.g. because it is
+/// not installed or `ptrace` is denied, in which case the command is executed without tracing.
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The command fails to execute.
+/// - There is an error capturing the output or converting it to a UTF-8 string.
+#[cfg(all(target_os = "linux", feature = "trace-paths"))]
+pub fn run_sh_traced(
+    command: &str,
+    options: &ExecOptions,
+) -> Result<(Output, Option<Vec<PathBuf>>)> {
+    run_traced(
+        &format!("sh -c {}", quote(command)),
+        invocation(command, options),
+        || run_sh_with(command, options),
+        options,
+    )
+}
+
+/// Executes `program` directly with the arguments `args` under `strace`, like
+/// [`run_argv_with`], and returns its output together with the paths it wrote to, see
+/// [`run_sh_traced`].
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The command fails to execute.
+/// - There is an error capturing the output or converting it to a UTF-8 string.
+#[cfg(all(target_os = "linux", feature = "trace-paths"))]
+pub fn run_argv_traced(
+    program: &str,
+    args: &[String],
+    options: &ExecOptions,
+) -> Result<(Output, Option<Vec<PathBuf>>)> {
+    let target = std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .map(quote)
+        .collect::<Vec<_>>()
+        .join(" ");
+    run_traced(
+        &format!("-- {target}"),
+        invocation_line(render_argv(program, args), options),
+        || run_argv_with(program, args, options),
+        options,
+    )
+}
+
+/// Runs `strace` on the command line `target`, running `untraced` instead when `strace` could
+/// not be run or could not trace the command.
+#[cfg(all(target_os = "linux", feature = "trace-paths"))]
+fn run_traced(
+    target: &str,
+    invocation: String,
+    untraced: impl FnOnce() -> Result<Output>,
+    options: &ExecOptions,
+) -> Result<(Output, Option<Vec<PathBuf>>)> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static TRACE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let trace_file = std::env::temp_dir().join(format!(
+        "crazy-train-trace-{}-{}",
+        std::process::id(),
+        TRACE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let traced_command = format!(
+        "strace -f -qq -y -s 4096 -e trace={} -o {} {target}",
+        crate::trace::TRACED_SYSCALLS,
+        quote(&trace_file.display().to_string()),
+    );
+
+    let mut output = run_sh_with(&traced_command, options)?;
+    output.invocation = invocation;
+
+    let trace = std::fs::read_to_string(&trace_file);
+    let _ = std::fs::remove_file(&trace_file);
+    let trace = match trace {
+        Ok(trace) if crate::trace::executed(&trace) => trace,
+        // `strace` is missing or could not attach, so the command did not run under it.
+        _ => return Ok((untraced()?, None)),
+    };
+
+    let cwd = match &options.cwd {
+        Some(cwd) => cwd.clone(),
+        None => std::env::current_dir()?,
+    };
+    Ok((output, Some(crate::trace::written_paths(&trace, &cwd))))
+}
+
+/// Renders the command line that is run for `command` with the given options.
+fn invocation(command: &str, options: &ExecOptions) -> String {
+    let command_line = options.shell.map_or_else(
+        || {
+            if cfg!(windows) {
+                format!("cmd.exe /Q /C {command}")
+            } else {
+                format!("sh -c {}", quote(command))
+            }
+        },
+        |shell| shell.render(command),
+    );
+    invocation_line(command_line, options)
+}
+
+/// Renders `command_line` preceded by the working directory and the environment variables of
+/// the options.
+fn invocation_line(command_line: String, options: &ExecOptions) -> String {
+    let mut parts = Vec::new();
+
+    if let Some(cwd) = &options.cwd {
+        parts.push(format!("cd {} &&", quote(&cwd.display().to_string())));
+    }
+
+    let mut env: Vec<_> = options.env.iter().collect();
+    env.sort();
+    for (key, value) in env {
+        parts.push(format!("{key}={}", quote(value)));
+    }
+
+    parts.push(command_line);
+    if options.combine_output {
+        parts.push("2>&1".to_string());
+    }
+    parts.join(" ")
+}
+
+/// Renders a program and its arguments as a command line, quoting the arguments.
+pub(crate) fn render_argv(program: &str, args: &[String]) -> String {
+    std::iter::once(program.to_string())
+        .chain(args.iter().map(|arg| quote(arg)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Wraps `value` in single quotes, escaping any single quote it contains.
+pub(crate) fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn diff_highlights_differing_line() {
+        let expected = Output {
+            stdout: "one\ntwo\nthree\n".to_string(),
+            stderr: "warning\n".to_string(),
+            ..Default::default()
+        };
+        let actual = Output {
+            stdout: "one\n2\nthree\n".to_string(),
+            ..expected.clone()
+        };
+
+        let diff = expected.diff(&actual);
+        let changed: Vec<&str> = diff
+            .lines()
+            .filter(|line| {
+                (line.starts_with('-') || line.starts_with('+'))
+                    && !line.starts_with("---")
+                    && !line.starts_with("+++")
+            })
+            .collect();
+        assert_eq!(changed, vec!["-two", "+2"]);
+        assert!(diff.starts_with("--- a/stdout\n+++ b/stdout\n"));
+        assert!(!diff.contains("stderr"));
+        assert!(expected.diff(&expected).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timeout_kills_command_and_keeps_partial_output() {
+        let options = ExecOptions {
+            timeout: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+
+        let started = Instant::now();
+        let result = run_sh_with("echo partial; sleep 10", &options);
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        match result {
+            Err(crate::Error::Timeout {
+                command,
+                elapsed,
+                partial_output,
+            }) => {
+                assert_eq!(command, "echo partial; sleep 10");
+                assert!(elapsed >= Duration::from_secs(1));
+                assert_eq!(partial_output.stdout, "partial\n");
+            }
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+
+        let quick = run_sh_with("echo done", &options).unwrap();
+        assert_eq!(quick.stdout, "done\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_sh_with_env_overrides_inherited_variables() {
+        let env = HashMap::from([
+            ("FOO".to_string(), "bar".to_string()),
+            ("HOME".to_string(), "/nowhere".to_string()),
+        ]);
+
+        let output = run_sh_with_env("echo $FOO $HOME", &env).unwrap();
+
+        assert_eq!(output.stdout, "bar /nowhere\n");
+        assert!(std::env::var("FOO").is_err());
+        assert_ne!(std::env::var("HOME").ok().as_deref(), Some("/nowhere"));
+    }
+
+    #[test]
+    fn run_sh_in_runs_in_directory() {
+        let location = std::env::temp_dir().join("crazy-train-run-sh-in");
+        std::fs::create_dir_all(&location).unwrap();
+        std::fs::write(location.join("marker.txt"), "").unwrap();
+
+        let command = if cfg!(windows) { "dir /B" } else { "ls" };
+        let output = run_sh_in(command, &location).unwrap();
+        assert!(output.stdout.contains("marker.txt"));
+
+        let missing = location.join("missing");
+        assert!(matches!(
+            run_sh_in(command, &missing),
+            Err(crate::Error::WorkingDirectoryNotFound { path }) if path == missing
+        ));
+
+        let _ = std::fs::remove_dir_all(&location);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_records_duration() {
+        let output = run_sh("sleep 0.2").unwrap();
+        assert!(output.duration >= Duration::from_millis(200));
+        assert!(output.duration < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn limits_explain_only_their_signals() {
+        let memory = RLimits {
+            address_space: Some(1024),
+            ..Default::default()
+        };
+        let cpu = RLimits {
+            cpu_seconds: Some(2),
+            ..Default::default()
+        };
+
+        for signal in [libc::SIGSEGV, libc::SIGABRT, libc::SIGBUS] {
+            assert!(memory.explain(signal, Duration::ZERO));
+            assert!(!cpu.explain(signal, Duration::ZERO));
+        }
+        assert!(!memory.explain(libc::SIGKILL, Duration::from_secs(5)));
+        assert!(cpu.explain(libc::SIGXCPU, Duration::ZERO));
+        assert!(cpu.explain(libc::SIGKILL, Duration::from_secs(2)));
+        assert!(!cpu.explain(libc::SIGKILL, Duration::from_millis(500)));
+        assert!(!cpu.explain(libc::SIGTERM, Duration::from_secs(5)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn invocation_reflects_env_and_cwd() {
+        let cwd = std::env::temp_dir();
+        let options = ExecOptions {
+            env: HashMap::from([("FOO".to_string(), "bar".to_string())]),
+            cwd: Some(cwd.clone()),
+            ..Default::default()
+        };
+
+        let output = run_sh_with("echo $FOO && pwd", &options).unwrap();
+
+        assert_eq!(
+            output.invocation,
+            format!(
+                "cd '{}' && FOO='bar' sh -c 'echo $FOO && pwd'",
+                cwd.display()
+            )
+        );
+        assert!(output.stdout.starts_with("bar\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn invocation_quotes_command() {
+        let output = run_sh("echo 'it works'").unwrap();
+        assert_eq!(output.invocation, r"sh -c 'echo '\''it works'\'''");
+        assert_eq!(output.stdout, "it works\n");
+    }
+
+    #[test]
+    fn masked_outputs_compare_equal() {
+        let output = |stderr: &str| Output {
+            status_code: Some(1),
+            stdout: "processing".to_string(),
+            stderr: stderr.to_string(),
+            invocation: "sh -c 'run'".to_string(),
+            pid: None,
+            duration: Duration::from_millis(5),
+        };
+        let first = output("2024-01-01T10:00:00Z pid 1234: failed to open /tmp/a1b2/db");
+        let second = Output {
+            pid: Some(98),
+            duration: Duration::from_millis(12),
+            ..output("2024-03-17T22:41:09.531+02:00 pid 98: failed to open /tmp/zz9/db")
+        };
+        let masks = Output::default_masks();
+
+        assert_ne!(first, second);
+        assert_eq!(first.masked(&masks), second.masked(&masks));
+        assert_eq!(
+            first.masked(&masks).stderr,
+            "<masked> <masked>: failed to open <masked>"
+        );
+
+        let custom = [Regex::new(r"run-\d+").unwrap()];
+        assert_eq!(
+            output("run-42 failed").masked(&custom).stderr,
+            "<masked> failed"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reports_pid_of_spawned_command() {
+        let output = run_sh("echo $$").unwrap();
+        let pid = output.pid.expect("pid of the spawned shell");
+        assert_eq!(output.stdout.trim(), pid.to_string());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn combines_stdout_and_stderr() {
+        let command = "echo out; echo err >&2; echo done";
+        let output = run_sh_combined(command).unwrap();
+        assert!(output.stdout.contains("out"));
+        assert!(output.stdout.contains("err"));
+        assert_eq!(output.stdout, "out\nerr\ndone\n");
+        assert!(output.stderr.is_empty());
+        assert!(output.invocation.ends_with(" 2>&1"));
+
+        let separate = run_sh(command).unwrap();
+        assert_eq!(separate.stdout, "out\ndone\n");
+        assert_eq!(separate.stderr, "err\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_with_selected_shell() {
+        assert_eq!(Shell::default(), Shell::Sh);
+        for shell in [Shell::Sh, Shell::Bash] {
+            let output = run_with_shell("echo hello", shell).unwrap();
+            assert_eq!(output.stdout, "hello\n");
+        }
+
+        let command = r#"test -n "$BASH_VERSION" && echo bash"#;
+        let output = run_with_shell(command, Shell::Bash).unwrap();
+        assert_eq!(output.stdout, "bash\n");
+        assert_eq!(output.invocation, format!("bash -c {}", quote(command)));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn runs_with_selected_shell() {
+        assert_eq!(Shell::default(), Shell::Cmd);
+        let output = run_with_shell("echo hello", Shell::Cmd).unwrap();
+        assert_eq!(output.stdout.trim_end(), "hello");
+        assert_eq!(output.invocation, "cmd.exe /Q /C echo hello");
+    }
+
+    #[test]
+    fn assertion_helpers() {
+        let output = Output {
+            status_code: Some(0),
+            stdout: "created 3 users\n".to_string(),
+            stderr: "warning: deprecated flag\n".to_string(),
+            ..Default::default()
+        };
+        assert!(output.succeeded());
+        assert!(output.stdout_contains("3 users"));
+        assert!(!output.stdout_contains("warning"));
+        assert!(output.stderr_contains("deprecated"));
+        assert!(!output.stderr_contains("users"));
+        assert!(output.stdout_matches(&Regex::new(r"created \d+ users").unwrap()));
+        assert!(!output.stdout_matches(&Regex::new(r"^warning: \w+").unwrap()));
+        assert!(output.stderr_matches(&Regex::new(r"^warning: \w+").unwrap()));
+        assert!(!output.stderr_matches(&Regex::new(r"\d").unwrap()));
+
+        let failed = Output {
+            status_code: Some(2),
+            ..Default::default()
+        };
+        assert!(!failed.succeeded());
+
+        let empty = Output::default();
+        assert_eq!(empty.status_code, None);
+        assert!(!empty.succeeded());
+        assert!(empty.stdout_contains(""));
+        assert!(!empty.stdout_contains("done"));
+        assert!(!empty.stderr_contains("error"));
+        assert!(empty.stdout_matches(&Regex::new(r"^\s*$").unwrap()));
+        assert!(!empty.stderr_matches(&Regex::new(".").unwrap()));
+    }
 }
@@ -0,0 +1,201 @@
+//! This module defines the portable document a run plan is exported to.
+//!
+//! The [`ExportedPlan`] struct holds the fully materialized plan of a run: the resolved
+//! commands of every step together with the status codes they are expected to exit with and
+//! their check and test commands. It can be serialized to JSON or YAML, as selected by
+//! [`PlanFormat`], and executed by any tool that understands the schema below.
+//!
+//! ```yaml
+//! version: 1          # the schema version, see EXPORT_VERSION
+//! seed: 42            # the seed the plan was generated with
+//! steps:
+//!   - id: my_crate::CreateFile
+//!     commands:       # the resolved commands, executed in order
+//!       - touch /tmp/file
+//!     expected_status: # exit status codes mapped to success, skip or failure,
+//!       0: success    # codes missing from the map are failures
+//!     check: null     # an optional command that must exit with 0 after the commands
+//!     test: null      # an optional command that must exit with 0 after the check
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::Result, step::Outcome};
+
+/// The current version of the exported plan schema.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// The format of an exported plan document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanFormat {
+    Json,
+    Yaml,
+}
+
+impl PlanFormat {
+    /// Returns the format matching the extension of `path`: JSON for `.json` files, YAML
+    /// otherwise.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Self {
+        if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+        {
+            Self::Json
+        } else {
+            Self::Yaml
+        }
+    }
+}
+
+/// A fully materialized run plan, see the module documentation for its schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedPlan {
+    /// The schema version of the document.
+    pub version: u32,
+    /// The seed the plan was generated with.
+    pub seed: u64,
+    /// The steps of the plan, in execution order.
+    pub steps: Vec<ExportedStep>,
+}
+
+/// A single step of an [`ExportedPlan`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedStep {
+    /// The id of the step's plans.
+    pub id: String,
+    /// The resolved commands of the step, executed in order.
+    pub commands: Vec<String>,
+    /// The exit status codes of the commands mapped to their outcome. Codes missing from the
+    /// map are failures.
+    pub expected_status: BTreeMap<i32, Outcome>,
+    /// The command to run as a check after the commands.
+    pub check: Option<String>,
+    /// The command to run as a test after the check.
+    pub test: Option<String>,
+}
+
+impl ExportedPlan {
+    /// Serializes the plan to a document in the given format.
+    ///
+    /// # Errors
+    ///
+    /// when the plan could not be serialized
+    #[allow(clippy::result_large_err)]
+    pub fn to_document(&self, format: PlanFormat) -> Result<String> {
+        Ok(match format {
+            PlanFormat::Json => serde_json::to_string_pretty(self)?,
+            PlanFormat::Yaml => serde_yaml::to_string(self)?,
+        })
+    }
+
+    /// Parses a plan from a document in the given format.
+    ///
+    /// # Errors
+    ///
+    /// when the document does not match the schema
+    #[allow(clippy::result_large_err)]
+    pub fn from_document(document: &str, format: PlanFormat) -> Result<Self> {
+        Ok(match format {
+            PlanFormat::Json => serde_json::from_str(document)?,
+            PlanFormat::Yaml => serde_yaml::from_str(document)?,
+        })
+    }
+}
+
+/// A machine-readable dump of the plans of a run, see
+/// [`Runner::dump_plan_json`](crate::Runner::dump_plan_json). It is also the document a plan
+/// is saved to with [`Runner::save_plan`](crate::Runner::save_plan) and replayed from with
+/// [`Runner::from_plan`](crate::Runner::from_plan).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionPlan {
+    /// The seed of the randomizer the plans were generated with.
+    pub seed: u64,
+    /// The number of executed steps.
+    pub step_count: usize,
+    /// Whether the steps were shuffled, see
+    /// [`Runner::shuffle_steps`](crate::Runner::shuffle_steps).
+    #[serde(default)]
+    pub shuffled: bool,
+    /// The range the number of executed steps was drawn from, see
+    /// [`Runner::random_step_count`](crate::Runner::random_step_count).
+    #[serde(default)]
+    pub step_count_range: Option<(usize, usize)>,
+    /// The steps, in execution order.
+    pub steps: Vec<ExecutionPlanStep>,
+}
+
+/// A single step of an [`ExecutionPlan`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionPlanStep {
+    /// The id of the step's plans.
+    pub id: String,
+    /// The commands of the step's plans, in execution order.
+    pub commands: Vec<String>,
+    /// The stdin, environment variables and working directory of the step's plans, one entry
+    /// per command.
+    #[serde(default)]
+    pub inputs: Vec<PlanInputs>,
+    /// The state of the step, as returned by [`StepTrait::to_yaml`](crate::step::StepTrait::to_yaml).
+    pub state: serde_json::Value,
+}
+
+/// The inputs a plan of an [`ExecutionPlanStep`] runs its command with, besides the command
+/// itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanInputs {
+    /// Data written to the stdin of the command, see
+    /// [`Plan::with_stdin`](crate::step::Plan::with_stdin).
+    #[serde(default)]
+    pub stdin: Option<String>,
+    /// Environment variables the command runs with, see
+    /// [`Plan::with_env`](crate::step::Plan::with_env).
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// The directory the command runs in, see [`Plan::with_cwd`](crate::step::Plan::with_cwd).
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+}
+
+impl ExecutionPlan {
+    /// Serializes the plan to pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// when the plan could not be serialized
+    #[allow(clippy::result_large_err)]
+    pub fn to_json(&self) -> Result<String> {
+        self.to_document(PlanFormat::Json)
+    }
+
+    /// Serializes the plan to a document in the given format.
+    ///
+    /// # Errors
+    ///
+    /// when the plan could not be serialized
+    #[allow(clippy::result_large_err)]
+    pub fn to_document(&self, format: PlanFormat) -> Result<String> {
+        Ok(match format {
+            PlanFormat::Json => serde_json::to_string_pretty(self)?,
+            PlanFormat::Yaml => serde_yaml::to_string(self)?,
+        })
+    }
+
+    /// Parses a plan from a document in the given format.
+    ///
+    /// # Errors
+    ///
+    /// when the document is not a valid plan
+    #[allow(clippy::result_large_err)]
+    pub fn from_document(document: &str, format: PlanFormat) -> Result<Self> {
+        Ok(match format {
+            PlanFormat::Json => serde_json::from_str(document)?,
+            PlanFormat::Yaml => serde_yaml::from_str(document)?,
+        })
+    }
+}
@@ -0,0 +1,123 @@
+//! This module defines the [`FlagSpec`] struct, which declares the flags of a command together
+//! with the constraints between them, so [`Randomizer::valid_flags`](crate::Randomizer::valid_flags)
+//! can generate combinations that pass the early validation of the command.
+//!
+//! The supported constraints are listed in [`FlagRule`].
+
+/// A constraint between the flags of a [`FlagSpec`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlagRule {
+    /// At most one of the flags is used, e.g. `--json` and `--yaml`.
+    Exclusive(Vec<String>),
+    /// When the first flag is used, all the others are used as well, e.g. `--output`
+    /// requires `--format`. Requirements are transitive.
+    Requires(String, Vec<String>),
+    /// The two flags are never used together, e.g. `--quiet` and `--verbose`.
+    Conflicts(String, String),
+}
+
+/// Declares the flags of a command and the constraints between them.
+///
+/// # Example
+///
+/// ```rust
+/// use crazy_train::FlagSpec;
+/// let spec = FlagSpec::new(["--json", "--yaml", "--output", "--format", "--quiet", "--verbose"])
+///     .exclusive(["--json", "--yaml"])
+///     .requires("--output", ["--format"])
+///     .conflicts("--quiet", "--verbose");
+/// assert!(spec.is_satisfied_by(&["--json", "--quiet"]));
+/// assert!(!spec.is_satisfied_by(&["--output"]));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlagSpec {
+    /// The flags of the command, in the order they are rendered.
+    pub flags: Vec<String>,
+    /// The constraints between the flags.
+    pub rules: Vec<FlagRule>,
+}
+
+impl FlagSpec {
+    /// Creates a spec for the given flags, without constraints.
+    #[must_use]
+    pub fn new<I, S>(flags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            flags: flags.into_iter().map(Into::into).collect(),
+            rules: Vec::new(),
+        }
+    }
+
+    /// Adds a [`FlagRule::Exclusive`] group.
+    #[must_use]
+    pub fn exclusive<I, S>(mut self, group: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rules.push(FlagRule::Exclusive(
+            group.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Adds a [`FlagRule::Requires`] rule.
+    #[must_use]
+    pub fn requires<I, S>(mut self, flag: impl Into<String>, required: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rules.push(FlagRule::Requires(
+            flag.into(),
+            required.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Adds a [`FlagRule::Conflicts`] rule.
+    #[must_use]
+    pub fn conflicts(mut self, flag: impl Into<String>, other: impl Into<String>) -> Self {
+        self.rules
+            .push(FlagRule::Conflicts(flag.into(), other.into()));
+        self
+    }
+
+    /// Returns whether the given flags satisfy every rule of the spec.
+    #[must_use]
+    pub fn is_satisfied_by<S: AsRef<str>>(&self, flags: &[S]) -> bool {
+        let used = |flag: &str| flags.iter().any(|used| used.as_ref() == flag);
+
+        self.rules.iter().all(|rule| match rule {
+            FlagRule::Exclusive(group) => group.iter().filter(|flag| used(flag)).count() <= 1,
+            FlagRule::Requires(flag, required) => {
+                !used(flag) || required.iter().all(|required| used(required))
+            }
+            FlagRule::Conflicts(flag, other) => !(used(flag) && used(other)),
+        })
+    }
+
+    /// Returns the flag together with every flag it transitively requires.
+    pub(crate) fn closure(&self, flag: &str) -> Vec<String> {
+        let mut closure = vec![flag.to_string()];
+        let mut index = 0;
+        while index < closure.len() {
+            for rule in &self.rules {
+                if let FlagRule::Requires(flag, required) = rule {
+                    if *flag == closure[index] {
+                        for required in required {
+                            if !closure.contains(required) {
+                                closure.push(required.clone());
+                            }
+                        }
+                    }
+                }
+            }
+            index += 1;
+        }
+        closure
+    }
+}
@@ -5,29 +5,121 @@
 //! to build and customize a [`StringDef`] instance. The module also includes various utility
 //! functions to check for specific character types in a string.
 
-use std::cell::RefCell;
+use std::{cell::RefCell, ops::RangeInclusive};
 
-use rand::prelude::IteratorRandom;
+use rand::prelude::{IteratorRandom, SliceRandom};
 use rand::{Rng, RngCore};
 
-use crate::Randomizer;
+use crate::{Error, Randomizer, Result};
 
 const SYMBOLS: &str = r##"!\"#$%&'()*+,-./:;<=>?@[\]^_`{|}~"##;
 
+/// The emoji code points Unicode characters are drawn from by default.
+pub const DEFAULT_UNICODE_RANGE: RangeInclusive<u32> = 0x1F600..=0x1F64E;
+
+/// The maximum number of times a string is regenerated when it collides with a reserved word.
+pub const MAX_RESERVED_RETRIES: u32 = 100;
+
+/// Returns the characters an identifier may start with when `start` is set, or the characters
+/// it may continue with otherwise.
+fn identifier_chars(start: bool) -> impl Iterator<Item = char> {
+    ('a'..='z')
+        .chain('A'..='Z')
+        .chain(('0'..='9').filter(move |_| !start))
+        .chain(std::iter::once('_'))
+}
+
 /// Defines the criteria for generating random strings.
 #[derive(Clone)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct StringDef {
-    /// The desired length of the generated string.
+    /// The desired length of the random body of the generated string, without the
+    /// [`StringDef::prefix`] and the [`StringDef::suffix`].
     pub length: u32,
+    /// The minimum length of the generated string. When either bound is set, the length of each
+    /// string is drawn uniformly from the range, see [`StringDef::length_range`].
+    pub min_length: Option<u32>,
+    /// The maximum length of the generated string, see [`StringDef::min_length`].
+    pub max_length: Option<u32>,
+    /// The characters the generated string is drawn from. When set, it takes precedence over
+    /// the character classes: the `include_*` flags are ignored and only these characters are
+    /// used.
+    pub charset: Option<Vec<char>>,
+    /// Characters that never appear in the generated string, whatever the charset or the
+    /// character classes. Excluded characters are drawn again.
+    pub exclude: Vec<char>,
+    /// Whether the generated string contains at least one character of every enabled class,
+    /// e.g. a digit and a symbol when both numbers and symbols are enabled. The required
+    /// characters are mixed with randomly drawn ones and shuffled.
+    pub require_each_enabled_class: bool,
     /// Whether to include Unicode characters in the generated string.
     pub include_unicode: bool,
+    /// The code point ranges Unicode characters are drawn from: a range is picked uniformly,
+    /// then a code point within it. Code points that are not valid characters, like
+    /// surrogates, are skipped. Defaults to [`DEFAULT_UNICODE_RANGE`].
+    pub unicode_ranges: Vec<RangeInclusive<u32>>,
     /// Whether to include symbols in the generated string.
     pub include_symbol: bool,
     /// Whether to include capital letters in the generated string.
     pub include_capital_letters: bool,
     /// Whether to include numeric characters in the generated string.
     pub include_numbers: bool,
+    /// Words the generated string must never be equal to. A generated string that exactly
+    /// matches one of them is discarded and generated again, up to [`MAX_RESERVED_RETRIES`]
+    /// times.
+    pub reserved: Vec<String>,
+    /// Whether the generated string is a valid program identifier: the first character is an
+    /// ASCII letter or an underscore, the rest are ASCII letters, digits or underscores. When
+    /// set, it takes precedence over the charset, the character classes and
+    /// [`StringDef::require_each_enabled_class`].
+    pub identifier: bool,
+    /// A fixed text the generated string starts with, e.g. `--name=`. It is not counted in
+    /// [`StringDef::length`], which describes the random body only.
+    pub prefix: Option<String>,
+    /// A fixed text the generated string ends with, e.g. `.tmp`. It is not counted in
+    /// [`StringDef::length`], which describes the random body only.
+    pub suffix: Option<String>,
+}
+
+/// A class of characters a [`StringDef`] can generate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Lowercase,
+    Capital,
+    Number,
+    Symbol,
+    Unicode,
+    /// The characters of [`StringDef::charset`].
+    Custom,
+}
+
+/// A structured summary of the effective configuration of a [`StringDef`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringDefSummary {
+    /// The length of the generated strings.
+    pub length: u32,
+    /// The inclusive range the length of the generated strings is drawn from, `(length, length)`
+    /// when no bound is set.
+    pub length_range: (u32, u32),
+    /// The character classes generated strings are drawn from, only [`CharClass::Custom`] when a
+    /// charset is set.
+    pub classes: Vec<CharClass>,
+    /// The custom characters generated strings are drawn from, if any.
+    pub charset: Option<Vec<char>>,
+    /// The characters that never appear in generated strings.
+    pub exclude: Vec<char>,
+    /// Whether generated strings contain every enabled class.
+    pub require_each_enabled_class: bool,
+    /// The code point ranges Unicode characters are drawn from.
+    pub unicode_ranges: Vec<RangeInclusive<u32>>,
+    /// The words generated strings are never equal to.
+    pub reserved: Vec<String>,
+    /// Whether generated strings are valid program identifiers.
+    pub identifier: bool,
+    /// The fixed text generated strings start with, if any.
+    pub prefix: Option<String>,
+    /// The fixed text generated strings end with, if any.
+    pub suffix: Option<String>,
 }
 
 /// Provides a builder for constructing a [`StringDef`] instance.
@@ -35,10 +127,20 @@ impl Default for StringDef {
     fn default() -> Self {
         Self {
             length: 6,
+            min_length: None,
+            max_length: None,
+            charset: None,
+            exclude: Vec::new(),
+            require_each_enabled_class: false,
             include_unicode: false,
+            unicode_ranges: vec![DEFAULT_UNICODE_RANGE],
             include_symbol: false,
             include_capital_letters: false,
             include_numbers: false,
+            reserved: Vec::new(),
+            identifier: false,
+            prefix: None,
+            suffix: None,
         }
     }
 }
@@ -47,6 +149,9 @@ impl Default for StringDef {
 pub struct StringDefBuilder<'a> {
     pub string_def: StringDef,
     pub rng: &'a RefCell<dyn RngCore + Send>,
+    /// The randomizer the generated strings are recorded to, see
+    /// [`Randomizer::record_provenance`].
+    pub(crate) randomizer: Option<&'a Randomizer>,
 }
 
 impl StringDefBuilder<'_> {
@@ -57,6 +162,80 @@ impl StringDefBuilder<'_> {
         self
     }
 
+    /// Sets the minimum length of the generated string, making its length vary.
+    #[must_use]
+    pub const fn min_length(mut self, length: u32) -> Self {
+        self.string_def.min_length = Some(length);
+        self
+    }
+
+    /// Sets the maximum length of the generated string, making its length vary.
+    #[must_use]
+    pub const fn max_length(mut self, length: u32) -> Self {
+        self.string_def.max_length = Some(length);
+        self
+    }
+
+    /// Draws the generated string only from the given characters, ignoring the character class
+    /// flags.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let hex = randomizer.string(Default::default()).charset("0123456789abcdef".chars());
+    /// assert_eq!(hex.to_string(), "8a6208");
+    /// ```
+    #[must_use]
+    pub fn charset(mut self, chars: impl IntoIterator<Item = char>) -> Self {
+        self.string_def.charset = Some(chars.into_iter().collect());
+        self
+    }
+
+    /// Sets the characters that must never appear in the generated string, e.g. quotes or path
+    /// separators that would break the command under test.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let name = randomizer
+    ///     .string(Default::default())
+    ///     .include_symbol(true)
+    ///     .exclude(['/', '.', '\'', '"']);
+    /// assert_eq!(name.to_string(), "eq)a)=");
+    /// ```
+    #[must_use]
+    pub fn exclude(mut self, chars: impl IntoIterator<Item = char>) -> Self {
+        self.string_def.exclude = chars.into_iter().collect();
+        self
+    }
+
+    /// Specifies whether the generated string must contain at least one character of every
+    /// enabled class.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::{Randomizer, StringDef};
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let password = randomizer
+    ///     .string(Default::default())
+    ///     .include_numbers(true)
+    ///     .include_symbol(true)
+    ///     .require_each_enabled_class(true)
+    ///     .to_string();
+    /// assert!(StringDef::contains_numbers(&password));
+    /// assert!(StringDef::contains_symbols(&password));
+    /// ```
+    #[must_use]
+    pub const fn require_each_enabled_class(mut self, yes: bool) -> Self {
+        self.string_def.require_each_enabled_class = yes;
+        self
+    }
+
     /// Specifies whether to include Unicode characters.
     #[must_use]
     pub const fn include_unicode(mut self, yes: bool) -> Self {
@@ -64,6 +243,27 @@ impl StringDefBuilder<'_> {
         self
     }
 
+    /// Sets the code point ranges Unicode characters are drawn from, e.g. CJK ideographs,
+    /// combining marks or right-to-left scripts. Only used when Unicode characters are
+    /// included.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let hebrew = randomizer
+    ///     .string(Default::default())
+    ///     .include_unicode(true)
+    ///     .unicode_ranges([0x05D0..=0x05EA]);
+    /// assert_eq!(hebrew.to_string(), "מoqka");
+    /// ```
+    #[must_use]
+    pub fn unicode_ranges(mut self, ranges: impl IntoIterator<Item = RangeInclusive<u32>>) -> Self {
+        self.string_def.unicode_ranges = ranges.into_iter().collect();
+        self
+    }
+
     /// Specifies whether to include symbols.
     #[must_use]
     pub const fn include_symbol(mut self, yes: bool) -> Self {
@@ -84,18 +284,217 @@ impl StringDefBuilder<'_> {
         self.string_def.include_numbers = yes;
         self
     }
+
+    /// Sets the words the generated string must never be equal to.
+    #[must_use]
+    pub fn reserved<I, S>(mut self, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.string_def.reserved = words.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Specifies whether the generated string is a valid program identifier, e.g. a variable,
+    /// file or flag name that never starts with a digit.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let name = randomizer.string(Default::default()).length(8).identifier(true);
+    /// assert_eq!(name.to_string(), "hHpI2O_z");
+    /// ```
+    #[must_use]
+    pub const fn identifier(mut self, yes: bool) -> Self {
+        self.string_def.identifier = yes;
+        self
+    }
+
+    /// Sets a fixed text the generated string starts with. The length only counts the random
+    /// body after it.
+    #[must_use]
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.string_def.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets a fixed text the generated string ends with. The length only counts the random body
+    /// before it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let flag = randomizer.string(Default::default()).prefix("--name=").suffix(".tmp");
+    /// assert_eq!(flag.to_string(), "--name=noqkak.tmp");
+    /// ```
+    #[must_use]
+    pub fn suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.string_def.suffix = Some(suffix.into());
+        self
+    }
+
+    /// Returns the definition configured so far, e.g. to inspect its flags.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let builder = randomizer.string(Default::default()).include_numbers(true);
+    /// assert!(builder.def().include_numbers);
+    /// ```
+    #[must_use]
+    pub const fn def(&self) -> &StringDef {
+        &self.string_def
+    }
+
+    /// Consumes the builder and returns the configured definition, so it can be stored and
+    /// reused to generate many strings later.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let def = randomizer.string(Default::default()).length(4).build();
+    /// assert_eq!(randomizer.string(def.clone()).to_string(), "noqk");
+    /// assert_eq!(randomizer.string(def).to_string(), "aktw");
+    /// ```
+    #[must_use]
+    pub fn build(self) -> StringDef {
+        self.string_def
+    }
+
+    /// Generates a string based on the current configuration of the builder, like its
+    /// [`Display`](std::fmt::Display) implementation, but returns an error instead of panicking.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.string(Default::default()).try_generate().unwrap(), "noqkak");
+    /// assert!(randomizer.string(Default::default()).charset([]).try_generate().is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// See [`StringDef::try_generate`].
+    #[allow(clippy::result_large_err)]
+    pub fn try_generate(&self) -> Result<String> {
+        let draw = self.randomizer.map(Randomizer::draws);
+        let result = self.string_def.try_generate(&mut *self.rng.borrow_mut())?;
+        if let (Some(randomizer), Some(draw)) = (self.randomizer, draw) {
+            randomizer.record("string", draw, &result);
+        }
+        Ok(result)
+    }
 }
 
+/// Displays a string generated from the current configuration of the builder.
+///
+/// # Panics
+///
+/// When [`StringDef::try_generate`] fails, e.g. with [`Error::ReservedWordsExhausted`],
+/// [`Error::NoCharactersAvailable`] or [`Error::LengthTooShortForClasses`]. Use
+/// [`StringDefBuilder::try_generate`] to handle these cases as an error.
 impl std::fmt::Display for StringDefBuilder<'_> {
     /// Displays the generated string based on the current configuration of the builder.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut rng = self.rng.borrow_mut();
-        let result = self.string_def.generate(&mut *rng);
-        write!(f, "{result}")
+        match self.try_generate() {
+            Ok(result) => write!(f, "{result}"),
+            Err(err) => panic!("{err}"),
+        }
     }
 }
 
 impl StringDef {
+    /// Describes the effective configuration of this definition.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::{CharClass, StringDef};
+    /// let summary = StringDef::default().describe();
+    /// assert_eq!(summary.length, 6);
+    /// assert_eq!(summary.classes, vec![CharClass::Lowercase]);
+    /// ```
+    #[must_use]
+    pub fn describe(&self) -> StringDefSummary {
+        let classes = self.enabled_classes();
+
+        StringDefSummary {
+            length: self.length,
+            length_range: self.length_range(),
+            classes,
+            charset: self.charset.clone(),
+            exclude: self.exclude.clone(),
+            require_each_enabled_class: self.require_each_enabled_class,
+            unicode_ranges: self.unicode_ranges.clone(),
+            reserved: self.reserved.clone(),
+            identifier: self.identifier,
+            prefix: self.prefix.clone(),
+            suffix: self.suffix.clone(),
+        }
+    }
+
+    /// Returns the character classes generated strings are drawn from.
+    fn enabled_classes(&self) -> Vec<CharClass> {
+        if self.identifier {
+            return vec![CharClass::Lowercase, CharClass::Capital, CharClass::Number];
+        }
+        if self.charset.is_some() {
+            return vec![CharClass::Custom];
+        }
+
+        let mut classes = vec![CharClass::Lowercase];
+        if self.include_capital_letters {
+            classes.push(CharClass::Capital);
+        }
+        if self.include_numbers {
+            classes.push(CharClass::Number);
+        }
+        if self.include_symbol {
+            classes.push(CharClass::Symbol);
+        }
+        if self.include_unicode {
+            classes.push(CharClass::Unicode);
+        }
+        classes
+    }
+
+    /// Returns the inclusive range the length of generated strings is drawn from.
+    ///
+    /// Without [`StringDef::min_length`] and [`StringDef::max_length`] every string has the
+    /// fixed [`StringDef::length`]. A missing bound falls back to `length`, clamped so the range
+    /// is never empty, and inverted bounds are swapped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::StringDef;
+    /// assert_eq!(StringDef::default().length_range(), (6, 6));
+    /// let def = StringDef {
+    ///     max_length: Some(20),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(def.length_range(), (6, 20));
+    /// ```
+    #[must_use]
+    pub fn length_range(&self) -> (u32, u32) {
+        match (self.min_length, self.max_length) {
+            (None, None) => (self.length, self.length),
+            (Some(min), None) => (min, self.length.max(min)),
+            (None, Some(max)) => (self.length.min(max), max),
+            (Some(min), Some(max)) => (min.min(max), min.max(max)),
+        }
+    }
+
     /// Creates a [`StringDef`] from a given [`Randomizer`].
     pub fn from_randomizer(randomizer: &Randomizer) -> Self {
         Self {
@@ -104,6 +503,7 @@ impl StringDef {
             include_symbol: randomizer.bool(),
             include_capital_letters: randomizer.bool(),
             include_numbers: randomizer.bool(),
+            ..Self::default()
         }
     }
 
@@ -120,38 +520,217 @@ impl StringDef {
     /// assert_eq!(string_def.generate(&mut *rng), "twdayn");
     /// assert_eq!(string_def.generate(&mut *rng), "kdnfan");
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// When every attempt collides with a reserved word, or when no character is available.
+    /// Use [`StringDef::try_generate`] to handle these cases as an error.
     pub fn generate(&self, rng: &mut dyn RngCore) -> String {
+        match self.try_generate(rng) {
+            Ok(result) => result,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Generates a random string based on the current configuration, regenerating it whenever it
+    /// exactly matches one of the reserved words. The random body is wrapped between the
+    /// [`StringDef::prefix`] and the [`StringDef::suffix`], and the reserved words are compared
+    /// to the wrapped string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::{Randomizer, StringDef};
+    /// let string_def = StringDef {
+    ///     reserved: vec!["noqkak".to_string()],
+    ///     ..Default::default()
+    /// };
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let mut rng = randomizer.rng.borrow_mut();
+    /// assert_eq!(string_def.try_generate(&mut *rng).unwrap(), "twdayn");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// - When [`MAX_RESERVED_RETRIES`] consecutive attempts all collide with a reserved word.
+    /// - When no character can be generated, because the custom [`StringDef::charset`] is empty
+    ///   or every character of it, or of the enabled classes, is excluded. This is reported as
+    ///   [`Error::NoCharactersAvailable`]. With
+    ///   [`StringDef::require_each_enabled_class`], the error is returned as soon as any
+    ///   enabled class has no character left.
+    /// - When [`StringDef::require_each_enabled_class`] is set and the minimum length is shorter
+    ///   than the number of enabled classes, reported as [`Error::LengthTooShortForClasses`].
+    /// - When [`StringDef::identifier`] is set and every character allowed at a position of the
+    ///   identifier is excluded, reported as [`Error::NoCharactersAvailable`].
+    #[allow(clippy::result_large_err)]
+    pub fn try_generate(&self, rng: &mut dyn RngCore) -> Result<String> {
+        let classes = self.enabled_classes();
+        let available = |class: &CharClass| self.class_chars(*class).any(|ch| self.allows(ch));
+        if self.identifier {
+            let (_, max_length) = self.length_range();
+            let start_available =
+                max_length == 0 || identifier_chars(true).any(|ch| self.allows(ch));
+            let rest_available =
+                max_length < 2 || identifier_chars(false).any(|ch| self.allows(ch));
+            if !start_available || !rest_available {
+                return Err(Error::NoCharactersAvailable);
+            }
+        } else if self.require_each_enabled_class {
+            if !classes.iter().all(available) {
+                return Err(Error::NoCharactersAvailable);
+            }
+            let (min_length, _) = self.length_range();
+            if (min_length as usize) < classes.len() {
+                return Err(Error::LengthTooShortForClasses {
+                    length: min_length,
+                    classes: classes.len(),
+                });
+            }
+        } else if !classes.iter().any(available) {
+            return Err(Error::NoCharactersAvailable);
+        }
+
+        for _ in 0..=MAX_RESERVED_RETRIES {
+            let body = self.generate_candidate(rng);
+            let result = [self.prefix.as_deref(), Some(&body), self.suffix.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect::<String>();
+            if !self.reserved.iter().any(|word| word == &result) {
+                return Ok(result);
+            }
+        }
+
+        Err(Error::ReservedWordsExhausted {
+            attempts: MAX_RESERVED_RETRIES + 1,
+        })
+    }
+
+    fn generate_candidate(&self, rng: &mut dyn RngCore) -> String {
         let mut result = String::new();
-        let length: usize = self.length as usize;
+        let length = match self.length_range() {
+            (min, max) if min == max => min as usize,
+            (min, max) => rng.gen_range(min..=max) as usize,
+        };
 
-        while result.len() < length {
-            let choice: u8 = rng.gen_range(0..100);
+        if self.identifier {
+            return self.generate_identifier(length, rng);
+        }
 
-            if self.include_unicode && choice < 20 {
-                if let Some(unicode_char) = std::char::from_u32(rng.gen_range(0x1F600..0x1F64F)) {
-                    result.push(unicode_char);
-                } else {
-                    result.push('?');
+        if self.require_each_enabled_class {
+            for class in self.enabled_classes() {
+                // `try_generate` checked that every class has an allowed character.
+                if let Some(ch) = std::iter::repeat_with(|| self.draw(class, rng))
+                    .flatten()
+                    .find(|ch| self.allows(*ch))
+                {
+                    result.push(ch);
                 }
-            } else if self.include_symbol && choice < 40 {
-                if let Some(symbol) = SYMBOLS.chars().choose(rng) {
-                    result.push(symbol);
-                } else {
-                    result.push('#');
+            }
+        }
+
+        while result.len() < length {
+            let class = self.pick_class(rng);
+            if let Some(ch) = self.draw(class, rng).filter(|ch| self.allows(*ch)) {
+                result.push(ch);
+            }
+        }
+
+        if !self.require_each_enabled_class {
+            return result;
+        }
+        let mut chars: Vec<char> = result.chars().collect();
+        chars.shuffle(rng);
+        chars.into_iter().collect()
+    }
+
+    /// Generates an identifier of the given length, drawing the first character from
+    /// `[a-zA-Z_]` and the others from `[a-zA-Z0-9_]`. `try_generate` checked that both sets
+    /// have an allowed character.
+    fn generate_identifier(&self, length: usize, rng: &mut dyn RngCore) -> String {
+        let start: Vec<char> = identifier_chars(true)
+            .filter(|ch| self.allows(*ch))
+            .collect();
+        let rest: Vec<char> = identifier_chars(false)
+            .filter(|ch| self.allows(*ch))
+            .collect();
+        (0..length)
+            .filter_map(|index| {
+                let chars = if index == 0 { &start } else { &rest };
+                chars.choose(rng).copied()
+            })
+            .collect()
+    }
+
+    /// Returns whether `ch` may appear in generated strings.
+    fn allows(&self, ch: char) -> bool {
+        !self.exclude.contains(&ch)
+    }
+
+    /// Picks the class the next character is drawn from. Every enabled class is picked with a
+    /// positive probability, lowercase letters being the fallback.
+    fn pick_class(&self, rng: &mut dyn RngCore) -> CharClass {
+        if self.charset.is_some() {
+            return CharClass::Custom;
+        }
+
+        let choice: u8 = rng.gen_range(0..100);
+        if self.include_unicode && choice < 20 {
+            CharClass::Unicode
+        } else if self.include_symbol && choice < 40 {
+            CharClass::Symbol
+        } else if self.include_capital_letters && choice < 60 {
+            CharClass::Capital
+        } else if self.include_numbers && choice < 80 {
+            CharClass::Number
+        } else {
+            CharClass::Lowercase
+        }
+    }
+
+    /// Draws a character of the given class, `None` when the drawn code point is not a valid
+    /// character, e.g. a surrogate, in which case the caller draws again.
+    fn draw(&self, class: CharClass, rng: &mut dyn RngCore) -> Option<char> {
+        match class {
+            CharClass::Lowercase => Some(rng.gen_range(b'a'..=b'z') as char),
+            CharClass::Capital => Some(rng.gen_range(b'A'..=b'Z') as char),
+            CharClass::Number => Some(rng.gen_range(b'0'..=b'9') as char),
+            CharClass::Symbol => SYMBOLS.chars().choose(rng),
+            CharClass::Unicode => {
+                let range = match self.unicode_ranges.as_slice() {
+                    [range] => range,
+                    ranges => ranges.choose(rng)?,
+                };
+                if range.is_empty() {
+                    return None;
                 }
-            } else if self.include_capital_letters && choice < 60 {
-                let capital_letter = rng.gen_range(b'A'..=b'Z') as char;
-                result.push(capital_letter);
-            } else if self.include_numbers && choice < 80 {
-                let number = rng.gen_range(b'0'..=b'9') as char;
-                result.push(number);
-            } else {
-                let lowercase_letter = rng.gen_range(b'a'..=b'z') as char;
-                result.push(lowercase_letter);
+                std::char::from_u32(rng.gen_range(range.clone()))
+            }
+            CharClass::Custom => {
+                let charset = self
+                    .charset
+                    .as_ref()
+                    .filter(|charset| !charset.is_empty())?;
+                Some(charset[rng.gen_range(0..charset.len())])
             }
         }
+    }
 
-        result
+    /// Returns every character of the given class.
+    fn class_chars(&self, class: CharClass) -> Box<dyn Iterator<Item = char> + '_> {
+        match class {
+            CharClass::Lowercase => Box::new('a'..='z'),
+            CharClass::Capital => Box::new('A'..='Z'),
+            CharClass::Number => Box::new('0'..='9'),
+            CharClass::Symbol => Box::new(SYMBOLS.chars()),
+            CharClass::Unicode => Box::new(
+                self.unicode_ranges
+                    .iter()
+                    .flat_map(Clone::clone)
+                    .filter_map(std::char::from_u32),
+            ),
+            CharClass::Custom => Box::new(self.charset.iter().flatten().copied()),
+        }
     }
 
     /// Checks if a given string contains only lowercase letters.
@@ -184,8 +763,8 @@ impl StringDef {
     /// assert!(StringDef::contains_unicode("🙆Test"));
     /// ```
     #[must_use]
-    pub fn contains_unicode(s: &str) -> bool {
-        s.chars().any(|ch| !ch.is_ascii())
+    pub const fn contains_unicode(s: &str) -> bool {
+        !s.is_ascii()
     }
 
     /// Checks if a given string contains any symbols.
@@ -260,6 +839,109 @@ mod tests {
         assert!(StringDef::contains_capital_letters("Test1"));
     }
 
+    #[test]
+    fn string_def_describe() {
+        let randomizer = Randomizer::with_seed(42);
+        let builder = randomizer
+            .string(StringDef::default())
+            .length(12)
+            .include_numbers(true)
+            .include_unicode(true)
+            .reserved(["admin"]);
+
+        assert_eq!(
+            builder.string_def.describe(),
+            StringDefSummary {
+                length: 12,
+                length_range: (12, 12),
+                classes: vec![CharClass::Lowercase, CharClass::Number, CharClass::Unicode],
+                charset: None,
+                exclude: Vec::new(),
+                require_each_enabled_class: false,
+                unicode_ranges: vec![DEFAULT_UNICODE_RANGE],
+                reserved: vec!["admin".to_string()],
+                identifier: false,
+                prefix: None,
+                suffix: None,
+            }
+        );
+    }
+
+    #[test]
+    fn identifier_never_starts_with_a_digit() {
+        let string_def = StringDef {
+            min_length: Some(1),
+            max_length: Some(12),
+            include_numbers: true,
+            include_symbol: true,
+            identifier: true,
+            ..Default::default()
+        };
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        for _ in 0..500 {
+            let result = string_def.generate(&mut rand);
+            let mut chars = result.chars();
+            let first = chars.next().unwrap();
+            assert!(first.is_ascii_alphabetic() || first == '_', "{result}");
+            assert!(
+                chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_'),
+                "{result}"
+            );
+        }
+
+        let excluded = StringDef {
+            exclude: identifier_chars(true).collect(),
+            identifier: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            excluded.try_generate(&mut rand),
+            Err(Error::NoCharactersAvailable)
+        ));
+    }
+
+    #[test]
+    fn builder_exposes_the_configured_def() {
+        let randomizer = Randomizer::with_seed(42);
+        let builder = randomizer
+            .string(StringDef::default())
+            .min_length(3)
+            .include_capital_letters(true)
+            .exclude(['a']);
+        assert_eq!(builder.def().min_length, Some(3));
+        assert!(builder.def().include_capital_letters);
+
+        let def = builder.build();
+        assert_eq!(def.exclude, vec!['a']);
+        assert_eq!(def.describe().length_range, (3, 6));
+
+        let mut first = StdRng::seed_from_u64(42);
+        let mut second = StdRng::seed_from_u64(42);
+        assert_eq!(
+            randomizer.string(def.clone()).def().generate(&mut first),
+            def.generate(&mut second)
+        );
+    }
+
+    #[test]
+    fn prefix_and_suffix_wrap_the_body() {
+        let string_def = StringDef {
+            prefix: Some("pre".to_string()),
+            suffix: Some(".tmp".to_string()),
+            ..Default::default()
+        };
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        assert_eq!(string_def.generate(&mut rand), "prenoqkak.tmp");
+        assert_eq!(string_def.generate(&mut rand), "pretwdayn.tmp");
+
+        let reserved = StringDef {
+            reserved: vec!["prenoqkak.tmp".to_string()],
+            ..string_def
+        };
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        assert_eq!(reserved.generate(&mut rand), "pretwdayn.tmp");
+    }
+
     #[test]
     fn string_def_default() {
         let string_def = StringDef::default();
@@ -274,10 +956,7 @@ mod tests {
     fn string_def_with_length() {
         let string_def = StringDef {
             length: 10,
-            include_unicode: false,
-            include_symbol: false,
-            include_capital_letters: false,
-            include_numbers: false,
+            ..Default::default()
         };
         let mut rand = Box::new(StdRng::seed_from_u64(42));
         assert_eq!(string_def.generate(&mut rand), "noqkaktwda");
@@ -285,6 +964,179 @@ mod tests {
         assert_eq!(string_def.generate(&mut rand), "vmnbjlufkr");
     }
 
+    #[test]
+    fn string_def_with_length_range() {
+        let randomizer = Randomizer::with_seed(42);
+        let builder = randomizer
+            .string(StringDef::default())
+            .min_length(2)
+            .max_length(12);
+
+        let lengths: Vec<usize> = (0..8)
+            .map(|_| builder.to_string().chars().count())
+            .collect();
+        assert_eq!(lengths, vec![3, 6, 11, 7, 9, 3, 2, 2]);
+        assert_eq!(builder.string_def.describe().length_range, (2, 12));
+    }
+
+    #[test]
+    fn string_def_without_length_range_is_unchanged() {
+        let string_def = StringDef {
+            min_length: None,
+            max_length: None,
+            ..Default::default()
+        };
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        assert_eq!(string_def.generate(&mut rand), "noqkak");
+        assert_eq!(string_def.generate(&mut rand), "twdayn");
+
+        let fixed = StringDef {
+            min_length: Some(4),
+            max_length: Some(4),
+            ..Default::default()
+        };
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        assert_eq!(fixed.generate(&mut rand), "noqk");
+    }
+
+    #[test]
+    fn string_def_with_charset() {
+        let string_def = StringDef {
+            length: 32,
+            charset: Some(('0'..='9').chain('a'..='f').collect()),
+            include_unicode: true,
+            include_symbol: true,
+            include_capital_letters: true,
+            ..Default::default()
+        };
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        for _ in 0..20 {
+            let result = string_def.generate(&mut rand);
+            assert_eq!(result.len(), 32);
+            assert!(result
+                .chars()
+                .all(|ch| ch.is_ascii_hexdigit() && !ch.is_uppercase()));
+        }
+        assert_eq!(string_def.describe().classes, vec![CharClass::Custom]);
+
+        let empty = StringDef {
+            charset: Some(Vec::new()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            empty.try_generate(&mut rand),
+            Err(Error::NoCharactersAvailable)
+        ));
+    }
+
+    #[test]
+    fn string_def_excludes_chars() {
+        let string_def = StringDef {
+            length: 20,
+            include_symbol: true,
+            include_numbers: true,
+            exclude: vec!['/', '.'],
+            ..Default::default()
+        };
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        let results: Vec<String> = (0..200).map(|_| string_def.generate(&mut rand)).collect();
+        assert!(results.iter().all(|result| !result.contains(['/', '.'])));
+        assert!(results
+            .iter()
+            .any(|result| StringDef::contains_symbols(result)));
+    }
+
+    #[test]
+    fn string_def_excluding_whole_class_falls_back_to_other_classes() {
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        let no_lowercase = StringDef {
+            include_numbers: true,
+            exclude: ('a'..='z').collect(),
+            ..Default::default()
+        };
+        let result = no_lowercase.generate(&mut rand);
+        assert!(result.chars().all(|ch| ch.is_ascii_digit()));
+
+        let nothing_left = StringDef {
+            exclude: ('a'..='z').collect(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            nothing_left.try_generate(&mut rand),
+            Err(Error::NoCharactersAvailable)
+        ));
+
+        let hex_excluded = StringDef {
+            charset: Some(vec!['a', 'b']),
+            exclude: vec!['a', 'b'],
+            ..Default::default()
+        };
+        assert!(matches!(
+            hex_excluded.try_generate(&mut rand),
+            Err(Error::NoCharactersAvailable)
+        ));
+    }
+
+    #[test]
+    fn string_def_requires_each_enabled_class() {
+        let string_def = StringDef {
+            length: 4,
+            include_numbers: true,
+            include_symbol: true,
+            require_each_enabled_class: true,
+            ..Default::default()
+        };
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        for _ in 0..100 {
+            let result = string_def.generate(&mut rand);
+            assert_eq!(result.len(), 4);
+            assert!(StringDef::contains_numbers(&result));
+            assert!(StringDef::contains_symbols(&result));
+            assert!(result.chars().any(|ch| ch.is_ascii_lowercase()));
+        }
+
+        let too_short = StringDef {
+            length: 2,
+            ..string_def
+        };
+        assert!(matches!(
+            too_short.try_generate(&mut rand),
+            Err(Error::LengthTooShortForClasses {
+                length: 2,
+                classes: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn string_def_with_unicode_ranges() {
+        let cjk = StringDef {
+            length: 12,
+            include_unicode: true,
+            unicode_ranges: vec![0x4E00..=0x9FFF],
+            ..Default::default()
+        };
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        let result = cjk.generate(&mut rand);
+        assert!(StringDef::contains_unicode(&result));
+        assert!(std::str::from_utf8(result.as_bytes()).is_ok());
+        assert!(result
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ('\u{4E00}'..='\u{9FFF}').contains(&ch)));
+
+        let with_surrogates = StringDef {
+            length: 40,
+            include_unicode: true,
+            unicode_ranges: vec![0xD7FE..=0xD801],
+            ..Default::default()
+        };
+        let result = with_surrogates.generate(&mut rand);
+        assert!(result.contains(['\u{D7FE}', '\u{D7FF}']));
+        assert!(result
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ch == '\u{D7FE}' || ch == '\u{D7FF}'));
+    }
+
     #[test]
     fn string_def_include_unicode() {
         let string_def = StringDef {
@@ -293,6 +1145,7 @@ mod tests {
             include_symbol: false,
             include_capital_letters: false,
             include_numbers: false,
+            ..Default::default()
         };
         let mut rand = Box::new(StdRng::seed_from_u64(42));
         assert_eq!(string_def.generate(&mut rand), "😩oq");
@@ -308,6 +1161,7 @@ mod tests {
             include_symbol: true,
             include_capital_letters: false,
             include_numbers: false,
+            ..Default::default()
         };
         let mut rand = Box::new(StdRng::seed_from_u64(42));
         assert_eq!(string_def.generate(&mut rand), "\"eq)a)");
@@ -323,6 +1177,7 @@ mod tests {
             include_symbol: false,
             include_capital_letters: true,
             include_numbers: false,
+            ..Default::default()
         };
         let mut rand = Box::new(StdRng::seed_from_u64(42));
         assert_eq!(string_def.generate(&mut rand), "NOqkak");
@@ -338,10 +1193,60 @@ mod tests {
             include_symbol: false,
             include_capital_letters: false,
             include_numbers: true,
+            ..Default::default()
         };
         let mut rand = Box::new(StdRng::seed_from_u64(42));
         assert_eq!(string_def.generate(&mut rand), "55qka4");
         assert_eq!(string_def.generate(&mut rand), "7810y5");
         assert_eq!(string_def.generate(&mut rand), "k1nf05");
     }
+
+    #[test]
+    fn string_def_never_generates_reserved_words() {
+        let reserved: Vec<String> = ('a'..='y').map(String::from).collect();
+        let string_def = StringDef {
+            length: 1,
+            reserved: reserved.clone(),
+            ..Default::default()
+        };
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        for _ in 0..50 {
+            let result = string_def.generate(&mut rand);
+            assert!(!reserved.contains(&result));
+            assert_eq!(result, "z");
+        }
+    }
+
+    #[test]
+    fn string_def_reserved_words_exhausted() {
+        let string_def = StringDef {
+            length: 1,
+            reserved: ('a'..='z').map(String::from).collect(),
+            ..Default::default()
+        };
+        let mut rand = Box::new(StdRng::seed_from_u64(42));
+        assert!(matches!(
+            string_def.try_generate(&mut rand),
+            Err(Error::ReservedWordsExhausted { attempts }) if attempts == MAX_RESERVED_RETRIES + 1
+        ));
+    }
+
+    #[test]
+    fn builder_try_generate_records_provenance() {
+        let randomizer = Randomizer::with_seed(42).record_provenance(true);
+        let result = randomizer
+            .string(StringDef::default())
+            .try_generate()
+            .unwrap();
+        assert!(randomizer
+            .string(StringDef::default())
+            .charset([])
+            .try_generate()
+            .is_err());
+
+        let provenance = randomizer.take_provenance();
+        assert_eq!(provenance.len(), 1);
+        assert_eq!(provenance[0].kind, "string");
+        assert_eq!(provenance[0].value, result);
+    }
 }
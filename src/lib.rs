@@ -26,12 +26,31 @@
 
 mod errors;
 pub mod executer;
+mod export;
+mod flags;
 mod generator;
+mod pattern;
 mod randomizer;
+mod report;
 mod runner;
 pub mod step;
+#[cfg(all(target_os = "linux", feature = "trace-paths"))]
+mod trace;
 
 pub use errors::{Error, Result};
-pub use generator::StringDef;
-pub use randomizer::Randomizer;
-pub use runner::{new, Runner};
+pub use export::{
+    ExecutionPlan, ExecutionPlanStep, ExportedPlan, ExportedStep, PlanFormat, PlanInputs,
+    EXPORT_VERSION,
+};
+pub use flags::{FlagRule, FlagSpec};
+pub use generator::{CharClass, StringDef, StringDefSummary, DEFAULT_UNICODE_RANGE};
+pub use pattern::MAX_PATTERN_REPETITION;
+pub use randomizer::{
+    DelimitedListEdgeCases, FuzzedInput, PathListEdgeCases, Provenance, Randomizer, SeedDerivation,
+    SharedRandomizer,
+};
+pub use report::{
+    Divergence, DiversityMetrics, PhaseDurations, Reproducer, RunReport, StepReport, StepStats,
+    StepStatus, SweepResult,
+};
+pub use runner::{new, Runner, RunnerObserver, DEFAULT_MAX_COMMAND_LENGTH};
@@ -0,0 +1,269 @@
+//! This module generates random strings matching a regular expression, see
+//! [`Randomizer::from_regex`](crate::Randomizer::from_regex).
+//!
+//! The pattern is parsed with `regex_syntax` into its high-level intermediate representation,
+//! which is then walked with the seeded rng. Only the subset of the regular expression syntax
+//! that describes a bounded set of strings is supported; anything else is rejected with
+//! [`Error::InvalidPattern`] instead of generating strings that may not match.
+
+use rand::{Rng, RngCore};
+use regex_syntax::hir::{Class, Hir, HirKind, Look};
+
+use crate::{Error, Result};
+
+/// The largest repetition count a pattern may ask for, so a pattern like `a{0,1000000000}`
+/// fails instead of exhausting the memory. Nested repetitions count as the product of their
+/// maxima, so `(a{100}){100}` is rejected as well.
+pub const MAX_PATTERN_REPETITION: u32 = 1_000;
+
+/// The characters classes are drawn from when they contain any: printable ASCII.
+const PRINTABLE: (char, char) = (' ', '~');
+
+/// Generates a random string matching `pattern`.
+#[allow(clippy::result_large_err)]
+pub fn generate(pattern: &str, rng: &mut dyn RngCore) -> Result<String> {
+    let hir = regex_syntax::Parser::new()
+        .parse(pattern)
+        .map_err(|err| invalid(pattern, describe(&err)))?;
+    validate(&hir, Position::WHOLE, 1).map_err(|reason| invalid(pattern, reason.to_string()))?;
+
+    let mut output = String::new();
+    generate_hir(&hir, rng, &mut output);
+    Ok(output)
+}
+
+fn invalid(pattern: &str, reason: String) -> Error {
+    Error::InvalidPattern {
+        pattern: pattern.to_string(),
+        reason,
+    }
+}
+
+fn describe(err: &regex_syntax::Error) -> String {
+    match err {
+        regex_syntax::Error::Parse(err) => {
+            format!("{} at offset {}", err.kind(), err.span().start.offset)
+        }
+        regex_syntax::Error::Translate(err) => {
+            format!("{} at offset {}", err.kind(), err.span().start.offset)
+        }
+        err => err.to_string(),
+    }
+}
+
+/// Where a node is in the generated string: whether nothing can be generated before it and
+/// whether nothing can be generated after it.
+#[derive(Clone, Copy)]
+struct Position {
+    start: bool,
+    end: bool,
+}
+
+impl Position {
+    const WHOLE: Self = Self {
+        start: true,
+        end: true,
+    };
+    const INNER: Self = Self {
+        start: false,
+        end: false,
+    };
+}
+
+/// Checks that `hir` describes a bounded set of strings, with anchors only at the start or the
+/// end of the pattern, and that the repetitions around it, `repeat` times in total, stay within
+/// [`MAX_PATTERN_REPETITION`].
+fn validate(hir: &Hir, position: Position, repeat: u32) -> std::result::Result<(), &'static str> {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Literal(_) => Ok(()),
+        HirKind::Class(class) => {
+            if class_ranges(class).is_empty() {
+                Err("empty character class")
+            } else {
+                Ok(())
+            }
+        }
+        HirKind::Look(Look::Start | Look::StartLF | Look::StartCRLF) if position.start => Ok(()),
+        HirKind::Look(Look::End | Look::EndLF | Look::EndCRLF) if position.end => Ok(()),
+        HirKind::Look(Look::Start | Look::StartLF | Look::StartCRLF) => {
+            Err("start anchor in the middle of the pattern")
+        }
+        HirKind::Look(Look::End | Look::EndLF | Look::EndCRLF) => {
+            Err("end anchor in the middle of the pattern")
+        }
+        HirKind::Look(_) => Err("unsupported word boundary"),
+        HirKind::Repetition(repetition) => {
+            let Some(max) = repetition.max else {
+                return Err("unbounded repetition");
+            };
+            let repeat = repeat.saturating_mul(max);
+            if repeat > MAX_PATTERN_REPETITION {
+                return Err("repetition exceeds the maximum count");
+            }
+            // A repeated anchor would be generated after the first repetition.
+            let position = if max > 1 { Position::INNER } else { position };
+            validate(&repetition.sub, position, repeat)
+        }
+        HirKind::Capture(capture) => validate(&capture.sub, position, repeat),
+        HirKind::Concat(hirs) => {
+            let is_look = |hir: &Hir| matches!(hir.kind(), HirKind::Look(_));
+            for (index, hir) in hirs.iter().enumerate() {
+                let inner = Position {
+                    start: position.start && hirs[..index].iter().all(is_look),
+                    end: position.end && hirs[index + 1..].iter().all(is_look),
+                };
+                validate(hir, inner, repeat)?;
+            }
+            Ok(())
+        }
+        HirKind::Alternation(hirs) => hirs
+            .iter()
+            .try_for_each(|hir| validate(hir, position, repeat)),
+    }
+}
+
+/// Returns the inclusive ranges a class is drawn from: its printable ASCII characters when it
+/// has any, so `.` and negated classes generate readable strings, and all of it otherwise.
+fn class_ranges(class: &Class) -> Vec<(char, char)> {
+    let ranges: Vec<(char, char)> = match class {
+        Class::Unicode(class) => class
+            .ranges()
+            .iter()
+            .map(|range| (range.start(), range.end()))
+            .collect(),
+        Class::Bytes(class) => class
+            .ranges()
+            .iter()
+            .map(|range| (char::from(range.start()), char::from(range.end())))
+            .collect(),
+    };
+    let printable: Vec<(char, char)> = ranges
+        .iter()
+        .filter_map(|&(start, end)| {
+            let start = start.max(PRINTABLE.0);
+            let end = end.min(PRINTABLE.1);
+            (start <= end).then_some((start, end))
+        })
+        .collect();
+    if printable.is_empty() {
+        ranges
+    } else {
+        printable
+    }
+}
+
+fn generate_hir(hir: &Hir, rng: &mut dyn RngCore, output: &mut String) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => output.push_str(&String::from_utf8_lossy(&literal.0)),
+        HirKind::Class(class) => {
+            let ranges = class_ranges(class);
+            let size = |&(start, end): &(char, char)| u32::from(end) - u32::from(start) + 1;
+            let mut index = rng.gen_range(0..ranges.iter().map(size).sum::<u32>());
+            for range in &ranges {
+                if index < size(range) {
+                    let code = u32::from(range.0) + index;
+                    output.push(char::from_u32(code).unwrap_or(range.0));
+                    return;
+                }
+                index -= size(range);
+            }
+        }
+        HirKind::Repetition(repetition) => {
+            let max = repetition.max.unwrap_or(repetition.min);
+            for _ in 0..rng.gen_range(repetition.min..=max) {
+                generate_hir(&repetition.sub, rng, output);
+            }
+        }
+        HirKind::Capture(capture) => generate_hir(&capture.sub, rng, output),
+        HirKind::Concat(hirs) => {
+            for hir in hirs {
+                generate_hir(hir, rng, output);
+            }
+        }
+        HirKind::Alternation(hirs) => {
+            generate_hir(&hirs[rng.gen_range(0..hirs.len())], rng, output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::Randomizer;
+
+    #[allow(clippy::result_large_err)]
+    fn generate(pattern: &str) -> Result<String> {
+        super::generate(pattern, &mut rand::rngs::mock::StepRng::new(0, 1))
+    }
+
+    #[test]
+    fn rejects_invalid_and_unbounded_patterns() {
+        for pattern in [
+            "a*",
+            "a+",
+            "a{2,}",
+            "a{0,1001}",
+            "a{3,2}",
+            "(ab",
+            "ab)",
+            "[ab",
+            "[z-a]",
+            "*a",
+            r"\b",
+        ] {
+            assert!(
+                matches!(generate(pattern), Err(Error::InvalidPattern { .. })),
+                "{pattern} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn bounds_the_total_of_nested_repetitions() {
+        assert!(generate("(a{10}){100}").is_ok());
+        for pattern in ["((a{1000}){1000}){100}", "(a{10}){101}", "((a{2}){2}){251}"] {
+            assert!(
+                matches!(generate(pattern), Err(Error::InvalidPattern { .. })),
+                "{pattern} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_anchors_in_the_middle() {
+        for pattern in ["a^b", "a$b", "(^a){2}", "(a$)b"] {
+            assert!(
+                matches!(generate(pattern), Err(Error::InvalidPattern { .. })),
+                "{pattern} should be rejected"
+            );
+        }
+        for pattern in ["^a$", "^^a$$", "^a|^b", "(^a)?b", "(?:a$)"] {
+            assert!(generate(pattern).is_ok(), "{pattern} should be accepted");
+        }
+    }
+
+    #[test]
+    fn generates_matching_strings() {
+        for pattern in [
+            r"^[a-z]{3}-\d{4}$",
+            r"^(?:get|set|del) [A-Z_]{1,8}( -v)?$",
+            r"^\w{2}\.[^a-z\s]{3}$",
+            r"^(?P<user>[a-z]{1,5})@example\.(com|org)$",
+            r"^.{5}$",
+        ] {
+            let regex = regex::Regex::new(pattern).unwrap();
+            for seed in 0..50 {
+                let randomizer = Randomizer::with_seed(seed);
+                let value = randomizer.from_regex(pattern).unwrap();
+                assert!(regex.is_match(&value), "{value:?} does not match {pattern}");
+                assert!(value.chars().all(|ch| (' '..='~').contains(&ch)));
+                assert_eq!(
+                    value,
+                    Randomizer::with_seed(seed).from_regex(pattern).unwrap()
+                );
+            }
+        }
+    }
+}
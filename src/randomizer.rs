@@ -1,16 +1,325 @@
 //! This module provides a Randomizer struct that manages random number generation
 //! with support for seeded and non-seeded generation. It allows for generating
-//! random numbers, booleans, strings, paths, and shuffling items.
+//! random numbers, booleans, strings, paths, and shuffling items. Generated values can
+//! optionally be recorded together with the random draw that produced them, see
+//! [`Provenance`].
 //!
 
-use crate::generator::{StringDef, StringDefBuilder};
-use rand::{rngs::StdRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
-use std::{cell::RefCell, path::PathBuf};
+use crate::{
+    executer,
+    flags::FlagSpec,
+    generator::{StringDef, StringDefBuilder},
+    pattern,
+    step::Plan,
+    Error, Result,
+};
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+use unicode_normalization::UnicodeNormalization;
+
+/// Opening and closing characters used by [`Randomizer::balanced_brackets`] and
+/// [`Randomizer::unbalanced_brackets`].
+const BRACKET_PAIRS: [(char, char); 6] = [
+    ('(', ')'),
+    ('[', ']'),
+    ('{', '}'),
+    ('<', '>'),
+    ('"', '"'),
+    ('\'', '\''),
+];
+
+/// Malformed byte sequences injected by [`Randomizer::bad_utf8_bytes`]. The UTF-8 byte order mark
+/// is valid on its own and is kept last so it can be excluded when a guaranteed invalid sequence
+/// is needed.
+const BAD_UTF8_SEQUENCES: [&[u8]; 11] = [
+    // Overlong encodings of `/` in two, three and four bytes.
+    &[0xC0, 0xAF],
+    &[0xE0, 0x80, 0xAF],
+    &[0xF0, 0x80, 0x80, 0xAF],
+    // Overlong encoding of NUL.
+    &[0xC0, 0x80],
+    // UTF-16 big-endian and little-endian byte order marks.
+    &[0xFE, 0xFF],
+    &[0xFF, 0xFE],
+    // Multibyte sequences truncated before their last byte (`€` and `😀`).
+    &[0xE2, 0x82],
+    &[0xF0, 0x9F, 0x98],
+    // A continuation byte without a leading byte.
+    &[0x80],
+    // An encoded UTF-16 surrogate half.
+    &[0xED, 0xA0, 0x80],
+    // A UTF-8 byte order mark in an unexpected position.
+    &[0xEF, 0xBB, 0xBF],
+];
+
+/// A generated value together with the random decision that produced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// The kind of the generated value, e.g. `string`, `number` or `path`.
+    pub kind: &'static str,
+    /// The generated value.
+    pub value: String,
+    /// The index of the first RNG draw made to generate the value, counted from the seed.
+    pub draw: u64,
+}
+
+/// An RNG counting the draws made from it.
+struct CountingRng {
+    inner: StdRng,
+    draws: Arc<AtomicU64>,
+}
+
+impl CountingRng {
+    fn new(seed: u64, draws: &Arc<AtomicU64>) -> Self {
+        draws.store(0, Ordering::Relaxed);
+        Self {
+            inner: StdRng::seed_from_u64(seed),
+            draws: Arc::clone(draws),
+        }
+    }
+
+    fn count(&self) {
+        self.draws.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl RngCore for CountingRng {
+    fn next_u32(&mut self) -> u32 {
+        self.count();
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.count();
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.count();
+        self.inner.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand::Error> {
+        self.count();
+        self.inner.try_fill_bytes(dest)
+    }
+}
+
+/// The characters of filenames generated by [`Randomizer::portable_filename`]. A `.` is
+/// only used inside a name.
+const PORTABLE_FILENAME_CHARS: &[u8] =
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-_";
+
+/// Device names reserved on Windows, regardless of case and extension.
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// The separator of path lists generated by [`Randomizer::path_list`].
+const PATH_LIST_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
+
+/// The edge cases [`Randomizer::path_list`] may include. All of them are enabled by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathListEdgeCases {
+    /// Empty segments, e.g. `a::b`.
+    pub empty_segments: bool,
+    /// A trailing separator, e.g. `a:b:`.
+    pub trailing_separator: bool,
+    /// Entries repeating a previous entry, e.g. `a:b:a`.
+    pub duplicates: bool,
+}
+
+impl Default for PathListEdgeCases {
+    fn default() -> Self {
+        Self {
+            empty_segments: true,
+            trailing_separator: true,
+            duplicates: true,
+        }
+    }
+}
+
+/// The edge cases [`Randomizer::delimited_list`] may include. All of them are enabled by
+/// default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelimitedListEdgeCases {
+    /// Empty fields between two separators, e.g. `a\0\0b`.
+    pub empty_fields: bool,
+    /// A leading separator, e.g. `\0a\0b`.
+    pub leading_separator: bool,
+    /// A trailing separator, e.g. `a\0b\0`.
+    pub trailing_separator: bool,
+}
+
+impl Default for DelimitedListEdgeCases {
+    fn default() -> Self {
+        Self {
+            empty_fields: true,
+            leading_separator: true,
+            trailing_separator: true,
+        }
+    }
+}
+
+/// How [`Randomizer::child`] derives the seed of a named child from the parent seed.
+///
+/// Every strategy is deterministic, so the same parent seed and name always give the same
+/// child seed, but the strategies differ in how likely two `(seed, name)` pairs collide:
+///
+/// - [`SeedDerivation::SplitMix64`] hashes the name and mixes it into the seed with the
+///   `SplitMix64` finalizer. Collisions are as unlikely as with a 64-bit hash.
+/// - [`SeedDerivation::Hash`] hashes the seed and the name together with FNV-1a. It is as
+///   collision resistant, but nearby seeds give less scattered child seeds.
+/// - [`SeedDerivation::Xor`] xors the seed with the hash of the name. It is cheap and
+///   reversible, but `(a, x)` and `(b, y)` collide whenever `a ^ b == hash(x) ^ hash(y)`, and
+///   swapping seeds between runs of related names can reproduce the same children.
+/// - [`SeedDerivation::Custom`] calls the given function with the parent seed and the name.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum SeedDerivation {
+    #[default]
+    SplitMix64,
+    Hash,
+    Xor,
+    Custom(fn(u64, &str) -> u64),
+}
+
+/// Arguments and stdin generated together for the same command, see
+/// [`Randomizer::argv_and_stdin`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzedInput {
+    /// The arguments of the command, unquoted.
+    pub args: Vec<String>,
+    /// The data written to the stdin of the command.
+    pub stdin: String,
+}
+
+impl FuzzedInput {
+    /// Creates a plan running `program` with the arguments, each quoted for the shell, and the
+    /// stdin of the input. Both are shown by [`crate::Runner::dump_plan`].
+    #[must_use]
+    pub fn into_plan<T>(self, program: &str) -> Plan {
+        let mut command = program.to_string();
+        for arg in &self.args {
+            command.push(' ');
+            command.push_str(&executer::quote(arg));
+        }
+        Plan::new::<T>(command).with_stdin(self.stdin)
+    }
+}
+
+/// FNV-1a 64-bit offset basis.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+/// FNV-1a 64-bit prime.
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Hashes the bytes with FNV-1a, starting from the given hash.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// The `SplitMix64` finalizer.
+const fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl SeedDerivation {
+    /// Derives the seed of the child named `name` from `seed`.
+    #[must_use]
+    pub fn derive(self, seed: u64, name: &str) -> u64 {
+        match self {
+            Self::SplitMix64 => splitmix64(seed ^ fnv1a(FNV_OFFSET_BASIS, name.as_bytes())),
+            Self::Hash => fnv1a(
+                fnv1a(FNV_OFFSET_BASIS, &seed.to_le_bytes()),
+                name.as_bytes(),
+            ),
+            Self::Xor => seed ^ fnv1a(FNV_OFFSET_BASIS, name.as_bytes()),
+            Self::Custom(derive) => derive(seed, name),
+        }
+    }
+}
+
+/// Quotes the characters for a POSIX shell: with single quotes for style 0, double quotes for
+/// style 1 and backslash escapes for style 2.
+fn quote_with_style(chars: &[char], style: u32) -> String {
+    let mut token = String::new();
+    match style {
+        0 => {
+            token.push('\'');
+            for ch in chars {
+                if *ch == '\'' {
+                    token.push_str(r"'\''");
+                } else {
+                    token.push(*ch);
+                }
+            }
+            token.push('\'');
+        }
+        1 => {
+            token.push('"');
+            for ch in chars {
+                if matches!(ch, '\\' | '"' | '$' | '`') {
+                    token.push('\\');
+                }
+                token.push(*ch);
+            }
+            token.push('"');
+        }
+        _ => {
+            if chars.is_empty() {
+                return "''".to_string();
+            }
+            for ch in chars {
+                if *ch == '\n' {
+                    // A backslash before a newline continues the line instead.
+                    token.push_str("'\n'");
+                } else if ch.is_alphanumeric() {
+                    token.push(*ch);
+                } else {
+                    token.push('\\');
+                    token.push(*ch);
+                }
+            }
+        }
+    }
+    token
+}
+
+/// Maps a random draw to an index below `bound`, using the high bits of `draw * bound`.
+#[allow(clippy::cast_possible_truncation)]
+fn uniform_index(draw: u64, bound: usize) -> usize {
+    ((u128::from(draw) * bound as u128) >> 64) as usize
+}
 
 /// Struct for managing random number generation, allowing seed control for reproducibility.
 pub struct Randomizer {
     pub rng: RefCell<Box<dyn RngCore + Send>>,
+    /// The seed the randomizer was created with. It is not changed by
+    /// [`Randomizer::reseed`], see [`Randomizer::current_seed`].
     pub seed: u64,
+    current_seed: Cell<u64>,
+    path_base: Option<PathBuf>,
+    pick_range: (u32, u32),
+    path_list_edge_cases: PathListEdgeCases,
+    delimited_list_edge_cases: DelimitedListEdgeCases,
+    derivation: SeedDerivation,
+    file_lines: RefCell<HashMap<PathBuf, Vec<String>>>,
+    draws: Arc<AtomicU64>,
+    provenance: RefCell<Option<Vec<Provenance>>>,
 }
 
 /// Default implementation for [`Randomizer`], initializing RNG with a random seed.
@@ -19,9 +328,7 @@ impl Default for Randomizer {
         let mut seed_rng = StdRng::from_entropy();
         let seed = seed_rng.next_u64();
 
-        let rng = RefCell::new(Box::new(StdRng::seed_from_u64(seed)));
-
-        Self { rng, seed }
+        Self::with_seed(seed)
     }
 }
 
@@ -36,8 +343,239 @@ impl Randomizer {
     /// ```
     #[must_use]
     pub fn with_seed(seed: u64) -> Self {
-        let rng = RefCell::new(Box::new(StdRng::seed_from_u64(seed)));
-        Self { rng, seed }
+        let draws = Arc::new(AtomicU64::new(0));
+        let rng = RefCell::new(Box::new(CountingRng::new(seed, &draws)) as Box<_>);
+        Self {
+            rng,
+            seed,
+            current_seed: Cell::new(seed),
+            path_base: None,
+            pick_range: (1, 10),
+            path_list_edge_cases: PathListEdgeCases::default(),
+            delimited_list_edge_cases: DelimitedListEdgeCases::default(),
+            derivation: SeedDerivation::default(),
+            file_lines: RefCell::default(),
+            draws,
+            provenance: RefCell::default(),
+        }
+    }
+
+    /// Restart the random sequence from the given seed.
+    pub(crate) fn reset(&mut self, seed: u64) {
+        self.reseed(seed);
+        self.seed = seed;
+    }
+
+    /// Restart the random sequence from the given seed in place, so builders and other holders
+    /// of a reference to this randomizer draw from the new sequence. [`Randomizer::seed`] is
+    /// unchanged, and [`Randomizer::child`] keeps deriving from it, so the children match the
+    /// seed a run reports.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(7);
+    /// randomizer.reseed(42);
+    /// assert_eq!(randomizer.current_seed(), 42);
+    /// assert_eq!(randomizer.number_between(1, 10), 7);
+    /// ```
+    pub fn reseed(&self, seed: u64) {
+        *self.rng.borrow_mut() = Box::new(CountingRng::new(seed, &self.draws));
+        self.current_seed.set(seed);
+    }
+
+    /// Returns the seed the random sequence currently runs from: the seed of the last
+    /// [`Randomizer::reseed`], or [`Randomizer::seed`] when never reseeded.
+    #[must_use]
+    // `Cell::get` is not `const` on the minimum supported Rust version.
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn current_seed(&self) -> u64 {
+        self.current_seed.get()
+    }
+
+    /// Record every value generated by [`Randomizer::string`], [`Randomizer::number_between`]
+    /// and its wider variants, [`Randomizer::bool`], [`Randomizer::path`],
+    /// [`Randomizer::key_path`] and [`Randomizer::line_from_file`] together with the RNG draw
+    /// that produced it. The records are collected with [`Randomizer::take_provenance`], and the
+    /// runner attaches them to the plans of each step.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42).record_provenance(true);
+    /// let number = randomizer.number_between(1, 10);
+    /// let provenance = randomizer.take_provenance();
+    /// assert_eq!(provenance[0].kind, "number");
+    /// assert_eq!(provenance[0].value, number.to_string());
+    /// assert_eq!(provenance[0].draw, 0);
+    /// ```
+    #[must_use]
+    pub fn record_provenance(self, yes: bool) -> Self {
+        *self.provenance.borrow_mut() = yes.then(Vec::new);
+        self
+    }
+
+    /// Return the values recorded since the last call, see [`Randomizer::record_provenance`].
+    pub fn take_provenance(&self) -> Vec<Provenance> {
+        self.provenance
+            .borrow_mut()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Return the number of RNG draws made since the seed was set.
+    pub(crate) fn draws(&self) -> u64 {
+        self.draws.load(Ordering::Relaxed)
+    }
+
+    /// Record a generated value when provenance recording is enabled.
+    pub(crate) fn record(&self, kind: &'static str, draw: u64, value: &impl ToString) {
+        if let Some(provenance) = self.provenance.borrow_mut().as_mut() {
+            provenance.push(Provenance {
+                kind,
+                value: value.to_string(),
+                draw,
+            });
+        }
+    }
+
+    /// Set the inclusive range [`Randomizer::pick_random`] draws its item count from. Defaults
+    /// to `1..=10`.
+    ///
+    /// The range is part of the reproducible configuration: the same seed with the same range
+    /// always yields the same picks, but changing the range changes both the picks and every
+    /// value drawn after them.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42).default_pick_range(3, 5);
+    /// let list = vec![1, 2, 3, 4, 5, 6];
+    /// assert_eq!(randomizer.pick_random(&list), vec![2, 6, 4]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When `min` is greater than `max`.
+    #[must_use]
+    pub fn default_pick_range(mut self, min: u32, max: u32) -> Self {
+        assert!(min <= max, "pick range minimum must not exceed its maximum");
+        self.pick_range = (min, max);
+        self
+    }
+
+    /// Set the edge cases [`Randomizer::path_list`] may include.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::{PathListEdgeCases, Randomizer};
+    /// let randomizer = Randomizer::with_seed(42).path_list_edge_cases(PathListEdgeCases {
+    ///     duplicates: false,
+    ///     ..Default::default()
+    /// });
+    /// ```
+    #[must_use]
+    pub const fn path_list_edge_cases(mut self, edge_cases: PathListEdgeCases) -> Self {
+        self.path_list_edge_cases = edge_cases;
+        self
+    }
+
+    /// Set the edge cases [`Randomizer::delimited_list`] may include.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::{DelimitedListEdgeCases, Randomizer};
+    /// let randomizer = Randomizer::with_seed(42).delimited_list_edge_cases(DelimitedListEdgeCases {
+    ///     leading_separator: false,
+    ///     ..Default::default()
+    /// });
+    /// ```
+    #[must_use]
+    pub const fn delimited_list_edge_cases(mut self, edge_cases: DelimitedListEdgeCases) -> Self {
+        self.delimited_list_edge_cases = edge_cases;
+        self
+    }
+
+    /// Set how [`Randomizer::child`] derives child seeds. Defaults to
+    /// [`SeedDerivation::SplitMix64`].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::{Randomizer, SeedDerivation};
+    /// let randomizer = Randomizer::with_seed(42).with_derivation(SeedDerivation::Hash);
+    /// ```
+    #[must_use]
+    pub const fn with_derivation(mut self, derivation: SeedDerivation) -> Self {
+        self.derivation = derivation;
+        self
+    }
+
+    /// Converts the randomizer into a [`SharedRandomizer`] that is `Send` and `Sync`, so one
+    /// sequence can be drawn from by several threads. See [`SharedRandomizer`] for the
+    /// reproducibility guarantees.
+    #[must_use]
+    pub fn into_shared(self) -> SharedRandomizer {
+        SharedRandomizer(Arc::new(Mutex::new(self)))
+    }
+
+    /// Create a randomizer for the child named `name`, seeded from this randomizer's seed and
+    /// the name, see [`Randomizer::with_derivation`].
+    ///
+    /// The child does not draw from this randomizer, so adding or removing children never
+    /// changes the values of the parent or of other children. It keeps the configuration of
+    /// the parent, but not its provenance recording.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.child("db").seed, randomizer.child("db").seed);
+    /// assert_ne!(randomizer.child("db").seed, randomizer.child("cache").seed);
+    /// ```
+    #[must_use]
+    pub fn child(&self, name: &str) -> Self {
+        self.configured_with_seed(self.derivation.derive(self.seed, name))
+    }
+
+    /// Returns a new randomizer with the configuration of this one, starting the random
+    /// sequence over from [`Randomizer::seed`]. Drawing from it never advances this randomizer.
+    pub(crate) fn restarted(&self) -> Self {
+        self.configured_with_seed(self.seed)
+    }
+
+    /// Returns a new randomizer with the given seed and the configuration of this one, but not
+    /// its provenance recording.
+    fn configured_with_seed(&self, seed: u64) -> Self {
+        let mut randomizer = Self::with_seed(seed);
+        randomizer.path_base.clone_from(&self.path_base);
+        randomizer.pick_range = self.pick_range;
+        randomizer.path_list_edge_cases = self.path_list_edge_cases;
+        randomizer.delimited_list_edge_cases = self.delimited_list_edge_cases;
+        randomizer.derivation = self.derivation;
+        randomizer
+    }
+
+    /// Set the base directory absolute paths generated by [`Randomizer::path_any`] are rooted
+    /// at.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42).path_base("/var/data");
+    /// ```
+    #[must_use]
+    pub fn path_base(mut self, base: impl Into<PathBuf>) -> Self {
+        self.path_base = Some(base.into());
+        self
     }
 
     /// Generate a random number between the specified minimum and maximum values (inclusive).
@@ -51,9 +589,152 @@ impl Randomizer {
     //  assert_eq!(!randomizer.number_between(1,10), 2);
     /// ```
     pub fn number_between(&self, min: u32, max: u32) -> u32 {
+        let draw = self.draws();
+        let random_number = self.rng.borrow_mut().next_u32();
+        // The span is computed in `u64` so the full `u32` range does not overflow.
+        let span = u64::from(max) - u64::from(min) + 1;
+        let number = min + u32::try_from(u64::from(random_number) % span).unwrap_or_default();
+        self.record("number", draw, &number);
+        number
+    }
+
+    /// Generate a random signed number between the specified minimum and maximum values
+    /// (inclusive), e.g. for negative offsets like `--timeout=-1`.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.number_between_i64(-10, 10), 1);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When `min` is greater than `max`.
+    pub fn number_between_i64(&self, min: i64, max: i64) -> i64 {
+        assert!(min <= max, "minimum must not exceed maximum");
+        let draw = self.draws();
+        let number = self.rng.borrow_mut().gen_range(min..=max);
+        self.record("number", draw, &number);
+        number
+    }
+
+    /// Generate a random 64-bit number between the specified minimum and maximum values
+    /// (inclusive), e.g. for large record ids.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.number_between_u64(1 << 40, u64::MAX), 9_713_270_284_545_409_407);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When `min` is greater than `max`.
+    pub fn number_between_u64(&self, min: u64, max: u64) -> u64 {
+        assert!(min <= max, "minimum must not exceed maximum");
+        let draw = self.draws();
+        let number = self.rng.borrow_mut().gen_range(min..=max);
+        self.record("number", draw, &number);
+        number
+    }
+
+    /// Generate a random floating point number in `[min, max)`, e.g. for ratios, percentages
+    /// or coordinates.
+    ///
+    /// When `min` equals `max`, `min` is returned. When `min` is greater than `max`, the bounds
+    /// are swapped.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(format!("{:.3}", randomizer.float_between(-90.0, 90.0)), "4.780");
+    /// ```
+    pub fn float_between(&self, min: f64, max: f64) -> f64 {
+        let (min, max) = if min > max { (max, min) } else { (min, max) };
+        let draw = self.draws();
+        let ratio: f64 = self.rng.borrow_mut().gen();
+        let number = ratio.mul_add(max - min, min);
+        // Rounding may reach `max`, which is excluded.
+        let number = if number < max { number } else { min };
+        self.record("number", draw, &number);
+        number
+    }
+
+    /// Generate a random number from a normal distribution with the given mean and standard
+    /// deviation, so most values cluster around `mean` with occasional outliers. The value is
+    /// drawn with the Box-Muller transform over the seeded generator.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let length = randomizer.normal(100.0, 15.0).unwrap();
+    /// assert_eq!(format!("{length:.3}"), "82.314");
+    /// assert!(randomizer.normal(100.0, -1.0).is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// When `std_dev` is negative or not finite, reported as [`Error::InvalidStdDev`].
+    #[allow(clippy::result_large_err)]
+    pub fn normal(&self, mean: f64, std_dev: f64) -> Result<f64> {
+        if !(std_dev.is_finite() && std_dev >= 0.0) {
+            return Err(Error::InvalidStdDev { std_dev });
+        }
+
+        let draw = self.draws();
         let mut rng = self.rng.borrow_mut();
-        let random_number = rng.next_u32();
-        min + (random_number % (max - min + 1))
+        // `u1` is in `(0, 1]` so its logarithm is finite.
+        let u1 = 1.0 - rng.gen::<f64>();
+        let u2: f64 = rng.gen();
+        drop(rng);
+
+        let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+        let number = z.mul_add(std_dev, mean);
+        self.record("number", draw, &number);
+        Ok(number)
+    }
+
+    /// Generate a random number like [`Randomizer::normal`], clamped to `[min, max]`. When `min`
+    /// is greater than `max`, the bounds are swapped.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let count = randomizer.normal_clamped(0.0, 50.0, 0.0, 10.0).unwrap();
+    /// assert!((0.0..=10.0).contains(&count));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// When `std_dev` is negative or not finite, reported as [`Error::InvalidStdDev`].
+    #[allow(clippy::result_large_err)]
+    pub fn normal_clamped(&self, mean: f64, std_dev: f64, min: f64, max: f64) -> Result<f64> {
+        let (min, max) = if min > max { (max, min) } else { (min, max) };
+        Ok(self.normal(mean, std_dev)?.clamp(min, max))
+    }
+
+    /// Generate a random ratio in `[0.0, 1.0)`, like [`Randomizer::float_between`] with these
+    /// bounds.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(format!("{:.3}", randomizer.ratio()), "0.527");
+    /// ```
+    pub fn ratio(&self) -> f64 {
+        self.float_between(0.0, 1.0)
     }
 
     /// Generate a random boolean value (true or false).
@@ -67,9 +748,11 @@ impl Randomizer {
     //  assert!(!randomizer.bool());
     /// ```
     pub fn bool(&self) -> bool {
-        let mut rng = self.rng.borrow_mut();
-        let random_number = rng.next_u32();
-        random_number % 2 == 0
+        let draw = self.draws();
+        let random_number = self.rng.borrow_mut().next_u32();
+        let value = random_number & 1 == 0;
+        self.record("bool", draw, &value);
+        value
     }
 
     /// Create a [`StringDefBuilder`] based on a given [`StringDef`].
@@ -91,7 +774,84 @@ impl Randomizer {
         StringDefBuilder {
             string_def: def,
             rng: &self.rng,
+            randomizer: Some(self),
+        }
+    }
+
+    /// Generate a random string matching the regular expression `pattern`, e.g. to produce
+    /// inputs a parser should accept.
+    ///
+    /// Only patterns describing a bounded set of strings are supported: literals, classes,
+    /// groups, alternations, anchors at the start or the end of the pattern and the `?`, `{n}`
+    /// and `{n,m}` repetitions, up to [`MAX_PATTERN_REPETITION`](crate::MAX_PATTERN_REPETITION)
+    /// in total for nested repetitions. Classes with printable ASCII characters, like `.` and
+    /// negated classes, draw from those characters only.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.from_regex(r"^[a-z]{3}-\d{4}$").unwrap(), "ngo-0643");
+    /// assert!(randomizer.from_regex("a+").is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidPattern`] when `pattern` is not a valid regular expression, or uses
+    /// unbounded repetitions like `*`, `+` or `{n,}`, anchors in the middle of the pattern or word
+    /// boundaries.
+    #[allow(clippy::wrong_self_convention)]
+    #[allow(clippy::result_large_err)]
+    pub fn from_regex(&self, pattern: &str) -> Result<String> {
+        pattern::generate(pattern, &mut *self.rng.borrow_mut())
+    }
+
+    /// Generate a string probing a maximum length `limit`, with a length of `limit - 1`,
+    /// `limit` or `limit + 1` chosen at random. For a `limit` of zero, the lengths are `0` and
+    /// `1`.
+    ///
+    /// The characters are generated from `def`, whose length and length bounds are overridden,
+    /// and the length counts its [`StringDef::prefix`] and [`StringDef::suffix`]. Unicode
+    /// characters, including those of a custom [`StringDef::charset`], are excluded from the
+    /// random body so the length is exact: lengths are counted in bytes, as for
+    /// [`StringDef::length`]. A prefix and a suffix longer than the length are kept whole.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::{Randomizer, StringDef};
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let value = randomizer.length_boundary_string(255, StringDef::default()).unwrap();
+    /// assert_eq!(value.len(), 255);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// When the string can not be generated from `def`, see [`StringDef::try_generate`]. This
+    /// includes a custom charset made only of unicode characters, reported as
+    /// [`Error::NoCharactersAvailable`].
+    #[allow(clippy::result_large_err)]
+    pub fn length_boundary_string(&self, limit: u32, mut def: StringDef) -> Result<String> {
+        let mut lengths = vec![limit.saturating_sub(1), limit, limit.saturating_add(1)];
+        lengths.dedup();
+        let length = lengths[self.rng.borrow_mut().gen_range(0..lengths.len())];
+
+        let affixes = [&def.prefix, &def.suffix]
+            .into_iter()
+            .flatten()
+            .map(String::len)
+            .sum::<usize>();
+        def.min_length = None;
+        def.max_length = None;
+        if let Some(charset) = &def.charset {
+            def.exclude
+                .extend(charset.iter().filter(|ch| !ch.is_ascii()).copied());
         }
+        self.string(def)
+            .length(length.saturating_sub(u32::try_from(affixes).unwrap_or(u32::MAX)))
+            .include_unicode(false)
+            .try_generate()
     }
 
     /// Generate a random path of a specified length.
@@ -105,25 +865,680 @@ impl Randomizer {
     /// assert_eq!(randomizer.path(), PathBuf::from("gowqzkza"));
     /// ```
     pub fn path(&self) -> PathBuf {
+        let draw = self.draws();
         let mut rng = self.rng.borrow_mut();
 
         let path_length = rng.gen_range(5..=10);
         let path_name: String = (0..path_length)
             .map(|_| char::from(rng.gen_range(b'a'..=b'z')))
             .collect();
+        drop(rng);
 
+        self.record("path", draw, &path_name);
         PathBuf::from(path_name)
     }
 
-    /// Shuffle a slice of items and return a new vector with the shuffled items.
+    /// Generate a random nested path whose number of segments is drawn from `depth`, with at
+    /// least one segment. Each segment is a lowercase name like the ones of
+    /// [`Randomizer::path`], and `extension`, with or without its leading `.`, is appended to
+    /// the final one.
     ///
     /// # Example:
     ///
     /// ```rust
     /// use crazy_train::Randomizer;
+    /// use std::path::PathBuf;
     /// let randomizer = Randomizer::with_seed(42);
-    /// let list = vec![1, 2, 3, 4, 5, 6];
-    /// assert_eq!(randomizer.shuffle(&list), vec![1, 5, 6, 3, 2, 4]);
+    /// assert_eq!(
+    ///     randomizer.path_with(2..=4, Some("txt")),
+    ///     PathBuf::from("gowqzkza/kjtewqdm.txt")
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When `depth` is empty.
+    pub fn path_with(&self, depth: RangeInclusive<u32>, extension: Option<&str>) -> PathBuf {
+        assert!(!depth.is_empty(), "path depth range must not be empty");
+        let draw = self.draws();
+        let mut rng = self.rng.borrow_mut();
+
+        let segments = rng.gen_range(depth).max(1);
+        let mut path: PathBuf = (0..segments)
+            .map(|_| {
+                let segment_length = rng.gen_range(5..=10);
+                (0..segment_length)
+                    .map(|_| char::from(rng.gen_range(b'a'..=b'z')))
+                    .collect::<String>()
+            })
+            .collect();
+        drop(rng);
+
+        if let Some(extension) = extension {
+            path.set_extension(extension.trim_start_matches('.'));
+        }
+
+        self.record("path", draw, &path.display());
+        path
+    }
+
+    /// Generate a random filename of `len` characters (at least one) that is valid on
+    /// Windows, macOS and Linux alike.
+    ///
+    /// Names only use ASCII letters, digits, `-`, `_` and `.`, where a `.` never starts or
+    /// ends the name. This excludes the characters illegal on Windows (`< > : " / \ | ? *`),
+    /// control characters, spaces and non-ASCII characters, as well as the trailing dots and
+    /// spaces Windows strips. Names whose part before the first `.` is a reserved Windows
+    /// device name (`CON`, `PRN`, `AUX`, `NUL`, `COM1`-`COM9` and `LPT1`-`LPT9`, in any case)
+    /// are never generated.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.portable_filename(12), "cVi.GjH.2G.w");
+    /// ```
+    pub fn portable_filename(&self, len: usize) -> String {
+        let len = len.max(1);
+        let mut rng = self.rng.borrow_mut();
+
+        loop {
+            let name: String = (0..len)
+                .map(|index| {
+                    if index > 0 && index + 1 < len && rng.gen_bool(0.1) {
+                        '.'
+                    } else {
+                        let chars = PORTABLE_FILENAME_CHARS;
+                        char::from(chars[rng.gen_range(0..chars.len())])
+                    }
+                })
+                .collect();
+
+            let stem = name.split('.').next().unwrap_or_default();
+            if !WINDOWS_RESERVED_NAMES
+                .iter()
+                .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+            {
+                return name;
+            }
+        }
+    }
+
+    /// Generate a random path that is either relative or absolute.
+    ///
+    /// A coin flip decides the form: relative paths are generated like [`Randomizer::path`],
+    /// and absolute paths join such a path onto the base directory set with
+    /// [`Randomizer::path_base`], which defaults to the system temporary directory (`/tmp` on
+    /// most Unix systems). The base directory is used as given and is neither created nor
+    /// canonicalized.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// use std::path::PathBuf;
+    /// let randomizer = Randomizer::with_seed(42).path_base("/var/data");
+    /// assert_eq!(randomizer.path_any(), PathBuf::from("/var/data/gowqzkza"));
+    /// ```
+    pub fn path_any(&self) -> PathBuf {
+        if self.bool() {
+            let base = self.path_base.clone().unwrap_or_else(std::env::temp_dir);
+            base.join(self.path())
+        } else {
+            self.path()
+        }
+    }
+
+    /// Generate a list of `1..=max_entries` paths joined with the platform's path list
+    /// separator, `:` on Unix and `;` on Windows, like the `PATH` environment variable.
+    ///
+    /// Entries are generated like [`Randomizer::path_any`]. Unless disabled with
+    /// [`Randomizer::path_list_edge_cases`], an entry may be an empty segment or a duplicate
+    /// of a previous entry, and the list may end with a trailing separator.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42).path_base("/opt");
+    /// # #[cfg(unix)]
+    /// assert_eq!(randomizer.path_list(4), "zaqkjte::");
+    /// ```
+    pub fn path_list(&self, max_entries: u32) -> String {
+        let edge_cases = self.path_list_edge_cases;
+        let count = self.rng.borrow_mut().gen_range(1..=max_entries.max(1));
+
+        let mut entries: Vec<String> = Vec::new();
+        for _ in 0..count {
+            let edge_case = self.rng.borrow_mut().gen_range(0..100);
+            if edge_cases.empty_segments && edge_case < 15 {
+                entries.push(String::new());
+            } else if edge_cases.duplicates && edge_case < 30 && !entries.is_empty() {
+                let index = self.rng.borrow_mut().gen_range(0..entries.len());
+                entries.push(entries[index].clone());
+            } else {
+                entries.push(self.path_any().display().to_string());
+            }
+        }
+
+        let mut path_list = entries.join(&PATH_LIST_SEPARATOR.to_string());
+        if edge_cases.trailing_separator && self.rng.borrow_mut().gen_bool(0.2) {
+            path_list.push(PATH_LIST_SEPARATOR);
+        }
+        path_list
+    }
+
+    /// Generate a payload of `1..=max_items` items joined with `separator`, like the
+    /// NUL-delimited input of `xargs -0` or a newline-delimited list.
+    ///
+    /// Items are lowercase strings generated with [`StringDef::default`], so they never
+    /// contain the separator unless it is a lowercase letter. Unless disabled with
+    /// [`Randomizer::delimited_list_edge_cases`], the payload may contain:
+    ///
+    /// - empty fields, e.g. `a\0\0b`, which count as items,
+    /// - a leading separator, e.g. `\0a\0b`, adding an empty first field,
+    /// - a trailing separator, e.g. `a\0b\0`, which terminates the last item, so splitting
+    ///   on the separator yields one more, empty, field.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.delimited_list('\0', 4), "qkaktw\0\0");
+    /// ```
+    pub fn delimited_list(&self, separator: char, max_items: u32) -> String {
+        let edge_cases = self.delimited_list_edge_cases;
+        let count = self.rng.borrow_mut().gen_range(1..=max_items.max(1));
+
+        let mut items: Vec<String> = Vec::new();
+        for _ in 0..count {
+            if edge_cases.empty_fields && self.rng.borrow_mut().gen_bool(0.15) {
+                items.push(String::new());
+            } else {
+                items.push(self.string(StringDef::default()).to_string());
+            }
+        }
+
+        let mut list = items.join(&separator.to_string());
+        if edge_cases.leading_separator && self.rng.borrow_mut().gen_bool(0.2) {
+            list.insert(0, separator);
+        }
+        if edge_cases.trailing_separator && self.rng.borrow_mut().gen_bool(0.2) {
+            list.push(separator);
+        }
+        list
+    }
+
+    /// Generate the arguments and the stdin of a command together, so a bug depending on their
+    /// combination is reproduced by the seed alone.
+    ///
+    /// Up to `max_args` arguments are generated with letters, numbers and symbols, then up to
+    /// `max_lines` lines of stdin with symbols and unicode characters. Use
+    /// [`FuzzedInput::into_plan`] to run a program with them.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// struct Parse;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let input = randomizer.argv_and_stdin(3, 2);
+    /// assert_eq!(input.args.len(), 2);
+    /// let plan = input.into_plan::<Parse>("parse");
+    /// assert!(plan.stdin.is_some());
+    /// ```
+    pub fn argv_and_stdin(&self, max_args: u32, max_lines: u32) -> FuzzedInput {
+        let args = (0..self.number_between(0, max_args))
+            .map(|_| {
+                let def = StringDef {
+                    length: self.number_between(1, 12),
+                    include_symbol: true,
+                    include_capital_letters: true,
+                    include_numbers: true,
+                    ..Default::default()
+                };
+                self.string(def).to_string()
+            })
+            .collect();
+
+        let mut stdin = String::new();
+        for _ in 0..self.number_between(0, max_lines) {
+            let def = StringDef {
+                length: self.number_between(1, 40),
+                include_symbol: true,
+                include_unicode: true,
+                ..Default::default()
+            };
+            stdin.push_str(&self.string(def).to_string());
+            stdin.push('\n');
+        }
+
+        FuzzedInput { args, stdin }
+    }
+
+    /// Wrap `value` in a random but valid shell quoting style, so a POSIX shell (or a parser
+    /// following its rules, like `shell-words`) reads the token back as exactly `value`.
+    ///
+    /// The style is one of:
+    ///
+    /// - single quotes, e.g. `'it'\''s'`,
+    /// - double quotes, escaping `\`, `"`, `$` and `` ` ``, e.g. `"say \"hi\""`,
+    /// - backslash escapes outside of quotes, e.g. `it\'s`,
+    /// - a mix of the previous styles over consecutive chunks of `value`, e.g. `'it'"'"s`.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.quoted_token("it's $HOME"), r"it\'s\ \$HOME");
+    /// ```
+    pub fn quoted_token(&self, value: &str) -> String {
+        let chars: Vec<char> = value.chars().collect();
+        let style = self.rng.borrow_mut().gen_range(0..4);
+        if style < 3 || chars.len() < 2 {
+            return quote_with_style(&chars, style % 3);
+        }
+
+        let mut token = String::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let end = self.rng.borrow_mut().gen_range(start + 1..=chars.len());
+            let style = self.rng.borrow_mut().gen_range(0..3);
+            token.push_str(&quote_with_style(&chars[start..end], style));
+            start = end;
+        }
+        token
+    }
+
+    /// Render `(flag, value)` pairs as command-line arguments, choosing independently for each
+    /// flag between the `--flag=value` form and the space-separated `--flag value` form.
+    ///
+    /// The equals form produces a single argument while the space-separated form produces two,
+    /// so the result can be passed as argv or joined with spaces into a shell command.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let args = randomizer.flag_args(&[("--name", "app"), ("--count", "3")]);
+    /// assert_eq!(args.join(" "), "--name=app --count 3");
+    /// ```
+    pub fn flag_args<F, V>(&self, flags: &[(F, V)]) -> Vec<String>
+    where
+        F: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut args = Vec::new();
+        for (flag, value) in flags {
+            let (flag, value) = (flag.as_ref(), value.as_ref());
+            if self.bool() {
+                args.push(format!("{flag}={value}"));
+            } else {
+                args.push(flag.to_string());
+                args.push(value.to_string());
+            }
+        }
+        args
+    }
+
+    /// Generate a random combination of the flags of `spec` that satisfies all its rules, see
+    /// [`FlagSpec`].
+    ///
+    /// The flags are considered in a random order and each one is kept with a 50% chance,
+    /// together with the flags it requires, as long as the selection still satisfies the
+    /// rules. A flag whose requirements can never be satisfied is never used. The flags are
+    /// returned in the order of the spec, followed by the required flags that are missing from
+    /// [`FlagSpec::flags`] in the order they were selected.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::{FlagSpec, Randomizer};
+    /// let spec = FlagSpec::new(["--json", "--yaml", "--output", "--format"])
+    ///     .exclusive(["--json", "--yaml"])
+    ///     .requires("--output", ["--format"]);
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.valid_flags(&spec), vec!["--json", "--format"]);
+    /// ```
+    pub fn valid_flags(&self, spec: &FlagSpec) -> Vec<String> {
+        let mut selected: Vec<String> = Vec::new();
+        for flag in self.shuffle(&spec.flags) {
+            if selected.contains(&flag) || !self.bool() {
+                continue;
+            }
+
+            let mut candidate = selected.clone();
+            for required in spec.closure(&flag) {
+                if !candidate.contains(&required) {
+                    candidate.push(required);
+                }
+            }
+            if spec.is_satisfied_by(&candidate) {
+                selected = candidate;
+            }
+        }
+
+        let undeclared = selected.iter().filter(|flag| !spec.flags.contains(flag));
+        spec.flags
+            .iter()
+            .filter(|flag| selected.contains(flag))
+            .chain(undeclared)
+            .cloned()
+            .collect()
+    }
+
+    /// Generate the arguments of a flag or positional with a boundary argument count, to
+    /// exercise arity handling.
+    ///
+    /// When `flag` is given, every value is preceded by it (`--include a --include b`),
+    /// otherwise the values are positionals. One of these boundary cases is chosen:
+    ///
+    /// - none: no argument at all.
+    /// - one: a single value.
+    /// - many: between 2 and `cap` values (at least 2), picked from `values` with repetition.
+    /// - duplicated: the same value given twice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is empty.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let args = randomizer.arity_args(Some("--include"), &["a", "b", "c"], 5);
+    /// assert_eq!(args.join(" "), "--include b --include a --include b --include c");
+    /// ```
+    pub fn arity_args<V>(&self, flag: Option<&str>, values: &[V], cap: usize) -> Vec<String>
+    where
+        V: AsRef<str>,
+    {
+        assert!(!values.is_empty(), "values must not be empty");
+
+        let mut rng = self.rng.borrow_mut();
+        let picked: Vec<&str> = match rng.gen_range(0..4) {
+            0 => Vec::new(),
+            1 => vec![values[rng.gen_range(0..values.len())].as_ref()],
+            2 => {
+                let count = rng.gen_range(2..=cap.max(2));
+                (0..count)
+                    .map(|_| values[rng.gen_range(0..values.len())].as_ref())
+                    .collect()
+            }
+            _ => {
+                let value = values[rng.gen_range(0..values.len())].as_ref();
+                vec![value, value]
+            }
+        };
+
+        picked
+            .into_iter()
+            .flat_map(|value| flag.into_iter().chain(std::iter::once(value)))
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Generate a random nested configuration key path such as `server.ports[0].name`.
+    ///
+    /// The generated paths follow this grammar, with a total of `1..=max_depth` segments (a
+    /// `max_depth` of zero is treated as one):
+    ///
+    /// ```text
+    /// key_path   = identifier { segment }
+    /// segment    = "." identifier | "[" index "]"
+    /// identifier = [a-z_] [a-z0-9_]{0,7}
+    /// index      = 0..=99
+    /// ```
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.key_path(4), "g6");
+    /// assert_eq!(randomizer.key_path(4), "da8o.eryr_p[35].pt");
+    /// ```
+    pub fn key_path(&self, max_depth: u32) -> String {
+        let draw = self.draws();
+        let mut rng = self.rng.borrow_mut();
+        let mut key_path = key_identifier(&mut **rng);
+
+        let depth = rng.gen_range(1..=max_depth.max(1));
+        for _ in 1..depth {
+            if rng.gen_bool(0.3) {
+                key_path.push('[');
+                key_path.push_str(&rng.gen_range(0..=99).to_string());
+                key_path.push(']');
+            } else {
+                key_path.push('.');
+                key_path.push_str(&key_identifier(&mut **rng));
+            }
+        }
+        drop(rng);
+
+        self.record("key_path", draw, &key_path);
+        key_path
+    }
+
+    /// Generate the distinct Unicode normalization forms of `base`, in a random order.
+    ///
+    /// The NFC, NFD, NFKC and NFKD forms of `base` are computed and only the byte-distinct ones
+    /// are kept, so the result holds between one and four strings. The first element can be
+    /// used as a reproducibly selected variant. Note that the NFKC and NFKD forms are only
+    /// compatibility-equivalent to `base` when it contains compatibility characters such as
+    /// ligatures; for other inputs they match the NFC and NFD forms and are deduplicated.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let variants = randomizer.normalization_variants("café");
+    /// assert_eq!(variants.len(), 2);
+    /// assert!(variants.contains(&"caf\u{e9}".to_string()));
+    /// assert!(variants.contains(&"cafe\u{301}".to_string()));
+    /// ```
+    pub fn normalization_variants(&self, base: &str) -> Vec<String> {
+        let forms: [String; 4] = [
+            base.nfc().collect(),
+            base.nfd().collect(),
+            base.nfkc().collect(),
+            base.nfkd().collect(),
+        ];
+
+        let mut variants: Vec<String> = Vec::new();
+        for form in forms {
+            if !variants.contains(&form) {
+                variants.push(form);
+            }
+        }
+
+        self.shuffle(&variants)
+    }
+
+    /// Generate a random but valid TOML document.
+    ///
+    /// The document is a root table holding one to four keys. Values are tables, arrays,
+    /// strings, integers, booleans and offset date-times, and tables and arrays are nested at
+    /// most `max_depth` levels deep, the root table being the first level (a `max_depth` of
+    /// zero is treated as one).
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let document = randomizer.toml_value(3);
+    /// assert!(toml::from_str::<toml::Table>(&document).is_ok());
+    /// assert_eq!(document, Randomizer::with_seed(42).toml_value(3));
+    /// ```
+    pub fn toml_value(&self, max_depth: u32) -> String {
+        let table = self.toml_table(max_depth.max(1));
+        toml::to_string(&table).unwrap_or_default()
+    }
+
+    /// Generate a random but valid YAML document.
+    ///
+    /// The document is a root mapping holding one to four keys. Values are mappings,
+    /// sequences, strings, integers, booleans, nulls and ISO 8601 timestamps (emitted as
+    /// strings), and mappings and sequences are nested at most `max_depth` levels deep, the
+    /// root mapping being the first level (a `max_depth` of zero is treated as one).
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let document = randomizer.yaml_value(3);
+    /// assert!(serde_yaml::from_str::<serde_yaml::Value>(&document).is_ok());
+    /// assert_eq!(document, Randomizer::with_seed(42).yaml_value(3));
+    /// ```
+    pub fn yaml_value(&self, max_depth: u32) -> String {
+        let mapping = self.yaml_mapping(max_depth.max(1));
+        serde_yaml::to_string(&mapping).unwrap_or_default()
+    }
+
+    fn toml_table(&self, depth: u32) -> toml::Table {
+        let mut table = toml::Table::new();
+        for _ in 0..self.number_between(1, 4) {
+            let key = key_identifier(&mut **self.rng.borrow_mut());
+            table.insert(key, self.toml_item(depth - 1));
+        }
+        table
+    }
+
+    fn toml_item(&self, depth: u32) -> toml::Value {
+        if depth > 0 && self.number_between(0, 2) == 0 {
+            if self.bool() {
+                return toml::Value::Table(self.toml_table(depth));
+            }
+            return toml::Value::Array(
+                (0..self.number_between(0, 3))
+                    .map(|_| self.toml_item(depth - 1))
+                    .collect(),
+            );
+        }
+
+        match self.number_between(0, 3) {
+            0 => toml::Value::String(self.string(StringDef::default()).to_string()),
+            1 => toml::Value::Integer(i64::from(self.number_between(0, 2000)) - 1000),
+            2 => toml::Value::Boolean(self.bool()),
+            _ => {
+                let timestamp = self.timestamp();
+                timestamp
+                    .parse()
+                    .map_or(toml::Value::String(timestamp), toml::Value::Datetime)
+            }
+        }
+    }
+
+    fn yaml_mapping(&self, depth: u32) -> serde_yaml::Mapping {
+        let mut mapping = serde_yaml::Mapping::new();
+        for _ in 0..self.number_between(1, 4) {
+            let key = key_identifier(&mut **self.rng.borrow_mut());
+            mapping.insert(serde_yaml::Value::String(key), self.yaml_item(depth - 1));
+        }
+        mapping
+    }
+
+    fn yaml_item(&self, depth: u32) -> serde_yaml::Value {
+        if depth > 0 && self.number_between(0, 2) == 0 {
+            if self.bool() {
+                return serde_yaml::Value::Mapping(self.yaml_mapping(depth));
+            }
+            return serde_yaml::Value::Sequence(
+                (0..self.number_between(0, 3))
+                    .map(|_| self.yaml_item(depth - 1))
+                    .collect(),
+            );
+        }
+
+        match self.number_between(0, 4) {
+            0 => serde_yaml::Value::String(self.string(StringDef::default()).to_string()),
+            1 => serde_yaml::Value::from(i64::from(self.number_between(0, 2000)) - 1000),
+            2 => serde_yaml::Value::Bool(self.bool()),
+            3 => serde_yaml::Value::Null,
+            _ => serde_yaml::Value::String(self.timestamp()),
+        }
+    }
+
+    /// Generate a random UTC timestamp in RFC 3339 format.
+    fn timestamp(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.number_between(1970, 2100),
+            self.number_between(1, 12),
+            self.number_between(1, 28),
+            self.number_between(0, 23),
+            self.number_between(0, 59),
+            self.number_between(0, 59)
+        )
+    }
+
+    /// Pick a random line from a file, e.g. a curated list of interesting inputs.
+    ///
+    /// The file is read once and its lines are cached by path for the lifetime of the
+    /// [`Randomizer`], so later changes to the file are not picked up.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let path = std::env::temp_dir().join("crazy-train-doc-fixtures.txt");
+    /// std::fs::write(&path, "--help\n--version\n-v -v -v\n").unwrap();
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.line_from_file(&path).unwrap(), "--version");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// When the file can not be read or has no lines.
+    #[allow(clippy::result_large_err)]
+    pub fn line_from_file(&self, path: impl AsRef<Path>) -> Result<String> {
+        let path = path.as_ref();
+        let mut file_lines = self.file_lines.borrow_mut();
+
+        if !file_lines.contains_key(path) {
+            let content = std::fs::read_to_string(path).map_err(|source| Error::FixtureRead {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            let lines = content.lines().map(ToString::to_string).collect();
+            file_lines.insert(path.to_path_buf(), lines);
+        }
+
+        let lines = &file_lines[path];
+        if lines.is_empty() {
+            return Err(Error::EmptyFixture {
+                path: path.to_path_buf(),
+            });
+        }
+
+        let draw = self.draws();
+        let index = self.rng.borrow_mut().gen_range(0..lines.len());
+        self.record("line", draw, &lines[index]);
+        Ok(lines[index].clone())
+    }
+
+    /// Shuffle a slice of items and return a new vector with the shuffled items.
+    ///
+    /// The shuffle is an in-crate Fisher–Yates shuffle, so the order does not depend on the
+    /// shuffling algorithm of the `rand` crate. For every position `i` from the last one down
+    /// to the second one, a single `next_u64` draw `x` is made and the item at `i` is swapped
+    /// with the item at `(x * (i + 1)) >> 64`.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let list = vec![1, 2, 3, 4, 5, 6];
+    /// assert_eq!(randomizer.shuffle(&list), vec![6, 1, 2, 5, 3, 4]);
     /// ```
     pub fn shuffle<T>(&self, items: &[T]) -> Vec<T>
     where
@@ -131,114 +1546,1546 @@ impl Randomizer {
     {
         let mut rng = self.rng.borrow_mut();
         let mut shuffled_items = items.to_vec();
-        shuffled_items.shuffle(&mut *rng);
+        for index in (1..shuffled_items.len()).rev() {
+            let other = uniform_index(rng.next_u64(), index + 1);
+            shuffled_items.swap(index, other);
+        }
         shuffled_items
     }
 
-    /// Pick a random selection of items from a given slice.
-    ///
-    /// # Example:
-    ///
-    /// ```rust
-    /// use crazy_train::Randomizer;
-    /// let randomizer = Randomizer::with_seed(42);
-    /// let list = vec![1, 2, 3, 4, 5, 6];
-    /// assert_eq!(randomizer.pick_random(&list), vec![2, 6]);
-    /// ```
-    pub fn pick_random<T>(&self, items: &[T]) -> Vec<T>
-    where
-        T: Clone,
-    {
-        let mut rng = self.rng.borrow_mut();
+    /// Pick a random selection of items from a given slice.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let list = vec![1, 2, 3, 4, 5, 6];
+    /// assert_eq!(randomizer.pick_random(&list), vec![2, 6]);
+    /// ```
+    pub fn pick_random<T>(&self, items: &[T]) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut rng = self.rng.borrow_mut();
+
+        let (min, max) = self.pick_range;
+        let count = rng.gen_range(min..=max);
+
+        (0..count)
+            .map(|_| {
+                let index = rng.gen_range(0..items.len());
+                items[index].clone()
+            })
+            .collect()
+    }
+
+    /// Pick a random item from a given slice with a probability proportional to its integer
+    /// weight. Items with a weight of 0 are never picked.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let subcommands = [("list", 8), ("add", 2), ("purge", 0)];
+    /// assert_eq!(randomizer.pick_weighted(&subcommands).unwrap(), "list");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// When the slice is empty or all the weights are 0.
+    #[allow(clippy::result_large_err)]
+    pub fn pick_weighted<T>(&self, items: &[(T, u32)]) -> Result<T>
+    where
+        T: Clone,
+    {
+        let total: u64 = items.iter().map(|(_, weight)| u64::from(*weight)).sum();
+        if total == 0 {
+            return Err(Error::NoPositiveScore { items: items.len() });
+        }
+
+        let mut target = self.rng.borrow_mut().gen_range(0..total);
+        for (item, weight) in items {
+            let weight = u64::from(*weight);
+            if target < weight {
+                return Ok(item.clone());
+            }
+            target -= weight;
+        }
+        unreachable!("the target is below the total weight")
+    }
+
+    /// Pick a random item from a given slice with a probability proportional to its score, e.g.
+    /// to favor inputs that reached new code in a feedback-driven run.
+    ///
+    /// `score` is called once per item, in order. Items with a score that is not positive are
+    /// never picked. A single draw is made, so the pick is reproducible from the seed as long
+    /// as the scores are.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let flags = vec!["--fast", "--slow", "--verbose"];
+    /// let picked = randomizer.weighted_by(&flags, |flag| flag.len() as f64).unwrap();
+    /// assert_eq!(picked, "--slow");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// When a score is infinite or `NaN`, or no item has a positive score.
+    #[allow(clippy::result_large_err)]
+    pub fn weighted_by<T>(&self, items: &[T], score: impl Fn(&T) -> f64) -> Result<T>
+    where
+        T: Clone,
+    {
+        let mut scores = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            let score = score(item);
+            if !score.is_finite() {
+                return Err(Error::NonFiniteScore { index, score });
+            }
+            scores.push(score.max(0.0));
+        }
+        // Scaling by the largest score keeps the total finite when the scores are huge.
+        let largest = scores.iter().copied().fold(0.0, f64::max);
+        if largest <= 0.0 {
+            return Err(Error::NoPositiveScore { items: items.len() });
+        }
+        for score in &mut scores {
+            *score /= largest;
+        }
+        let total: f64 = scores.iter().sum();
+
+        let mut target = self.rng.borrow_mut().gen::<f64>() * total;
+        for (item, score) in items.iter().zip(&scores) {
+            if *score > 0.0 && target < *score {
+                return Ok(item.clone());
+            }
+            target -= score;
+        }
+        // Rounding may leave a remainder after the last item, which falls to the last
+        // positive one.
+        let index = scores
+            .iter()
+            .rposition(|score| *score > 0.0)
+            .unwrap_or_default();
+        Ok(items[index].clone())
+    }
+
+    /// Pick a single random item from a given slice, or `None` when it is empty.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let list = vec!["init", "build", "deploy"];
+    /// assert_eq!(randomizer.choose(&list), Some("build"));
+    /// assert_eq!(randomizer.choose::<&str>(&[]), None);
+    /// ```
+    pub fn choose<T>(&self, items: &[T]) -> Option<T>
+    where
+        T: Clone,
+    {
+        if items.is_empty() {
+            return None;
+        }
+        let index = self.rng.borrow_mut().gen_range(0..items.len());
+        Some(items[index].clone())
+    }
+
+    /// Pick up to `count` distinct items from a given slice, in random order. Items are
+    /// distinct by position, and when `count` is not smaller than the slice, all the items are
+    /// returned shuffled.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let flags = vec!["--all", "--long", "--human", "--reverse"];
+    /// assert_eq!(randomizer.pick_unique(&flags, 2), vec!["--all", "--reverse"]);
+    /// ```
+    pub fn pick_unique<T>(&self, items: &[T], count: usize) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut picked = self.shuffle(items);
+        picked.truncate(count);
+        picked
+    }
+
+    /// Pick a random item from a given slice, excluding `previous` so the same choice is not
+    /// repeated twice in a row. When every item equals `previous`, e.g. when the slice has a
+    /// single item, any item may be picked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `items` is empty.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let list = vec!["add", "remove", "list"];
+    /// let first = randomizer.choose_no_repeat(&list, None);
+    /// assert_eq!(first, "remove");
+    /// assert_eq!(randomizer.choose_no_repeat(&list, Some(&first)), "list");
+    /// ```
+    pub fn choose_no_repeat<T>(&self, items: &[T], previous: Option<&T>) -> T
+    where
+        T: Clone + PartialEq,
+    {
+        assert!(!items.is_empty(), "items must not be empty");
+
+        let candidates: Vec<&T> = items
+            .iter()
+            .filter(|item| !matches!(previous, Some(previous) if previous == *item))
+            .collect();
+        let candidates = if candidates.is_empty() {
+            items.iter().collect()
+        } else {
+            candidates
+        };
+
+        let index = self.rng.borrow_mut().gen_range(0..candidates.len());
+        candidates[index].clone()
+    }
+
+    /// Return a selector picking random items from a given slice, where a picked item does not
+    /// reappear within the next `cooldown` picks.
+    ///
+    /// Items are identified by their position, so equal items at different positions are
+    /// treated as distinct. When `cooldown` is not smaller than the number of items, the window
+    /// shrinks to `items.len() - 1` so a pick is always possible, which cycles through every
+    /// item before repeating any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `items` is empty.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let mut next = randomizer.cooldown_selector(&["get", "put", "delete", "list"], 2);
+    /// let picks: Vec<_> = (0..6).map(|_| next()).collect();
+    /// assert_eq!(picks, vec!["delete", "put", "list", "get", "delete", "put"]);
+    /// ```
+    pub fn cooldown_selector<'a, T>(
+        &'a self,
+        items: &'a [T],
+        cooldown: usize,
+    ) -> impl FnMut() -> T + 'a
+    where
+        T: Clone,
+    {
+        assert!(!items.is_empty(), "items must not be empty");
+
+        let window = cooldown.min(items.len() - 1);
+        let mut recent = std::collections::VecDeque::with_capacity(window);
+
+        move || {
+            let available: Vec<usize> = (0..items.len())
+                .filter(|index| !recent.contains(index))
+                .collect();
+            let index = available[self.rng.borrow_mut().gen_range(0..available.len())];
+
+            if window > 0 {
+                if recent.len() == window {
+                    recent.pop_front();
+                }
+                recent.push_back(index);
+            }
+            items[index].clone()
+        }
+    }
+
+    /// Sample `count` distinct combinations from the cartesian product of the given
+    /// dimensions, taking one value from each dimension in order.
+    ///
+    /// The product is never materialized: combinations are drawn by index and decoded, so
+    /// large matrices are cheap to sample. When `count` exceeds the number of combinations,
+    /// every combination is returned exactly once, in random order. When any dimension is
+    /// empty, the product is empty and so is the result.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let dimensions = vec![
+    ///     vec!["debug".to_string(), "release".to_string()],
+    ///     vec!["sqlite".to_string(), "postgres".to_string(), "mysql".to_string()],
+    /// ];
+    /// assert_eq!(
+    ///     randomizer.sample_matrix(&dimensions, 2),
+    ///     vec![vec!["debug", "postgres"], vec!["release", "sqlite"]]
+    /// );
+    /// ```
+    pub fn sample_matrix(&self, dimensions: &[Vec<String>], count: usize) -> Vec<Vec<String>> {
+        if dimensions.iter().any(Vec::is_empty) {
+            return Vec::new();
+        }
+
+        let combination = |mut index: usize| {
+            let mut values: Vec<String> = dimensions
+                .iter()
+                .rev()
+                .map(|dimension| {
+                    let value = dimension[index % dimension.len()].clone();
+                    index /= dimension.len();
+                    value
+                })
+                .collect();
+            values.reverse();
+            values
+        };
+
+        let mut rng = self.rng.borrow_mut();
+        let size = dimensions
+            .iter()
+            .try_fold(1_usize, |size, dimension| size.checked_mul(dimension.len()));
+
+        if let Some(size) = size {
+            return rand::seq::index::sample(&mut **rng, size, count.min(size))
+                .into_iter()
+                .map(combination)
+                .collect();
+        }
+
+        // The product is too large to be indexed, so random combinations are practically
+        // always distinct and duplicates are simply drawn again.
+        let mut seen = std::collections::HashSet::new();
+        let mut combinations = Vec::with_capacity(count);
+        while combinations.len() < count {
+            let indices: Vec<usize> = dimensions
+                .iter()
+                .map(|dimension| rng.gen_range(0..dimension.len()))
+                .collect();
+            if seen.insert(indices.clone()) {
+                combinations.push(
+                    indices
+                        .iter()
+                        .zip(dimensions)
+                        .map(|(index, dimension)| dimension[*index].clone())
+                        .collect(),
+                );
+            }
+        }
+        combinations
+    }
+
+    /// Generate a balanced string of brackets and quotes nested exactly `depth` levels deep.
+    ///
+    /// The included pairs are `()`, `[]`, `{}`, `<>`, `""` and `''`. A quote pair is never
+    /// nested inside another quote pair of the same kind, and each level may be followed by an
+    /// empty sibling pair, e.g. `([{}]<>)`.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.balanced_brackets(3), "<{{}}<>>");
+    /// ```
+    pub fn balanced_brackets(&self, depth: u32) -> String {
+        let mut rng = self.rng.borrow_mut();
+        let mut closers = Vec::new();
+        let mut result = String::new();
+
+        for _ in 0..depth {
+            let (open, close) = loop {
+                let pair = BRACKET_PAIRS[rng.gen_range(0..BRACKET_PAIRS.len())];
+                if pair.0 != pair.1 || !closers.iter().any(|(c, _)| *c == pair.1) {
+                    break pair;
+                }
+            };
+            result.push(open);
+            closers.push((close, rng.gen_bool(0.3)));
+        }
+
+        while let Some((close, with_sibling)) = closers.pop() {
+            result.push(close);
+            if with_sibling {
+                let (open, close) = BRACKET_PAIRS[rng.gen_range(0..4)];
+                result.push(open);
+                result.push(close);
+            }
+        }
+
+        result
+    }
+
+    /// Generate a deliberately unbalanced string of brackets and quotes.
+    ///
+    /// A balanced string (see [`Randomizer::balanced_brackets`]) of depth 2 to 4 is broken by
+    /// either dropping one character, swapping two adjacent closing brackets (e.g. `([)]`) or
+    /// replacing a closing bracket with one of a different kind.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.unbalanced_brackets(), "<[]>]");
+    /// ```
+    pub fn unbalanced_brackets(&self) -> String {
+        let depth = self.number_between(2, 4);
+        let mut chars: Vec<char> = self.balanced_brackets(depth).chars().collect();
+        let mut rng = self.rng.borrow_mut();
+
+        let is_closer = |ch: char| BRACKET_PAIRS[..4].iter().any(|(_, close)| *close == ch);
+        let swappable: Vec<usize> = (0..chars.len() - 1)
+            .filter(|&i| is_closer(chars[i]) && is_closer(chars[i + 1]) && chars[i] != chars[i + 1])
+            .collect();
+        let closers: Vec<usize> = (0..chars.len()).filter(|&i| is_closer(chars[i])).collect();
+
+        match rng.gen_range(0..3) {
+            1 if !swappable.is_empty() => {
+                let index = swappable[rng.gen_range(0..swappable.len())];
+                chars.swap(index, index + 1);
+            }
+            2 if !closers.is_empty() => {
+                let index = closers[rng.gen_range(0..closers.len())];
+                let current = chars[index];
+                let replacement = loop {
+                    let (_, close) = BRACKET_PAIRS[rng.gen_range(0..4)];
+                    if close != current {
+                        break close;
+                    }
+                };
+                chars[index] = replacement;
+            }
+            _ => {
+                chars.remove(rng.gen_range(0..chars.len()));
+            }
+        }
+
+        chars.into_iter().collect()
+    }
+
+    /// Generate bytes that look like text but are never valid UTF-8.
+    ///
+    /// Short lowercase ASCII fragments are interleaved with one to three of the following
+    /// malformations:
+    /// - overlong encodings (`C0 AF`, `E0 80 AF`, `F0 80 80 AF` for `/` and `C0 80` for NUL),
+    /// - UTF-16 byte order marks (`FE FF`, `FF FE`) and a misplaced UTF-8 byte order mark
+    ///   (`EF BB BF`),
+    /// - truncated multibyte sequences (`E2 82`, `F0 9F 98`),
+    /// - a lone continuation byte (`80`),
+    /// - an encoded surrogate half (`ED A0 80`).
+    ///
+    /// At least one of the malformations is always invalid on its own, so the result never
+    /// decodes as UTF-8. Since it is not a valid `String`, it is meant to be written to a file or
+    /// piped to a command's stdin.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// let bytes = randomizer.bad_utf8_bytes();
+    /// assert!(std::str::from_utf8(&bytes).is_err());
+    /// assert_eq!(bytes, Randomizer::with_seed(42).bad_utf8_bytes());
+    /// ```
+    pub fn bad_utf8_bytes(&self) -> Vec<u8> {
+        let mut rng = self.rng.borrow_mut();
+        let mut bytes = Vec::new();
+
+        let count = rng.gen_range(1..=3);
+        let guaranteed = rng.gen_range(0..count);
+        for index in 0..count {
+            let fragment_length = rng.gen_range(0..=4);
+            bytes.extend((0..fragment_length).map(|_| rng.gen_range(b'a'..=b'z')));
+
+            let candidates = if index == guaranteed {
+                &BAD_UTF8_SEQUENCES[..BAD_UTF8_SEQUENCES.len() - 1]
+            } else {
+                &BAD_UTF8_SEQUENCES[..]
+            };
+            bytes.extend_from_slice(candidates[rng.gen_range(0..candidates.len())]);
+        }
+
+        let fragment_length = rng.gen_range(0..=4);
+        bytes.extend((0..fragment_length).map(|_| rng.gen_range(b'a'..=b'z')));
+
+        bytes
+    }
+
+    /// Generate `len` random bytes over the full `0..=255` range, e.g. to pipe binary input
+    /// with NUL bytes or invalid UTF-8 to a command's stdin.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.bytes(4), vec![162, 36, 39, 34]);
+    /// ```
+    pub fn bytes(&self, len: usize) -> Vec<u8> {
+        let mut bytes = vec![0; len];
+        self.rng.borrow_mut().fill_bytes(&mut bytes);
+        bytes
+    }
+
+    /// Generate a random number of random bytes between `min` and `max` (inclusive), see
+    /// [`Randomizer::bytes`].
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let randomizer = Randomizer::with_seed(42);
+    /// assert_eq!(randomizer.bytes_between(0, 16).len(), 9);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When `min` is greater than `max`.
+    pub fn bytes_between(&self, min: usize, max: usize) -> Vec<u8> {
+        assert!(min <= max, "minimum must not exceed maximum");
+        let len = self.rng.borrow_mut().gen_range(min..=max);
+        self.bytes(len)
+    }
+}
+
+/// A [`Randomizer`] that can be shared between threads, created with
+/// [`Randomizer::into_shared`].
+///
+/// Clones share the same randomizer. Each call to [`SharedRandomizer::with`] holds a lock for
+/// the whole closure, so the values generated inside it come from consecutive draws of the
+/// sequence and never race with other threads. Which thread gets which part of the sequence
+/// depends on scheduling, so the values drawn by each thread are only reproducible when a
+/// single thread draws. For reproducible parallel runs, give each thread its own
+/// [child](Randomizer::child) instead.
+#[derive(Clone)]
+pub struct SharedRandomizer(Arc<Mutex<Randomizer>>);
+
+impl SharedRandomizer {
+    /// Runs `f` with exclusive access to the randomizer and returns its result.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::Randomizer;
+    /// let shared = Randomizer::with_seed(42).into_shared();
+    /// let worker = shared.clone();
+    /// let number = std::thread::spawn(move || worker.with(|randomizer| randomizer.number_between(1, 100)))
+    ///     .join()
+    ///     .unwrap();
+    /// assert_eq!(number, 27);
+    /// ```
+    pub fn with<R>(&self, f: impl FnOnce(&Randomizer) -> R) -> R {
+        let randomizer = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        f(&randomizer)
+    }
+
+    /// Returns the seed of the randomizer.
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.with(|randomizer| randomizer.seed)
+    }
+}
+
+/// Generate an identifier segment of a key path, see [`Randomizer::key_path`].
+fn key_identifier(rng: &mut dyn RngCore) -> String {
+    const START: &[u8] = b"abcdefghijklmnopqrstuvwxyz_";
+    const REST: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789_";
+
+    let length = rng.gen_range(0..=7);
+    let mut identifier = String::from(char::from(START[rng.gen_range(0..START.len())]));
+    identifier.extend((0..length).map(|_| char::from(REST[rng.gen_range(0..REST.len())])));
+    identifier
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn shared_randomizer_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedRandomizer>();
+
+        let shared = Randomizer::with_seed(42).into_shared();
+        // Every thread is spawned before any is joined, so they draw concurrently.
+        #[allow(clippy::needless_collect)]
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = shared.clone();
+                std::thread::spawn(move || {
+                    (0..100)
+                        .map(|_| shared.with(|randomizer| randomizer.number_between(1, 100)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut drawn: Vec<u32> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+
+        let sequential = Randomizer::with_seed(42);
+        let mut expected: Vec<u32> = (0..400)
+            .map(|_| sequential.number_between(1, 100))
+            .collect();
+        drawn.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(drawn, expected);
+        assert_eq!(shared.seed(), 42);
+        assert_eq!(shared.with(Randomizer::draws), sequential.draws());
+    }
+
+    #[test]
+    fn rand_number() {
+        let randomizer = Randomizer::with_seed(42);
+        assert_eq!(randomizer.number_between(1, 100), 27);
+        assert_eq!(randomizer.number_between(1, 100), 52);
+        assert_eq!(randomizer.number_between(1, 100), 98);
+    }
+
+    #[test]
+    fn rand_bool() {
+        let randomizer = Randomizer::with_seed(42);
+        assert!(randomizer.bool());
+        assert!(!randomizer.bool());
+        assert!(!randomizer.bool());
+        assert!(!randomizer.bool());
+        assert!(!randomizer.bool());
+        assert!(!randomizer.bool());
+        assert!(randomizer.bool());
+    }
+
+    #[test]
+    fn rand_string() {
+        let randomizer = Randomizer::with_seed(42);
+        assert_eq!(
+            randomizer.string(StringDef::default()).to_string(),
+            "noqkak".to_string()
+        );
+        assert_eq!(
+            randomizer.string(StringDef::default()).to_string(),
+            "twdayn".to_string()
+        );
+        assert_eq!(
+            randomizer
+                .string(StringDef {
+                    include_symbol: true,
+                    ..Default::default()
+                })
+                .to_string(),
+            "kdnfa)".to_string()
+        );
+
+        assert_eq!(
+            randomizer
+                .string(StringDef {
+                    length: 10,
+                    ..Default::default()
+                })
+                .to_string(),
+            "hdpnewfykq".to_string()
+        );
+        assert_eq!(
+            randomizer
+                .string(StringDef {
+                    include_unicode: true,
+                    ..Default::default()
+                })
+                .to_string(),
+            "vjjp😓".to_string()
+        );
+    }
+
+    #[test]
+    fn rand_path() {
+        let randomizer = Randomizer::with_seed(42);
+        assert_eq!(randomizer.path(), PathBuf::from("gowqzkza"));
+    }
+
+    #[test]
+    fn rand_path_any() {
+        let paths: Vec<PathBuf> = (0..20)
+            .map(|seed| Randomizer::with_seed(seed).path_base("/base").path_any())
+            .collect();
+
+        assert!(paths.iter().any(|path| path.is_relative()));
+        assert!(paths.iter().any(|path| path.is_absolute()));
+        for path in paths.iter().filter(|path| path.is_absolute()) {
+            assert!(path.starts_with("/base"));
+        }
+
+        assert_eq!(
+            Randomizer::with_seed(42).path_any(),
+            Randomizer::with_seed(42).path_any()
+        );
+    }
+
+    #[test]
+    fn flag_args() {
+        let flags = [("--name", "app"), ("--count", "3"), ("--env", "dev")];
+        let all_args: Vec<Vec<String>> = (0..20)
+            .map(|seed| Randomizer::with_seed(seed).flag_args(&flags))
+            .collect();
+
+        assert!(all_args
+            .iter()
+            .any(|args| args.contains(&"--name=app".to_string())));
+        assert!(all_args
+            .iter()
+            .any(|args| args.windows(2).any(|pair| pair == ["--name", "app"])));
+
+        for seed in 0..20 {
+            assert_eq!(
+                Randomizer::with_seed(seed).flag_args(&flags),
+                Randomizer::with_seed(seed).flag_args(&flags)
+            );
+        }
+    }
+
+    #[test]
+    fn arity_args() {
+        let values = ["a", "b", "c"];
+        let all_args: Vec<Vec<String>> = (0..50)
+            .map(|seed| Randomizer::with_seed(seed).arity_args(None, &values, 6))
+            .collect();
+
+        assert!(all_args.iter().any(Vec::is_empty));
+        assert!(all_args.iter().any(|args| args.len() == 1));
+        assert!(all_args.iter().any(|args| args.len() > 2));
+        assert!(all_args
+            .iter()
+            .any(|args| args.len() == 2 && args[0] == args[1]));
+        assert!(all_args.iter().all(|args| args.len() <= 6));
+
+        for seed in 0..50 {
+            assert_eq!(
+                Randomizer::with_seed(seed).arity_args(Some("--include"), &values, 6),
+                Randomizer::with_seed(seed).arity_args(Some("--include"), &values, 6)
+            );
+        }
+    }
+
+    #[test]
+    fn choose_no_repeat() {
+        let randomizer = Randomizer::with_seed(42);
+        let items = [1, 2, 3];
+        let mut previous = randomizer.choose_no_repeat(&items, None);
+        for _ in 0..500 {
+            let choice = randomizer.choose_no_repeat(&items, Some(&previous));
+            assert_ne!(choice, previous);
+            previous = choice;
+        }
+
+        assert_eq!(randomizer.choose_no_repeat(&[7], Some(&7)), 7);
+    }
+
+    #[test]
+    fn sample_matrix() {
+        let dimensions: Vec<Vec<String>> = vec![
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            vec!["1".to_string(), "2".to_string()],
+            vec!["x".to_string(), "y".to_string(), "z".to_string()],
+        ];
+
+        for seed in 0..20 {
+            let sampled = Randomizer::with_seed(seed).sample_matrix(&dimensions, 10);
+            assert_eq!(sampled.len(), 10);
+            let distinct: std::collections::HashSet<_> = sampled.iter().collect();
+            assert_eq!(distinct.len(), 10);
+            assert!(sampled.iter().all(|combination| combination.len() == 3
+                && combination
+                    .iter()
+                    .zip(&dimensions)
+                    .all(|(value, dimension)| dimension.contains(value))));
+            assert_eq!(
+                sampled,
+                Randomizer::with_seed(seed).sample_matrix(&dimensions, 10)
+            );
+        }
+
+        let all = Randomizer::with_seed(42).sample_matrix(&dimensions, 100);
+        assert_eq!(all.len(), 18);
+        assert_eq!(
+            all.iter().collect::<std::collections::HashSet<_>>().len(),
+            18
+        );
+
+        let huge = vec![(0..1000).map(|value| value.to_string()).collect::<Vec<_>>(); 8];
+        assert_eq!(Randomizer::with_seed(42).sample_matrix(&huge, 5).len(), 5);
+        assert!(Randomizer::with_seed(42)
+            .sample_matrix(&[vec!["a".to_string()], vec![]], 5)
+            .is_empty());
+    }
+
+    #[test]
+    fn cooldown_selector() {
+        let randomizer = Randomizer::with_seed(42);
+        let items = ["a", "b", "c", "d", "e"];
+        let mut next = randomizer.cooldown_selector(&items, 3);
+        let picks: Vec<_> = (0..1000).map(|_| next()).collect();
+
+        for window in picks.windows(4) {
+            for (index, pick) in window.iter().enumerate() {
+                assert!(!window[index + 1..].contains(pick));
+            }
+        }
+
+        let mut next = randomizer.cooldown_selector(&items[..2], 10);
+        let picks: Vec<_> = (0..10).map(|_| next()).collect();
+        assert!(picks.windows(2).all(|pair| pair[0] != pair[1]));
+
+        let picks_with_seed = |seed| {
+            let randomizer = Randomizer::with_seed(seed);
+            let mut next = randomizer.cooldown_selector(&items, 2);
+            (0..20).map(|_| next()).collect::<Vec<_>>()
+        };
+        assert_eq!(picks_with_seed(7), picks_with_seed(7));
+    }
+
+    #[test]
+    fn path_with_depth_and_extension() {
+        let path = Randomizer::with_seed(42).path_with(3..=3, Some(".txt"));
+        assert_eq!(path, PathBuf::from("gowqzkza/kjtewqdm/vypnw.txt"));
+        assert_eq!(path.components().count(), 3);
+        assert_eq!(
+            path,
+            Randomizer::with_seed(42).path_with(3..=3, Some("txt"))
+        );
+
+        let randomizer = Randomizer::with_seed(7);
+        for _ in 0..50 {
+            let path = randomizer.path_with(0..=5, None);
+            assert!((1..=5).contains(&path.components().count()));
+            assert!(path.extension().is_none());
+        }
+    }
+
+    #[test]
+    fn path_list() {
+        let separator = if cfg!(windows) { ';' } else { ':' };
+        let other_separator = if cfg!(windows) { ':' } else { ';' };
+        let lists: Vec<String> = (0..100)
+            .map(|seed| Randomizer::with_seed(seed).path_base("/opt").path_list(5))
+            .collect();
+
+        for list in &lists {
+            assert!(!list.contains(other_separator));
+            assert!(list.split(separator).count() <= 6);
+        }
+        assert!(lists.iter().any(|list| list
+            .split(separator)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .any(|pair| pair[0].is_empty() || pair[1].is_empty())));
+        assert!(lists.iter().any(|list| list.ends_with(separator)));
+        assert!(lists.iter().any(|list| {
+            let entries: Vec<_> = list.split(separator).filter(|e| !e.is_empty()).collect();
+            let distinct: std::collections::HashSet<_> = entries.iter().collect();
+            distinct.len() < entries.len()
+        }));
+
+        let edge_cases = PathListEdgeCases {
+            empty_segments: false,
+            trailing_separator: false,
+            duplicates: false,
+        };
+        for seed in 0..100 {
+            let list = Randomizer::with_seed(seed)
+                .path_list_edge_cases(edge_cases)
+                .path_list(5);
+            let entries: Vec<_> = list.split(separator).collect();
+            assert!(entries.iter().all(|entry| !entry.is_empty()));
+            let distinct: std::collections::HashSet<_> = entries.iter().collect();
+            assert_eq!(distinct.len(), entries.len());
+        }
+    }
+
+    #[test]
+    fn reseed_restarts_the_sequence_in_place() {
+        let randomizer = Randomizer::with_seed(7);
+        let builder = randomizer.string(StringDef::default());
+        let _ = builder.to_string();
 
-        let count = rng.gen_range(1..=10);
+        randomizer.reseed(42);
+        let fresh = Randomizer::with_seed(42);
+        assert_eq!(randomizer.seed, 7);
+        assert_eq!(randomizer.current_seed(), 42);
+        assert_eq!(
+            builder.to_string(),
+            fresh.string(StringDef::default()).to_string()
+        );
+        assert_eq!(
+            randomizer.number_between(1, 100),
+            fresh.number_between(1, 100)
+        );
+        assert_eq!(
+            randomizer.child("db").seed,
+            Randomizer::with_seed(7).child("db").seed
+        );
 
-        (0..count)
-            .map(|_| {
-                let index = rng.gen_range(0..items.len());
-                items[index].clone()
-            })
-            .collect()
+        randomizer.reseed(42);
+        let fresh = Randomizer::with_seed(42);
+        assert_eq!(
+            randomizer.number_between(1, 100),
+            fresh.number_between(1, 100)
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
+    #[test]
+    fn seed_derivations() {
+        let names = ["db", "cache", "queue", "db-1", "db-2"];
+        let child_seeds = |derivation: SeedDerivation| {
+            let randomizer = Randomizer::with_seed(42).with_derivation(derivation);
+            names
+                .iter()
+                .map(|name| randomizer.child(name).seed)
+                .collect::<Vec<_>>()
+        };
 
-    use super::*;
+        for derivation in [
+            SeedDerivation::SplitMix64,
+            SeedDerivation::Hash,
+            SeedDerivation::Xor,
+        ] {
+            let seeds = child_seeds(derivation);
+            assert_eq!(seeds, child_seeds(derivation));
+            let distinct: std::collections::HashSet<_> = seeds.iter().collect();
+            assert_eq!(distinct.len(), names.len());
+        }
+        assert_ne!(
+            child_seeds(SeedDerivation::SplitMix64),
+            child_seeds(SeedDerivation::Hash)
+        );
+        assert_ne!(
+            child_seeds(SeedDerivation::Hash),
+            child_seeds(SeedDerivation::Xor)
+        );
+
+        let custom = SeedDerivation::Custom(|seed, name| seed + name.len() as u64);
+        assert_eq!(child_seeds(custom), vec![44, 47, 47, 46, 46]);
+
+        let child = Randomizer::with_seed(42).child("db");
+        assert_eq!(
+            child.number_between(0, 1000),
+            Randomizer::with_seed(child.seed).number_between(0, 1000)
+        );
+    }
 
     #[test]
-    fn rand_number() {
+    fn number_between_bounds() {
         let randomizer = Randomizer::with_seed(42);
-        assert_eq!(randomizer.number_between(1, 100), 27);
-        assert_eq!(randomizer.number_between(1, 100), 52);
-        assert_eq!(randomizer.number_between(1, 100), 98);
+        assert_eq!(randomizer.number_between(1, 10), 7);
+
+        let randomizer = Randomizer::with_seed(42);
+        let numbers: Vec<u32> = (0..100)
+            .map(|_| randomizer.number_between(0, u32::MAX))
+            .collect();
+        assert!(numbers.iter().any(|number| *number > u32::MAX / 2));
+
+        assert!((0..10).all(|_| randomizer.number_between(5, 5) == 5));
+        assert!((0..10).all(|_| randomizer.number_between(u32::MAX, u32::MAX) == u32::MAX));
     }
 
     #[test]
-    fn rand_bool() {
+    fn wide_number_between_bounds() {
         let randomizer = Randomizer::with_seed(42);
-        assert!(randomizer.bool());
-        assert!(!randomizer.bool());
-        assert!(!randomizer.bool());
-        assert!(!randomizer.bool());
-        assert!(!randomizer.bool());
-        assert!(!randomizer.bool());
-        assert!(randomizer.bool());
+        for _ in 0..100 {
+            assert!((-5..=5).contains(&randomizer.number_between_i64(-5, 5)));
+            assert!((u64::MAX - 5..=u64::MAX)
+                .contains(&randomizer.number_between_u64(u64::MAX - 5, u64::MAX)));
+        }
+        assert_eq!(randomizer.number_between_i64(-3, -3), -3);
+        assert_eq!(randomizer.number_between_u64(9, 9), 9);
+        randomizer.number_between_i64(i64::MIN, i64::MAX);
+        randomizer.number_between_u64(0, u64::MAX);
+
+        let numbers_with_seed = |seed| {
+            let randomizer = Randomizer::with_seed(seed);
+            (0..10)
+                .map(|_| randomizer.number_between_i64(-1000, 1000))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(numbers_with_seed(7), numbers_with_seed(7));
     }
 
     #[test]
-    fn rand_string() {
+    #[should_panic(expected = "minimum must not exceed maximum")]
+    fn number_between_i64_rejects_inverted_range() {
+        Randomizer::with_seed(42).number_between_i64(1, -1);
+    }
+
+    #[test]
+    fn valid_flags_respect_rules() {
+        let spec = FlagSpec::new([
+            "--json",
+            "--yaml",
+            "--toml",
+            "--output",
+            "--format",
+            "--quiet",
+            "--verbose",
+            "--color",
+        ])
+        .exclusive(["--json", "--yaml", "--toml"])
+        .requires("--output", ["--format"])
+        .requires("--color", ["--verbose"])
+        .conflicts("--quiet", "--verbose");
+
+        let combinations: Vec<Vec<String>> = (0..200)
+            .map(|seed| Randomizer::with_seed(seed).valid_flags(&spec))
+            .collect();
+        for flags in &combinations {
+            let formats = flags
+                .iter()
+                .filter(|flag| ["--json", "--yaml", "--toml"].contains(&flag.as_str()))
+                .count();
+            assert!(formats <= 1, "{flags:?}");
+            assert!(spec.is_satisfied_by(flags), "{flags:?}");
+        }
+        assert!(combinations
+            .iter()
+            .any(|flags| flags.contains(&"--output".to_string())));
+        assert!(combinations
+            .iter()
+            .any(|flags| flags.contains(&"--color".to_string())));
+        assert_eq!(
+            Randomizer::with_seed(7).valid_flags(&spec),
+            Randomizer::with_seed(7).valid_flags(&spec)
+        );
+
+        let unsatisfiable = FlagSpec::new(["--a", "--b"])
+            .requires("--a", ["--b"])
+            .conflicts("--a", "--b");
+        for seed in 0..50 {
+            assert!(!Randomizer::with_seed(seed)
+                .valid_flags(&unsatisfiable)
+                .contains(&"--a".to_string()));
+        }
+    }
+
+    #[test]
+    fn valid_flags_keep_required_flags_missing_from_the_spec() {
+        let spec = FlagSpec::new(["--output", "--quiet"]).requires("--output", ["--format"]);
+
+        let combinations: Vec<Vec<String>> = (0..50)
+            .map(|seed| Randomizer::with_seed(seed).valid_flags(&spec))
+            .collect();
+        for flags in &combinations {
+            assert!(spec.is_satisfied_by(flags), "{flags:?}");
+        }
+        assert!(combinations.contains(&vec!["--output".to_string(), "--format".to_string()]));
+        assert!(combinations.contains(&vec![
+            "--output".to_string(),
+            "--quiet".to_string(),
+            "--format".to_string()
+        ]));
+    }
+
+    #[test]
+    fn float_between_bounds() {
+        let randomizer = Randomizer::with_seed(42);
+        for _ in 0..1000 {
+            let number = randomizer.float_between(-1.5, 2.5);
+            assert!((-1.5..2.5).contains(&number));
+            assert!((0.0..1.0).contains(&randomizer.ratio()));
+            assert!((1.0..3.0).contains(&randomizer.float_between(3.0, 1.0)));
+        }
+        assert!((randomizer.float_between(4.2, 4.2) - 4.2).abs() < f64::EPSILON);
+
+        let numbers_with_seed = |seed| {
+            let randomizer = Randomizer::with_seed(seed);
+            (0..10).map(|_| randomizer.ratio()).collect::<Vec<_>>()
+        };
+        assert_eq!(numbers_with_seed(7), numbers_with_seed(7));
+    }
+
+    #[test]
+    fn normal_distribution() {
+        let randomizer = Randomizer::with_seed(42);
+        let samples: Vec<f64> = (0..5000)
+            .map(|_| randomizer.normal(10.0, 2.0).unwrap())
+            .collect();
+        let mean = samples.iter().sum::<f64>() / 5000.0;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / 5000.0;
+        assert!((mean - 10.0).abs() < 0.2, "{mean}");
+        assert!((variance.sqrt() - 2.0).abs() < 0.2, "{variance}");
+
+        assert!((randomizer.normal(4.2, 0.0).unwrap() - 4.2).abs() < f64::EPSILON);
+        for _ in 0..1000 {
+            let number = randomizer.normal_clamped(0.0, 100.0, 5.0, -5.0).unwrap();
+            assert!((-5.0..=5.0).contains(&number));
+        }
+
+        for std_dev in [-1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                randomizer.normal(0.0, std_dev),
+                Err(Error::InvalidStdDev { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn pick_weighted() {
+        let items = [("common", 90), ("rare", 10), ("never", 0)];
+        let randomizer = Randomizer::with_seed(42);
+        let picks: Vec<&str> = (0..1000)
+            .map(|_| randomizer.pick_weighted(&items).unwrap())
+            .collect();
+
+        let common = picks.iter().filter(|pick| **pick == "common").count();
+        assert!(
+            (850..=950).contains(&common),
+            "common picked {common} times"
+        );
+        assert!(picks.contains(&"rare"));
+        assert!(!picks.contains(&"never"));
+
+        let picks_with_seed = |seed| {
+            let randomizer = Randomizer::with_seed(seed);
+            (0..20)
+                .map(|_| randomizer.pick_weighted(&items).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(picks_with_seed(7), picks_with_seed(7));
+
+        let empty: [(&str, u32); 0] = [];
+        assert!(matches!(
+            randomizer.pick_weighted(&empty),
+            Err(Error::NoPositiveScore { items: 0 })
+        ));
+        assert!(randomizer.pick_weighted(&[("a", 0), ("b", 0)]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quoted_token_round_trips_through_the_shell() {
+        let values = [
+            "plain",
+            "",
+            "it's",
+            r#"say "hi" \ $HOME `id` !"#,
+            "tab\tnew\nline",
+            "ünïcödé * ? [a] ~user #hash",
+        ];
+        for (seed, value) in (0..40).flat_map(|seed| values.iter().map(move |value| (seed, value)))
+        {
+            let token = Randomizer::with_seed(seed).quoted_token(value);
+            let output = crate::executer::run_sh(&format!("printf '%s' {token}")).unwrap();
+            assert_eq!(&output.stdout, value, "token {token} with seed {seed}");
+        }
+
+        let tokens: std::collections::HashSet<String> = (0..40)
+            .map(|seed| Randomizer::with_seed(seed).quoted_token("it's"))
+            .collect();
+        assert!(tokens.len() >= 4, "{tokens:?}");
+    }
+
+    #[test]
+    fn choose() {
+        let items = ["a", "b", "c"];
         let randomizer = Randomizer::with_seed(42);
+        let picks: Vec<&str> = (0..100)
+            .map(|_| randomizer.choose(&items).unwrap())
+            .collect();
+        assert!(items.iter().all(|item| picks.contains(item)));
         assert_eq!(
-            randomizer.string(StringDef::default()).to_string(),
-            "noqkak".to_string()
+            Randomizer::with_seed(7).choose(&items),
+            Randomizer::with_seed(7).choose(&items)
         );
+        assert_eq!(randomizer.choose::<u32>(&[]), None);
+    }
+
+    #[test]
+    fn pick_unique() {
+        let items: Vec<u32> = (0..10).collect();
+        for seed in 0..50 {
+            let randomizer = Randomizer::with_seed(seed);
+            let picked = randomizer.pick_unique(&items, 4);
+            assert_eq!(picked.len(), 4);
+            let distinct: std::collections::HashSet<_> = picked.iter().collect();
+            assert_eq!(distinct.len(), 4);
+
+            let mut all = randomizer.pick_unique(&items, 25);
+            all.sort_unstable();
+            assert_eq!(all, items);
+        }
         assert_eq!(
-            randomizer.string(StringDef::default()).to_string(),
-            "twdayn".to_string()
+            Randomizer::with_seed(7).pick_unique(&items, 5),
+            Randomizer::with_seed(7).pick_unique(&items, 5)
+        );
+        assert!(Randomizer::with_seed(7).pick_unique(&items, 0).is_empty());
+        assert!(Randomizer::with_seed(7)
+            .pick_unique::<u32>(&[], 3)
+            .is_empty());
+    }
+
+    #[test]
+    fn weighted_by() {
+        let items = ["rare", "common", "never"];
+        let score = |item: &&str| match *item {
+            "common" => 99.0,
+            "rare" => 1.0,
+            _ => -5.0,
+        };
+
+        let randomizer = Randomizer::with_seed(42);
+        let picks: Vec<&str> = (0..1000)
+            .map(|_| randomizer.weighted_by(&items, score).unwrap())
+            .collect();
+        let common = picks.iter().filter(|pick| **pick == "common").count();
+        assert!(common > 950, "common picked {common} times");
+        assert!(picks.contains(&"rare"));
+        assert!(!picks.contains(&"never"));
+
+        let picks_with_seed = |seed| {
+            let randomizer = Randomizer::with_seed(seed);
+            (0..20)
+                .map(|_| randomizer.weighted_by(&items, score).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(picks_with_seed(7), picks_with_seed(7));
+
+        assert!(matches!(
+            randomizer.weighted_by(&items, |_| 0.0),
+            Err(Error::NoPositiveScore { items: 3 })
+        ));
+        for invalid in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(
+                randomizer.weighted_by(&items, |item| if *item == "never" { invalid } else { 1.0 }),
+                Err(Error::NonFiniteScore { index: 2, .. })
+            ));
+        }
+        let huge = |item: &&str| if *item == "never" { 0.0 } else { f64::MAX };
+        let picks: Vec<&str> = (0..100)
+            .map(|_| randomizer.weighted_by(&items, huge).unwrap())
+            .collect();
+        assert!(picks.contains(&"rare") && picks.contains(&"common"));
+    }
+
+    #[test]
+    fn delimited_list() {
+        for separator in ['\0', '\n'] {
+            let lists: Vec<String> = (0..100)
+                .map(|seed| Randomizer::with_seed(seed).delimited_list(separator, 5))
+                .collect();
+
+            for list in &lists {
+                // One empty field may come from each of the leading and trailing separators.
+                let fields = list.split(separator).count();
+                assert!((1..=7).contains(&fields));
+            }
+            assert!(lists.iter().any(|list| list.starts_with(separator)));
+            assert!(lists.iter().any(|list| list.ends_with(separator)));
+            let doubled: String = [separator, separator].iter().collect();
+            assert!(lists.iter().any(|list| list.contains(&doubled)));
+            assert_eq!(
+                lists,
+                (0..100)
+                    .map(|seed| Randomizer::with_seed(seed).delimited_list(separator, 5))
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        let edge_cases = DelimitedListEdgeCases {
+            empty_fields: false,
+            leading_separator: false,
+            trailing_separator: false,
+        };
+        for seed in 0..100 {
+            let list = Randomizer::with_seed(seed)
+                .delimited_list_edge_cases(edge_cases)
+                .delimited_list('\0', 5);
+            let fields: Vec<_> = list.split('\0').collect();
+            assert!((1..=5).contains(&fields.len()));
+            assert!(fields.iter().all(|field| !field.is_empty()));
+        }
+    }
+
+    #[test]
+    fn length_boundary_string() {
+        let def = StringDef {
+            include_unicode: true,
+            include_symbol: true,
+            ..Default::default()
+        };
+        let lengths: Vec<usize> = (0..50)
+            .map(|seed| {
+                Randomizer::with_seed(seed)
+                    .length_boundary_string(255, def.clone())
+                    .unwrap()
+                    .len()
+            })
+            .collect();
+
+        for length in [254, 255, 256] {
+            assert!(lengths.contains(&length));
+        }
+        assert!(lengths.iter().all(|length| (254..=256).contains(length)));
+
+        for seed in 0..10 {
+            assert_eq!(
+                Randomizer::with_seed(seed)
+                    .length_boundary_string(8, def.clone())
+                    .unwrap(),
+                Randomizer::with_seed(seed)
+                    .length_boundary_string(8, def.clone())
+                    .unwrap()
+            );
+        }
+        let lengths: Vec<usize> = (0..20)
+            .map(|seed| {
+                Randomizer::with_seed(seed)
+                    .length_boundary_string(0, def.clone())
+                    .unwrap()
+                    .len()
+            })
+            .collect();
+        assert!(lengths.iter().all(|length| *length <= 1));
+        assert!(lengths.contains(&0) && lengths.contains(&1));
+    }
+
+    #[test]
+    fn length_boundary_string_keeps_ascii_of_custom_charsets() {
+        let mixed = StringDef {
+            charset: Some(vec!['a', 'é', '🙆']),
+            ..Default::default()
+        };
+        for seed in 0..20 {
+            let value = Randomizer::with_seed(seed)
+                .length_boundary_string(16, mixed.clone())
+                .unwrap();
+            assert!((15..=17).contains(&value.len()), "{value:?}");
+            assert!(value.chars().all(|ch| ch == 'a'), "{value:?}");
+        }
+
+        let unicode_only = StringDef {
+            charset: Some(vec!['é']),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Randomizer::with_seed(1).length_boundary_string(16, unicode_only),
+            Err(Error::NoCharactersAvailable)
+        ));
+    }
+
+    #[test]
+    fn length_boundary_string_overrides_bounds_and_counts_affixes() {
+        let bounded = StringDef {
+            min_length: Some(1),
+            max_length: Some(3),
+            ..Default::default()
+        };
+        let wrapped = StringDef {
+            prefix: Some("--name=".to_string()),
+            suffix: Some("!".to_string()),
+            ..Default::default()
+        };
+        for seed in 0..20 {
+            let randomizer = Randomizer::with_seed(seed);
+            let length = randomizer
+                .length_boundary_string(255, bounded.clone())
+                .unwrap()
+                .len();
+            assert!((254..=256).contains(&length), "{length}");
+
+            let value = randomizer
+                .length_boundary_string(10, wrapped.clone())
+                .unwrap();
+            assert!((9..=11).contains(&value.len()), "{value:?}");
+            assert!(value.starts_with("--name=") && value.ends_with('!'));
+        }
+        assert_eq!(
+            Randomizer::with_seed(1)
+                .length_boundary_string(2, wrapped)
+                .unwrap(),
+            "--name=!"
         );
+    }
+
+    #[test]
+    fn portable_filename() {
+        let illegal = ['<', '>', ':', '"', '/', '\\', '|', '?', '*', ' '];
+        for seed in 0..200 {
+            let randomizer = Randomizer::with_seed(seed);
+            for len in [1, 3, 4, 8, 20] {
+                let name = randomizer.portable_filename(len);
+                assert_eq!(name.len(), len);
+                assert!(name.chars().all(|ch| ch.is_ascii_graphic()));
+                assert!(!name.contains(illegal));
+                assert!(!name.starts_with('.') && !name.ends_with('.'));
+                let stem = name.split('.').next().unwrap();
+                assert!(!WINDOWS_RESERVED_NAMES
+                    .iter()
+                    .any(|reserved| reserved.eq_ignore_ascii_case(stem)));
+            }
+        }
+
         assert_eq!(
-            randomizer
-                .string(StringDef {
-                    include_symbol: true,
-                    ..Default::default()
-                })
-                .to_string(),
-            "kdnfa)".to_string()
+            Randomizer::with_seed(7).portable_filename(16),
+            Randomizer::with_seed(7).portable_filename(16)
         );
+    }
+
+    #[test]
+    fn key_path() {
+        let is_identifier = |segment: &str| {
+            let mut chars = segment.chars();
+            chars
+                .next()
+                .is_some_and(|ch| ch.is_ascii_lowercase() || ch == '_')
+                && segment.len() <= 8
+                && chars.all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+        };
+
+        for seed in 0..100 {
+            let key_path = Randomizer::with_seed(seed).key_path(5);
+            let mut depth = 0;
+            for segment in key_path.split('.') {
+                let (identifier, indices) = segment
+                    .split_once('[')
+                    .map_or((segment, ""), |(identifier, rest)| (identifier, rest));
+                assert!(is_identifier(identifier), "{key_path}");
+                depth += 1;
+                for index in indices.split('[').filter(|index| !index.is_empty()) {
+                    let number = index.strip_suffix(']').unwrap();
+                    assert!(number.parse::<u8>().unwrap() <= 99, "{key_path}");
+                    depth += 1;
+                }
+            }
+            assert!((1..=5).contains(&depth), "{key_path}");
+        }
 
         assert_eq!(
-            randomizer
-                .string(StringDef {
-                    length: 10,
-                    ..Default::default()
-                })
-                .to_string(),
-            "hdpnewfykq".to_string()
+            Randomizer::with_seed(42).key_path(5),
+            Randomizer::with_seed(42).key_path(5)
         );
+    }
+
+    #[test]
+    fn normalization_variants() {
+        let randomizer = Randomizer::with_seed(42);
+        let variants = randomizer.normalization_variants("résumé niño");
+
+        assert_eq!(variants.len(), 2);
+        assert_ne!(variants[0].as_bytes(), variants[1].as_bytes());
+        for variant in &variants {
+            assert_eq!(
+                variant.nfc().collect::<String>(),
+                "résumé niño".nfc().collect::<String>()
+            );
+        }
+
+        let variants = randomizer.normalization_variants("\u{fb01}anc\u{e9}");
+        assert_eq!(variants.len(), 4);
+
         assert_eq!(
-            randomizer
-                .string(StringDef {
-                    include_unicode: true,
-                    ..Default::default()
-                })
-                .to_string(),
-            "vjjp😓".to_string()
+            Randomizer::with_seed(7).normalization_variants("café"),
+            Randomizer::with_seed(7).normalization_variants("café")
         );
     }
 
     #[test]
-    fn rand_path() {
+    fn pick_random_with_default_range() {
+        let list = vec![1, 2, 3, 4, 5, 6];
+        for seed in 0..50 {
+            let randomizer = Randomizer::with_seed(seed).default_pick_range(2, 4);
+            for _ in 0..5 {
+                let count = randomizer.pick_random(&list).len();
+                assert!((2..=4).contains(&count));
+            }
+        }
+
+        let randomizer = Randomizer::with_seed(42).default_pick_range(3, 3);
+        assert_eq!(randomizer.pick_random(&list).len(), 3);
+    }
+
+    fn toml_depth(value: &toml::Value) -> u32 {
+        match value {
+            toml::Value::Table(table) => 1 + table.values().map(toml_depth).max().unwrap_or(0),
+            toml::Value::Array(array) => 1 + array.iter().map(toml_depth).max().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    fn yaml_depth(value: &serde_yaml::Value) -> u32 {
+        match value {
+            serde_yaml::Value::Mapping(mapping) => {
+                1 + mapping.values().map(yaml_depth).max().unwrap_or(0)
+            }
+            serde_yaml::Value::Sequence(sequence) => {
+                1 + sequence.iter().map(yaml_depth).max().unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn toml_value() {
+        let mut depths = Vec::new();
+        for seed in 0..50 {
+            let document = Randomizer::with_seed(seed).toml_value(3);
+            let table: toml::Table = toml::from_str(&document).unwrap();
+            let depth = toml_depth(&toml::Value::Table(table));
+            assert!((1..=3).contains(&depth), "{document}");
+            depths.push(depth);
+        }
+        assert!(depths.contains(&3));
+    }
+
+    #[test]
+    fn yaml_value() {
+        let mut depths = Vec::new();
+        for seed in 0..50 {
+            let document = Randomizer::with_seed(seed).yaml_value(3);
+            let value: serde_yaml::Value = serde_yaml::from_str(&document).unwrap();
+            let depth = yaml_depth(&value);
+            assert!((1..=3).contains(&depth), "{document}");
+            depths.push(depth);
+        }
+        assert!(depths.contains(&3));
+    }
+
+    #[test]
+    fn line_from_file() {
+        let path = std::env::temp_dir().join("crazy-train-fixtures.txt");
+        std::fs::write(&path, "alpha\nbeta\ngamma\ndelta\n").unwrap();
+
+        let picks = |seed| {
+            let randomizer = Randomizer::with_seed(seed);
+            (0..5)
+                .map(|_| randomizer.line_from_file(&path).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let first_run = picks(42);
+        assert_eq!(first_run, picks(42));
+        for line in &first_run {
+            assert!(["alpha", "beta", "gamma", "delta"].contains(&line.as_str()));
+        }
+
         let randomizer = Randomizer::with_seed(42);
-        assert_eq!(randomizer.path(), PathBuf::from("gowqzkza"));
+        assert!(randomizer.line_from_file(&path).is_ok());
+        std::fs::remove_file(&path).unwrap();
+        assert!(randomizer.line_from_file(&path).is_ok());
+
+        assert!(matches!(
+            Randomizer::with_seed(42).line_from_file(&path),
+            Err(Error::FixtureRead { .. })
+        ));
     }
 
     #[test]
     fn shuffle() {
         let randomizer = Randomizer::with_seed(42);
         let list = vec![1, 2, 3, 4, 5, 6];
-        assert_eq!(randomizer.shuffle(&list), vec![1, 5, 6, 3, 2, 4]);
+        assert_eq!(randomizer.shuffle(&list), vec![6, 1, 2, 5, 3, 4]);
     }
 
     #[test]
@@ -248,4 +3095,82 @@ mod tests {
         assert_eq!(randomizer.pick_random(&list), vec![2, 6]);
         assert_eq!(randomizer.pick_random(&list), vec![3, 1, 3, 5, 6, 1, 6]);
     }
+
+    fn is_balanced(s: &str) -> bool {
+        let mut stack = Vec::new();
+        for ch in s.chars() {
+            if let Some((_, close)) = BRACKET_PAIRS.iter().find(|(open, _)| *open == ch) {
+                if stack.last() == Some(close) && ch == *close {
+                    stack.pop();
+                } else {
+                    stack.push(*close);
+                }
+            } else if stack.pop() != Some(ch) {
+                return false;
+            }
+        }
+        stack.is_empty()
+    }
+
+    #[test]
+    fn balanced_brackets() {
+        for seed in 0..100 {
+            let randomizer = Randomizer::with_seed(seed);
+            let depth = randomizer.number_between(1, 6);
+            let result = randomizer.balanced_brackets(depth);
+            assert!(is_balanced(&result), "{result} should be balanced");
+            assert!(result.chars().count() >= depth as usize * 2);
+        }
+        let randomizer = Randomizer::with_seed(42);
+        assert_eq!(randomizer.balanced_brackets(0), "");
+    }
+
+    #[test]
+    fn unbalanced_brackets() {
+        for seed in 0..100 {
+            let randomizer = Randomizer::with_seed(seed);
+            let result = randomizer.unbalanced_brackets();
+            assert!(!is_balanced(&result), "{result} should not be balanced");
+        }
+    }
+
+    #[test]
+    fn bad_utf8_bytes() {
+        let outputs: Vec<Vec<u8>> = (0..200)
+            .map(|seed| Randomizer::with_seed(seed).bad_utf8_bytes())
+            .collect();
+
+        for bytes in &outputs {
+            assert!(std::str::from_utf8(bytes).is_err());
+        }
+
+        for sequence in BAD_UTF8_SEQUENCES {
+            assert!(outputs.iter().any(|bytes| bytes
+                .windows(sequence.len())
+                .any(|window| window == sequence)));
+        }
+
+        assert_eq!(
+            Randomizer::with_seed(42).bad_utf8_bytes(),
+            Randomizer::with_seed(42).bad_utf8_bytes()
+        );
+    }
+
+    #[test]
+    fn bytes_are_deterministic() {
+        let randomizer = Randomizer::with_seed(42);
+        let bytes = randomizer.bytes(512);
+        assert_eq!(bytes.len(), 512);
+        assert_eq!(bytes, Randomizer::with_seed(42).bytes(512));
+        assert_ne!(bytes, Randomizer::with_seed(7).bytes(512));
+        assert!(bytes.contains(&0));
+        assert!(bytes.iter().any(|byte| *byte > 127));
+
+        let lengths: Vec<usize> = (0..100)
+            .map(|_| randomizer.bytes_between(3, 6).len())
+            .collect();
+        assert!(lengths.iter().all(|len| (3..=6).contains(len)));
+        assert!((3..=6).all(|len| lengths.contains(&len)));
+        assert!(randomizer.bytes_between(0, 0).is_empty());
+    }
 }
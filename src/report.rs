@@ -0,0 +1,442 @@
+//! This module defines the reports produced while executing steps.
+//!
+//! The [`StepReport`] struct captures what a single step executed, how long its phases took
+//! and the output it produced, and can derive a stable fingerprint used to group identical
+//! failures together. The [`RunReport`] struct aggregates the step reports and the
+//! differential [`Divergence`]s of a whole run, with [`StepStats`] summarizing each step across
+//! iterations and [`DiversityMetrics`] summarizing how diverse its commands were, and the
+//! [`SweepResult`] struct aggregates the outcome of running the same steps with many seeds.
+
+use crate::executer::Output;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+/// FNV-1a 64-bit offset basis.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+/// FNV-1a 64-bit prime.
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// The final status of a step in a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum StepStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// How long each phase of a step took, `None` for the phases that did not run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PhaseDurations {
+    /// The duration of the plan command.
+    pub plan: Option<Duration>,
+    /// The duration of the [check command](crate::step::StepTrait::run_check), reported with
+    /// the last plan of the step.
+    pub check: Option<Duration>,
+    /// The duration of the [test command](crate::step::StepTrait::run_test), reported with the
+    /// last plan of the step.
+    pub test: Option<Duration>,
+}
+
+/// Represents the result of executing a single step.
+#[derive(Debug, Serialize)]
+pub struct StepReport {
+    /// The id of the executed plan.
+    pub id: String,
+    /// The command that was executed.
+    pub command: String,
+    /// The final status of the step.
+    pub status: StepStatus,
+    /// The output captured from the command, `None` when the step was skipped.
+    pub output: Option<Output>,
+    /// How long the phases of the step took.
+    pub durations: PhaseDurations,
+}
+
+/// A plan whose outputs differ between the two programs of a differential run.
+#[derive(Debug, Clone, Serialize)]
+pub struct Divergence {
+    /// The id of the plan.
+    pub id: String,
+    /// The command holding the arguments both programs were run with.
+    pub command: String,
+    /// The output of the first program.
+    pub output_a: Output,
+    /// The output of the second program.
+    pub output_b: Output,
+}
+
+impl Divergence {
+    /// Returns whether two outputs diverge, comparing their status codes and their stdout with
+    /// line endings and trailing whitespace normalized.
+    #[must_use]
+    pub fn between(output_a: &Output, output_b: &Output) -> bool {
+        let normalize_stdout = |stdout: &str| {
+            stdout
+                .lines()
+                .map(str::trim_end)
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        output_a.status_code != output_b.status_code
+            || normalize_stdout(&output_a.stdout) != normalize_stdout(&output_b.stdout)
+    }
+}
+
+impl std::fmt::Display for Divergence {
+    /// Formats the command followed by the diff of the two outputs, see [`Output::diff`].
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "outputs diverge for: {}", self.command)?;
+        if self.output_a.status_code != self.output_b.status_code {
+            writeln!(
+                f,
+                "status code: {:?} != {:?}",
+                self.output_a.status_code, self.output_b.status_code
+            )?;
+        }
+        write!(f, "{}", self.output_a.diff(&self.output_b))
+    }
+}
+
+/// Represents the result of a whole run, see [`Runner::run_report`](crate::Runner::run_report).
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+    /// The seed the run was executed with.
+    pub seed: u64,
+    /// How long the whole run took.
+    pub elapsed: Duration,
+    /// The reports of the executed steps, in execution order.
+    pub steps: Vec<StepReport>,
+    /// The plans whose outputs diverged in a differential run.
+    pub divergences: Vec<Divergence>,
+}
+
+/// Counts of how a step fared across the iterations of a run, see
+/// [`RunReport::per_step_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StepStats {
+    /// The number of times a plan of the step was executed.
+    pub runs: usize,
+    /// The number of times the step passed.
+    pub passed: usize,
+    /// The number of times the step failed.
+    pub failed: usize,
+    /// The number of times the step was skipped, either before executing it or by its outcome.
+    pub skipped: usize,
+}
+
+/// Metrics describing how diverse the commands of a run were, see
+/// [`RunReport::diversity_metrics`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiversityMetrics {
+    /// The number of reported plans.
+    pub plans: usize,
+    /// The number of distinct commands divided by the number of plans, `0.0` without plans.
+    pub unique_command_ratio: f64,
+    /// The average Levenshtein distance, in characters, between the commands of consecutive
+    /// plans, `0.0` with less than two plans.
+    pub average_edit_distance: f64,
+    /// The percentage of plans reported under each step id.
+    pub category_coverage: HashMap<String, f64>,
+}
+
+/// A failing seed together with the number of steps needed to reproduce the failure.
+#[derive(Debug, Clone)]
+pub struct Reproducer {
+    /// The seed the failure was found with.
+    pub seed: u64,
+    /// The number of steps executed until the failure, including the failing step.
+    pub steps: usize,
+    /// The error the run failed with.
+    pub error: String,
+}
+
+/// Represents the outcome of running the same steps with many seeds.
+#[derive(Debug, Default)]
+pub struct SweepResult {
+    /// The seeds that passed.
+    pub passed: Vec<u64>,
+    /// The reproducers of the seeds that failed, in sweep order.
+    pub failures: Vec<Reproducer>,
+    minimal: Option<usize>,
+}
+
+impl RunReport {
+    /// Creates an empty report for a run with the given seed.
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            elapsed: Duration::ZERO,
+            steps: Vec::new(),
+            divergences: Vec::new(),
+        }
+    }
+
+    /// Returns the statistics of every reported step, keyed by step id.
+    ///
+    /// A step reported several times, because it has several plans or because the run has
+    /// several [iterations](crate::Runner::iterations), accumulates all its reports.
+    #[must_use]
+    pub fn per_step_stats(&self) -> HashMap<String, StepStats> {
+        let mut stats: HashMap<String, StepStats> = HashMap::new();
+        for step in &self.steps {
+            let entry = stats.entry(step.id.clone()).or_default();
+            if step.output.is_some() {
+                entry.runs += 1;
+            }
+            match step.status {
+                StepStatus::Passed => entry.passed += 1,
+                StepStatus::Failed => entry.failed += 1,
+                StepStatus::Skipped => entry.skipped += 1,
+            }
+        }
+        stats
+    }
+
+    /// Returns metrics describing how diverse the commands of the reported plans were. A low
+    /// diversity suggests increasing the randomness of the steps or the number of iterations.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::{RunReport, StepReport, StepStatus};
+    /// let mut report = RunReport::new(42);
+    /// for command in ["ls -a", "ls -a", "ls -l"] {
+    ///     report.steps.push(StepReport {
+    ///         id: "list".to_string(),
+    ///         command: command.to_string(),
+    ///         status: StepStatus::Passed,
+    ///         output: None,
+    ///         durations: Default::default(),
+    ///     });
+    /// }
+    /// let metrics = report.diversity_metrics();
+    /// assert!((metrics.unique_command_ratio - 2.0 / 3.0).abs() < f64::EPSILON);
+    /// assert!((metrics.average_edit_distance - 0.5).abs() < f64::EPSILON);
+    /// assert_eq!(metrics.category_coverage["list"], 100.0);
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn diversity_metrics(&self) -> DiversityMetrics {
+        let plans = self.steps.len();
+        if plans == 0 {
+            return DiversityMetrics::default();
+        }
+
+        let unique: HashSet<&str> = self
+            .steps
+            .iter()
+            .map(|step| step.command.as_str())
+            .collect();
+        let distances: Vec<usize> = self
+            .steps
+            .windows(2)
+            .map(|pair| edit_distance(&pair[0].command, &pair[1].command))
+            .collect();
+        let average_edit_distance = if distances.is_empty() {
+            0.0
+        } else {
+            distances.iter().sum::<usize>() as f64 / distances.len() as f64
+        };
+
+        let mut category_coverage: HashMap<String, f64> = HashMap::new();
+        for step in &self.steps {
+            *category_coverage.entry(step.id.clone()).or_default() += 100.0 / plans as f64;
+        }
+
+        DiversityMetrics {
+            plans,
+            unique_command_ratio: unique.len() as f64 / plans as f64,
+            average_edit_distance,
+            category_coverage,
+        }
+    }
+
+    /// Returns the phase durations of the most recently reported step.
+    pub(crate) fn last_durations(&mut self) -> Option<&mut PhaseDurations> {
+        self.steps.last_mut().map(|step| &mut step.durations)
+    }
+
+    /// Marks the most recently reported step as failed.
+    pub(crate) fn mark_last_failed(&mut self) {
+        if let Some(step) = self.steps.last_mut() {
+            step.status = StepStatus::Failed;
+        }
+    }
+}
+
+impl SweepResult {
+    /// Records a failing seed, keeping track of the reproducer with the fewest steps.
+    pub(crate) fn add_failure(&mut self, reproducer: Reproducer) {
+        let is_smaller = !matches!(
+            self.minimal_reproducer(),
+            Some(minimal) if minimal.steps <= reproducer.steps
+        );
+        if is_smaller {
+            self.minimal = Some(self.failures.len());
+        }
+        self.failures.push(reproducer);
+    }
+
+    /// Returns the failing reproducer with the fewest steps. When several reproducers have
+    /// the same length, the first one found is returned.
+    #[must_use]
+    pub fn minimal_reproducer(&self) -> Option<&Reproducer> {
+        self.minimal.map(|index| &self.failures[index])
+    }
+}
+
+impl StepReport {
+    /// Returns a short, stable hash identifying this failure.
+    ///
+    /// The hash is computed over the step id, the exit code and the stderr output with volatile
+    /// parts (every run of digits, such as timestamps, pids or durations) replaced by a
+    /// placeholder. Two failures that only differ in those parts share the same fingerprint.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::{executer::Output, StepReport, StepStatus};
+    /// let report = StepReport {
+    ///     id: "step".to_string(),
+    ///     command: "cmd".to_string(),
+    ///     status: StepStatus::Failed,
+    ///     output: Some(Output {
+    ///         status_code: Some(1),
+    ///         stdout: String::new(),
+    ///         stderr: "failed at 12:00:01".to_string(),
+    ///         ..Default::default()
+    ///     }),
+    ///     durations: Default::default(),
+    /// };
+    /// assert_eq!(report.fingerprint(), "282106727ce2d9f2");
+    /// ```
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        let stderr = self.output.as_ref().map_or("", |output| &output.stderr);
+        let status_code = self.output.as_ref().and_then(|output| output.status_code);
+        let signature = format!("{}\0{}\0{status_code:?}", self.id, normalize(stderr));
+
+        let hash = signature.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        });
+
+        format!("{hash:016x}")
+    }
+}
+
+/// Returns the Levenshtein distance between two strings, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Replaces every run of ASCII digits with `#` and trims surrounding whitespace.
+fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    let mut in_digits = false;
+
+    for ch in text.trim().chars() {
+        if ch.is_ascii_digit() {
+            if !in_digits {
+                normalized.push('#');
+            }
+            in_digits = true;
+        } else {
+            normalized.push(ch);
+            in_digits = false;
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn failure(stderr: &str, status_code: i32) -> StepReport {
+        StepReport {
+            id: "step".to_string(),
+            command: "cmd".to_string(),
+            status: StepStatus::Failed,
+            output: Some(Output {
+                status_code: Some(status_code),
+                stdout: String::new(),
+                stderr: stderr.to_string(),
+                ..Default::default()
+            }),
+            durations: PhaseDurations::default(),
+        }
+    }
+
+    #[test]
+    fn fingerprint_ignores_timestamps() {
+        let first = failure("2024-01-01T10:00:00Z error: file not found", 1);
+        let second = failure("2024-03-17T22:41:09Z error: file not found", 1);
+        assert_eq!(first.fingerprint(), second.fingerprint());
+        assert_eq!(first.fingerprint().len(), 16);
+    }
+
+    #[test]
+    fn fingerprint_distinguishes_failures() {
+        let failure_one = failure("error: file not found", 1);
+        assert_ne!(
+            failure_one.fingerprint(),
+            failure("error: permission denied", 1).fingerprint()
+        );
+        assert_ne!(
+            failure_one.fingerprint(),
+            failure("error: file not found", 2).fingerprint()
+        );
+    }
+
+    #[test]
+    fn diversity_metrics() {
+        let mut report = RunReport::new(42);
+        for (id, command) in [
+            ("add", "app add a"),
+            ("add", "app add a"),
+            ("add", "app add b"),
+            ("remove", "app rm a"),
+            ("add", "app add a"),
+        ] {
+            report.steps.push(StepReport {
+                id: id.to_string(),
+                command: command.to_string(),
+                status: StepStatus::Passed,
+                output: None,
+                durations: PhaseDurations::default(),
+            });
+        }
+
+        let metrics = report.diversity_metrics();
+        assert_eq!(metrics.plans, 5);
+        assert!((metrics.unique_command_ratio - 0.6).abs() < 1e-9);
+        // 0 + 1 + 4 + 3 edits between consecutive commands.
+        assert!((metrics.average_edit_distance - 2.0).abs() < 1e-9);
+        assert!((metrics.category_coverage["add"] - 80.0).abs() < 1e-9);
+        assert!((metrics.category_coverage["remove"] - 20.0).abs() < 1e-9);
+
+        assert_eq!(
+            RunReport::new(42).diversity_metrics(),
+            DiversityMetrics::default()
+        );
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
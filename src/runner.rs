@@ -4,20 +4,115 @@
 //! The `Runner` can also generate and display an execution plan for the steps to be taken.
 //! The steps can be randomized using the [`Randomizer`], enhancing the unpredictability of the execution.
 //!
+
+// The errors built here carry the full command output, which is larger than clippy's
+// threshold for error variants; it stays unboxed so the public error fields are plain `Output`s.
+#![allow(clippy::result_large_err)]
+
 use crate::{
-    executer,
+    executer::{self, Output},
+    export::{
+        ExecutionPlan, ExecutionPlanStep, ExportedPlan, ExportedStep, PlanFormat, PlanInputs,
+        EXPORT_VERSION,
+    },
     randomizer::Randomizer,
-    step::{self, StepTrait},
+    report::{
+        Divergence, PhaseDurations, Reproducer, RunReport, StepReport, StepStatus, SweepResult,
+    },
+    step::{self, Plan, RunContext, StepTrait},
     Error, Result,
 };
-use colored::Colorize;
-use std::time::Instant;
+use colored::{ColoredString, Colorize};
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// The default template of the command reproducing a run, see [`Runner::reproduce_command`].
+const DEFAULT_REPRODUCE_COMMAND: &str = "cargo run -- --seed {seed}";
+/// The placeholder replaced with the seed in the reproduce command template.
+const SEED_PLACEHOLDER: &str = "{seed}";
+
+/// The default of [`Runner::max_command_length`]. Commands are passed to the shell as a single
+/// argument, which Linux limits to 128 KiB (`MAX_ARG_STRLEN`), and `cmd.exe` limits its
+/// command line to 8191 characters.
+pub const DEFAULT_MAX_COMMAND_LENGTH: usize = if cfg!(windows) { 8191 } else { 128 * 1024 };
+
+/// A callback validating invariants that only hold across a whole run.
+type FinalAssertion = Box<dyn Fn(&RunReport) -> Result<()>>;
+
+/// A callback notified of a failed step and its fingerprint.
+type FailureCallback = Box<dyn Fn(&StepReport, &str)>;
+
+/// Observes the lifecycle of the steps of a run, e.g. to report progress to a dashboard or emit
+/// metrics to an external system, see [`Runner::observer`]. Every method does nothing by
+/// default.
+pub trait RunnerObserver {
+    /// Invoked before a step is executed, with the id of its first plan and its index in the
+    /// run. Retries of the step are part of the same execution.
+    fn on_step_start(&self, _id: &str, _index: usize) {}
+
+    /// Invoked after each plan of a step was executed, with its report holding the command,
+    /// the output and the status of the plan.
+    fn on_plan(&self, _report: &StepReport) {}
+
+    /// Invoked after the [check command](StepTrait::run_check) of a step ran.
+    fn on_check(&self, _id: &str, _command: &str, _output: &Output) {}
+
+    /// Invoked after the [test command](StepTrait::run_test) of a step ran.
+    fn on_test(&self, _id: &str, _command: &str, _output: &Output) {}
+
+    /// Invoked once a step finished, after its last attempt, with its overall status. Steps
+    /// skipped because a dependency did not succeed or their run condition was not met are
+    /// only reported here, as [`StepStatus::Skipped`].
+    fn on_step_finish(&self, _id: &str, _status: StepStatus) {}
+}
+
+/// The level of a line of runner output, mapped to the `tracing` levels with the `tracing`
+/// feature.
+#[derive(Debug, Clone, Copy)]
+enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
 
 /// A struct that orchestrates the execution of a series of steps.
+#[allow(clippy::struct_excessive_bools)]
 pub struct Runner {
     steps: Vec<Box<dyn StepTrait>>,
     init: Option<Box<dyn StepTrait>>,
     randomizer: Randomizer,
+    start_from: usize,
+    final_assertion: Option<FinalAssertion>,
+    workdir: Option<PathBuf>,
+    max_disk_bytes: Option<u64>,
+    max_command_length: usize,
+    warmup: Option<(Box<dyn StepTrait>, u32)>,
+    isolate_warmup: bool,
+    differential: Option<(String, String)>,
+    reproduce_command: String,
+    quiet: bool,
+    #[cfg(unix)]
+    resource_limits: Option<executer::RLimits>,
+    step_timeout: Option<Duration>,
+    priming_commands: Vec<String>,
+    fail_on_priming_error: bool,
+    on_new_failure: Option<FailureCallback>,
+    observers: Vec<Box<dyn RunnerObserver>>,
+    seen_fingerprints: RefCell<HashSet<String>>,
+    isolation_dir: Option<PathBuf>,
+    iterations: u32,
+    randomize_affinity: bool,
+    output: RefCell<Box<dyn Write>>,
+    continue_on_error: bool,
+    color: bool,
+    shuffle_steps: bool,
+    step_count_range: Option<(usize, usize)>,
 }
 
 /// Creates a new [`Runner`] instance with the given steps.
@@ -27,6 +122,32 @@ pub fn new(steps: Vec<Box<dyn StepTrait>>) -> Runner {
         steps,
         init: None,
         randomizer: Randomizer::default(),
+        start_from: 0,
+        final_assertion: None,
+        workdir: None,
+        max_disk_bytes: None,
+        max_command_length: DEFAULT_MAX_COMMAND_LENGTH,
+        warmup: None,
+        isolate_warmup: false,
+        differential: None,
+        reproduce_command: DEFAULT_REPRODUCE_COMMAND.to_string(),
+        quiet: false,
+        #[cfg(unix)]
+        resource_limits: None,
+        step_timeout: None,
+        priming_commands: Vec::new(),
+        fail_on_priming_error: true,
+        on_new_failure: None,
+        observers: Vec::new(),
+        seen_fingerprints: RefCell::default(),
+        isolation_dir: None,
+        iterations: 1,
+        randomize_affinity: false,
+        output: RefCell::new(Box::new(std::io::stdout())),
+        continue_on_error: false,
+        color: std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal(),
+        shuffle_steps: false,
+        step_count_range: None,
     }
 }
 
@@ -45,32 +166,370 @@ impl Runner {
         self
     }
 
+    /// Skips the first `index` steps and starts the execution from there.
+    ///
+    /// Skipped steps are still planned so the randomizer advances exactly as it would in a full
+    /// run, which keeps the commands of the remaining steps identical to a full run with the
+    /// same seed. Note that the skipped steps are not set up, so any state their `setup` or
+    /// command would have created must already exist. For the same reason, the skipped steps
+    /// satisfy the [`StepTrait::dependencies`] of the remaining ones.
+    #[must_use]
+    pub const fn start_from(mut self, index: usize) -> Self {
+        self.start_from = index;
+        self
+    }
+
+    /// Sets an assertion that is invoked with the [`RunReport`] once all the steps completed.
+    ///
+    /// This allows validating invariants that only hold across the entire run, such as the
+    /// total number of files created. When the assertion returns an error, the whole run fails
+    /// even if every individual step passed.
+    #[must_use]
+    pub fn final_assertion<F>(mut self, assertion: F) -> Self
+    where
+        F: Fn(&RunReport) -> Result<()> + 'static,
+    {
+        self.final_assertion = Some(Box::new(assertion));
+        self
+    }
+
+    /// Sets how many times [`Runner::run`] executes the steps. Defaults to `1`.
+    ///
+    /// The iterations continue with the same randomizer, so every iteration draws new values.
+    /// A failing iteration does not stop the following ones: the run fails with the first
+    /// error once all of them completed. The [`RunReport`] accumulates the steps of every
+    /// iteration, see [`RunReport::per_step_stats`].
+    #[must_use]
+    pub const fn iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Writes the human-readable output of the runner, such as the plan dump, the progress of
+    /// the steps and the summaries, to `sink` instead of stdout. The output of the executed
+    /// commands is captured and never printed.
+    ///
+    /// With the `tracing` feature, every line is also emitted as a `tracing` event, inside a
+    /// `step` span for each step. Set `std::io::sink()` to only emit the events.
+    #[must_use]
+    pub fn output_sink(mut self, sink: Box<dyn Write>) -> Self {
+        self.output = RefCell::new(sink);
+        self
+    }
+
+    /// Keeps executing the following steps when a step fails, instead of stopping at the
+    /// first failure. Defaults to `false`.
+    ///
+    /// A failing plan, check or test is logged and reported as failed, and the run fails with
+    /// the first of these failures once all the steps completed. This includes a command that
+    /// timed out, broke its resource limits or wrote outside of its allowed paths. Other
+    /// errors, such as a step that can not be planned, still stop the run. See
+    /// [`Runner::run_all`] to collect the outcome of every step instead.
+    #[must_use]
+    pub const fn continue_on_error(mut self, yes: bool) -> Self {
+        self.continue_on_error = yes;
+        self
+    }
+
+    /// Sets the working directory the steps operate in.
+    #[must_use]
+    pub fn workdir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.workdir = Some(path.into());
+        self
+    }
+
+    /// Resets the working directory to a fresh copy of `initial_dir` before each step, so
+    /// every step starts from the same state and steps can not interfere with each other.
+    ///
+    /// The working directory set with [`Runner::workdir`] is removed and the whole
+    /// `initial_dir` is copied into it before every executed step, so each step pays a cost
+    /// proportional to the size of `initial_dir`. The working directory is removed once all
+    /// the steps completed, but kept when a step fails so it can be inspected. Has no effect
+    /// unless a working directory is set.
+    #[must_use]
+    pub fn isolate_steps(mut self, initial_dir: impl Into<PathBuf>) -> Self {
+        self.isolation_dir = Some(initial_dir.into());
+        self
+    }
+
+    /// Aborts the run with [`Error::DiskBudgetExceeded`] once the total size of the working
+    /// directory exceeds `bytes`.
+    ///
+    /// The size is checked between steps, not while a command is running, so a single command
+    /// can still write past the budget before the run is aborted. Has no effect unless a
+    /// working directory is set with [`Runner::workdir`].
+    #[must_use]
+    pub const fn max_disk_bytes(mut self, bytes: u64) -> Self {
+        self.max_disk_bytes = Some(bytes);
+        self
+    }
+
+    /// Rejects any plan whose command is longer than `length` bytes with
+    /// [`Error::CommandTooLong`], before executing it. Defaults to
+    /// [`DEFAULT_MAX_COMMAND_LENGTH`].
+    ///
+    /// This catches runaway generation, e.g. an unbounded template expansion, with a clear
+    /// error instead of an obscure failure of the operating system.
+    #[must_use]
+    pub const fn max_command_length(mut self, length: usize) -> Self {
+        self.max_command_length = length;
+        self
+    }
+
+    /// Runs the given step `times` times before the steps of every run, e.g. to warm up caches.
+    ///
+    /// Warmup runs are set up and executed, but their outcome is ignored and they are not part
+    /// of the [`RunReport`]. Their plans are drawn from the runner's randomizer, so they advance
+    /// it and change the commands of the following steps, unless [`Runner::isolate_warmup`] is
+    /// enabled.
+    #[must_use]
+    pub fn warmup(mut self, step: Box<dyn StepTrait>, times: u32) -> Self {
+        self.warmup = Some((step, times));
+        self
+    }
+
+    /// Draws the warmup plans from a separate randomizer with the same seed, so the warmup does
+    /// not affect the commands of the following steps.
+    #[must_use]
+    pub const fn isolate_warmup(mut self, yes: bool) -> Self {
+        self.isolate_warmup = yes;
+        self
+    }
+
+    /// Runs every plan against two implementations of the same program and reports the plans
+    /// whose outputs diverge.
+    ///
+    /// In this mode a plan's command holds the program arguments: it is executed once as
+    /// `<program_a> <command>` and once as `<program_b> <command>`. The program and the arguments
+    /// of a [`Plan::with_argv`] plan are all passed verbatim. The outputs diverge when
+    /// their status codes or their normalized stdout (line endings and trailing whitespace
+    /// ignored) differ, in which case a [`Divergence`] is added to the [`RunReport`]. The
+    /// output of `program_a` is the one checked by the step and recorded in its report, while the
+    /// log reports the duration of both programs.
+    #[must_use]
+    pub fn differential(
+        mut self,
+        program_a: impl Into<String>,
+        program_b: impl Into<String>,
+    ) -> Self {
+        self.differential = Some((program_a.into(), program_b.into()));
+        self
+    }
+
+    /// Executes the steps in a random order instead of the order they were given in, to
+    /// discover sequences of steps that were not foreseen.
+    ///
+    /// The order is drawn with a [child](Randomizer::child) of the runner's randomizer, so the
+    /// same seed always gives the same order without changing any other generated value. The
+    /// [init step](Runner::init_step) is not part of the shuffled steps. [`Runner::dump_plan`]
+    /// and the exported plans list the steps in the shuffled order.
+    #[must_use]
+    pub const fn shuffle_steps(mut self, yes: bool) -> Self {
+        self.shuffle_steps = yes;
+        self
+    }
+
+    /// Executes a random number of steps, between `min` and `max` (inclusive), instead of all
+    /// of them, to explore command sequences of varied lengths. The count is capped to the
+    /// number of steps.
+    ///
+    /// The steps are taken from the start of the execution order, so combined with
+    /// [`Runner::shuffle_steps`] each run executes a random subsequence of the steps. The count
+    /// is drawn with a [child](Randomizer::child) of the runner's randomizer, so it is
+    /// reproducible from the seed without changing any other generated value. The
+    /// [init step](Runner::init_step) is not counted.
+    ///
+    /// # Panics
+    ///
+    /// When `min` is greater than `max`.
+    #[must_use]
+    pub fn random_step_count(mut self, min: usize, max: usize) -> Self {
+        assert!(min <= max, "minimum must not exceed maximum");
+        self.step_count_range = Some((min, max));
+        self
+    }
+
+    /// Pins the commands of every step to a random, non-empty subset of the available CPUs,
+    /// e.g. to reproduce timing-sensitive bugs that only show on some cores.
+    ///
+    /// The CPUs of each step are chosen with a [child](Randomizer::child) of the runner's
+    /// randomizer named after the step index, so they are reproducible from the seed without
+    /// changing any other generated value. Only supported on Linux, elsewhere the commands run
+    /// on any CPU.
+    #[must_use]
+    pub const fn randomize_affinity(mut self, yes: bool) -> Self {
+        self.randomize_affinity = yes;
+        self
+    }
+
+    /// Runs every plan, check and test command under the given resource limits, so a pathological
+    /// input is killed instead of exhausting the machine. A command killed for exceeding the
+    /// limits fails the run with [`Error::ResourceLimitExceeded`], see [`executer::RLimits`]
+    /// for how a signal is attributed to the limits.
+    #[cfg(unix)]
+    #[must_use]
+    pub const fn resource_limits(mut self, limits: executer::RLimits) -> Self {
+        self.resource_limits = Some(limits);
+        self
+    }
+
+    /// Kills any command running longer than `timeout`, together with the processes it spawned
+    /// on Unix, so a hanging program does not block the run forever. A killed command fails the
+    /// run with [`Error::Timeout`], which holds the output captured before the kill. A killed
+    /// plan is reported as failed with that output, and like any failing plan it is retried and
+    /// tolerated by [`Runner::continue_on_error`] and [`Runner::run_all`].
+    #[must_use]
+    pub const fn step_timeout(mut self, timeout: Duration) -> Self {
+        self.step_timeout = Some(timeout);
+        self
+    }
+
+    /// Runs the given commands, in order, before the steps of every run, so known edge cases
+    /// are always covered alongside the randomized steps.
+    ///
+    /// Priming commands run after the warmup and are not part of the [`RunReport`]. A priming
+    /// command exiting with a non-zero status code fails the run before any step is executed,
+    /// unless disabled with [`Runner::fail_on_priming_error`].
+    #[must_use]
+    pub fn priming_commands(mut self, commands: Vec<String>) -> Self {
+        self.priming_commands = commands;
+        self
+    }
+
+    /// Sets whether a priming command exiting with a non-zero status code fails the run.
+    /// Defaults to `true`.
+    #[must_use]
+    pub const fn fail_on_priming_error(mut self, yes: bool) -> Self {
+        self.fail_on_priming_error = yes;
+        self
+    }
+
+    /// Sets a callback invoked the first time each distinct failure is found, with the failed
+    /// [`StepReport`] and its [`StepReport::fingerprint`].
+    ///
+    /// Fingerprints are remembered across every [`Runner::run`] and [`Runner::sweep`] of this
+    /// runner, so a failure that repeats, even with different timestamps or other digits in its
+    /// stderr, is only notified once.
+    #[must_use]
+    pub fn on_new_failure<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&StepReport, &str) + 'static,
+    {
+        self.on_new_failure = Some(Box::new(callback));
+        self
+    }
+
+    /// Adds an observer notified as the steps of each run start and finish, and as their plans,
+    /// checks and tests are executed. Observers are notified in the order they were added.
+    #[must_use]
+    pub fn observer(mut self, observer: Box<dyn RunnerObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Sets the template of the command printed in the banner to reproduce a run. Every
+    /// `{seed}` placeholder is replaced with the seed of the run.
+    ///
+    /// Defaults to `cargo run -- --seed {seed}`.
+    #[must_use]
+    pub fn reproduce_command(mut self, template: impl Into<String>) -> Self {
+        self.reproduce_command = template.into();
+        self
+    }
+
+    /// Enables or disables ANSI colors and styles in the output of the runner, including the
+    /// strings returned by [`Runner::banner`] and [`Runner::dump_plan`].
+    ///
+    /// Defaults to enabled only when stdout is a terminal and the `NO_COLOR` environment
+    /// variable is not set, so output captured to a file or a CI log is plain text. Enabled
+    /// colors are rendered with the `colored` crate and remain subject to its global override.
+    #[must_use]
+    pub const fn color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
+    }
+
+    /// Suppresses the banner printed when a run starts.
+    #[must_use]
+    pub const fn quiet(mut self, yes: bool) -> Self {
+        self.quiet = yes;
+        self
+    }
+
+    /// Returns the banner printed when a run starts, showing the seed and the command
+    /// reproducing the run.
+    #[must_use]
+    pub fn banner(&self) -> String {
+        let seed = self.randomizer.seed;
+        let command = self
+            .reproduce_command
+            .replace(SEED_PLACEHOLDER, &seed.to_string());
+
+        [
+            "====================================".to_string(),
+            format!("{}: {seed}", self.paint("Seed".bold())),
+            format!("{}: {command}", self.paint("Reproduce with".bold())),
+            "====================================".to_string(),
+        ]
+        .join("\n")
+    }
+
     // Dumps the execution plan for the steps to be executed.
     ///
+    /// The plans are drawn from a copy of the randomizer restarted from its seed, so dumping
+    /// never changes what a following [`Runner::run`] executes, and shows exactly its commands.
+    ///
     /// # Errors
     ///
     /// when could not present the plan
     pub fn dump_plan(&self) -> Result<String> {
+        let randomizer = self.preview_randomizer()?;
         let mut output: Vec<String> = Vec::new();
 
         output.push("====================================".to_string());
-        output.push("          Execution Plan Dump        ".green().to_string());
+        output.push(self.paint("          Execution Plan Dump        ".green()));
         output.push("====================================".to_string());
-        output.push(format!("{}: {}", "Step Count".bold(), &self.steps.len()));
-        output.push(format!("{}: {}", "Seed".bold(), &self.randomizer.seed));
+        output.push(format!(
+            "{}: {}",
+            self.paint("Step Count".bold()),
+            self.ordered_steps().len()
+        ));
+        output.push(format!(
+            "{}: {}",
+            self.paint("Seed".bold()),
+            &self.randomizer.seed
+        ));
         output.push("------------------------------------".to_string());
 
-        for (i, step) in self.steps.iter().enumerate() {
-            let execution_plan = step.plan(&self.randomizer)?;
-            output.push(
-                format!("Step {}: {}", i + 1, execution_plan.id)
-                    .green()
-                    .to_string(),
-            );
+        for (i, step) in self.ordered_steps().into_iter().enumerate() {
+            let execution_plans = step.plans(&randomizer)?;
+            let step_id = execution_plans
+                .first()
+                .map_or_else(String::new, |plan| plan.id.clone());
+            output.push(self.paint(format!("Step {}: {step_id}", i + 1).green()));
             output.push("------------------------------------".to_string());
-            output.push("Command:".bold().to_string());
-            output.push(execution_plan.command.clone());
-            output.push("State:".bold().to_string());
+            output.push(self.paint("Command:".bold()));
+            for execution_plan in &execution_plans {
+                output.push(execution_plan.command.clone());
+            }
+            for stdin in execution_plans
+                .iter()
+                .filter_map(|plan| plan.stdin.as_ref())
+            {
+                output.push(self.paint("Stdin:".bold()));
+                output.push(stdin.clone());
+            }
+            for execution_plan in execution_plans.iter().filter(|plan| !plan.env.is_empty()) {
+                let mut env: Vec<_> = execution_plan.env.iter().collect();
+                env.sort();
+                output.push(self.paint("Env:".bold()));
+                output.extend(env.into_iter().map(|(key, value)| format!("{key}={value}")));
+            }
+            for cwd in execution_plans.iter().filter_map(|plan| plan.cwd.as_ref()) {
+                output.push(self.paint("Working directory:".bold()));
+                output.push(cwd.display().to_string());
+            }
+            output.push(self.paint("State:".bold()));
             output.push("---".to_string());
 
             let state = serde_yaml::to_string(&step.to_yaml()).unwrap_or_default();
@@ -82,181 +541,3380 @@ impl Runner {
         Ok(output.join("\n"))
     }
 
-    /// Executes the steps in the runner.
+    /// Dumps the execution plan for the steps to be executed as JSON, see [`ExecutionPlan`].
+    ///
+    /// This is the machine-readable counterpart of [`Runner::dump_plan`], holding the same
+    /// seed, commands and step states without any formatting.
     ///
     /// # Errors
-    /// On the first step that fails
-    pub fn run(&self) -> Result<()> {
-        println!("{}", self.dump_plan()?);
-        for step in &self.steps {
-            let step_plan = step.plan(&self.randomizer)?;
-
-            println!();
-            println!("{}", format!("Run step: {}", step_plan.id).yellow());
-            println!();
-
-            step.setup()?;
-            let start = Instant::now();
-            println!("{}", "Execute plan...".yellow());
-            let result = step.plan(&self.randomizer)?.execute()?;
-            println!(
-                "{}",
-                format!("Execute plan finished in {:?}", start.elapsed()).yellow()
-            );
-            let is_success =
-                step.is_success(&result, &step_plan.ctx)
-                    .map_err(|err| Error::StepError {
-                        kind: step::Kind::Plan,
-                        description: err.to_string(),
-                        command_output: result,
-                    })?;
-
-            if !is_success {
-                continue;
-            }
-
-            if let Some(check_command) = step.run_check() {
-                let start = Instant::now();
-                println!("{}", "Execute check...".yellow());
-                let output = executer::run_sh(&check_command)?;
-                println!(
-                    "{}",
-                    format!("Execute check finished in {:?}", start.elapsed()).yellow()
-                );
-                if output.status_code != Some(0) {
-                    return Err(Error::StepError {
-                        kind: step::Kind::Check,
-                        description: "check not finish with status code 0".to_string(),
-                        command_output: output,
-                    });
-                }
-            }
-
-            if let Some(test_command) = step.run_test() {
-                let start = Instant::now();
-                println!("{}", "Execute test...".yellow());
-                let output = executer::run_sh(&test_command)?;
-                println!(
-                    "{}",
-                    format!("Execute tests finished in {:?}", start.elapsed()).yellow()
-                );
-                if output.status_code != Some(0) {
-                    return Err(Error::StepError {
-                        kind: step::Kind::Test,
-                        description: "test command not finish with status code 0".to_string(),
-                        command_output: output,
-                    });
-                }
-            }
-        }
+    ///
+    /// when could not prepare the plans or serialize the document
+    pub fn dump_plan_json(&self) -> Result<String> {
+        self.execution_plan()?.to_json()
+    }
 
-        println!("{}", "Execution plan is pass successfully".green());
+    /// Saves the seed of the runner together with the commands, their stdin, environment
+    /// variables and working directory, and the state of every step to `path`, as JSON when
+    /// the file has a `.json` extension and as YAML otherwise. The saved commands are the ones
+    /// a [`Runner::run`] executes. The run can be replayed with [`Runner::from_plan`] or
+    /// [`Runner::load_plan`].
+    ///
+    /// # Errors
+    ///
+    /// when could not prepare the plans, serialize them or write the file
+    pub fn save_plan(&self, path: &Path) -> Result<()> {
+        let plan = self.execution_plan()?;
+        std::fs::write(path, plan.to_document(PlanFormat::from_path(path))?)?;
         Ok(())
     }
-}
-
-#[cfg(test)]
-mod tests {
-
-    use std::{collections::HashMap, path::PathBuf};
 
-    use serde::{Deserialize, Serialize};
-    use step::PlanCtx;
+    /// Creates a runner replaying a plan saved with [`Runner::save_plan`], see
+    /// [`Runner::load_plan`]. The runner and its randomizer have the default configuration;
+    /// use [`Runner::load_plan`] on a configured runner when the run depends on it.
+    ///
+    /// # Errors
+    ///
+    /// The same as [`Runner::load_plan`].
+    pub fn from_plan(path: &Path, steps: Vec<Box<dyn StepTrait>>) -> Result<Self> {
+        new(steps).load_plan(path)
+    }
 
-    use super::*;
-    use crate::{executer::Output, generator::StringDef, step::Plan};
+    /// Prepares the runner to replay a plan saved with [`Runner::save_plan`]: the randomizer
+    /// is reset to the saved seed, keeping its configuration, and the saved step order is
+    /// applied, so running the steps executes the identical command sequence with the same
+    /// stdin, environment variables and working directories. The other settings of the runner
+    /// are kept.
+    ///
+    /// # Errors
+    ///
+    /// - When the file could not be read or is not a valid plan.
+    /// - When the steps do not produce the saved commands and inputs from the saved seed, e.g.
+    ///   because they changed since the plan was saved, reported as [`Error::PlanMismatch`].
+    pub fn load_plan(mut self, path: &Path) -> Result<Self> {
+        let document = std::fs::read_to_string(path)?;
+        let saved = ExecutionPlan::from_document(&document, PlanFormat::from_path(path))?;
 
-    #[derive(Serialize, Deserialize)]
-    struct TestStepOne {
-        location: PathBuf,
-    }
+        self.randomizer.reset(saved.seed);
+        self.shuffle_steps = saved.shuffled;
+        self.step_count_range = saved.step_count_range;
+        let replayed = self.execution_plan()?;
+        let mismatch = (0..saved.steps.len().max(replayed.steps.len())).find(|&index| {
+            let saved = saved.steps.get(index);
+            let replayed = replayed.steps.get(index);
+            saved.map(|step| (&step.commands, &step.inputs))
+                != replayed.map(|step| (&step.commands, &step.inputs))
+        });
+        if let Some(index) = mismatch {
+            return Err(Error::PlanMismatch { index });
+        }
 
-    #[derive(Serialize, Deserialize)]
-    struct TestStepTwo {
-        location: PathBuf,
+        Ok(self)
     }
 
-    impl StepTrait for TestStepOne {
-        fn setup(&self) -> crate::errors::Result<()> {
-            Ok(std::fs::create_dir_all(&self.location)?)
+    /// Returns the steps that are executed, in execution order, see [`Runner::shuffle_steps`]
+    /// and [`Runner::random_step_count`].
+    fn ordered_steps(&self) -> Vec<&dyn StepTrait> {
+        let mut order: Vec<usize> = (0..self.steps.len()).collect();
+        if self.shuffle_steps {
+            order = self.randomizer.child("steps").shuffle(&order);
         }
-
-        fn plan(&self, randomizer: &Randomizer) -> Result<Plan> {
-            let eco_string = randomizer.string(StringDef::default()).to_string();
-            Ok(Plan::with_vars::<Self>(
-                format!(
-                    "echo {eco_string} >> {}",
-                    self.location.join("test.txt").display()
-                ),
-                HashMap::from([("foo".to_string(), "bar".to_string())]),
-            ))
+        if let Some((min, max)) = self.step_count_range {
+            let count = self
+                .randomizer
+                .child("step_count")
+                .number_between_u64(min as u64, max as u64);
+            order.truncate(usize::try_from(count).unwrap_or(usize::MAX));
         }
+        order
+            .into_iter()
+            .map(|index| self.steps[index].as_ref())
+            .collect()
+    }
 
-        fn is_success(
-            &self,
-            execution_result: &Output,
-            plan_ctx: &PlanCtx,
-        ) -> Result<bool, &'static str> {
-            if let Some(foo_var) = plan_ctx.vars.get("foo") {
-                if foo_var != "bar" {
-                    return Err("foo value should be equal to var");
+    /// Returns a copy of the randomizer restarted from its seed, advanced past the draws of
+    /// the warmup when it shares the randomizer of the run, so it draws the plans a
+    /// [`Runner::run`] executes without advancing the randomizer of the runner.
+    fn preview_randomizer(&self) -> Result<Randomizer> {
+        let randomizer = self.randomizer.restarted();
+        if let Some((step, times)) = &self.warmup {
+            if !self.isolate_warmup {
+                for _ in 0..*times {
+                    step.plan(&randomizer)?;
                 }
-            } else {
-                return Err("foo plan ctx var not found");
-            };
-
-            if execution_result.status_code == Some(0) {
-                Ok(true)
-            } else {
-                Err("status code should be 0")
             }
         }
+        Ok(randomizer)
+    }
 
-        fn run_check(&self) -> Option<String> {
-            Some(format!(
-                "test -f {}",
-                self.location.join("test.txt").display()
-            ))
+    /// Builds the [`ExecutionPlan`] of the steps, drawing the plans a [`Runner::run`]
+    /// executes.
+    fn execution_plan(&self) -> Result<ExecutionPlan> {
+        let randomizer = self.preview_randomizer()?;
+        let mut steps = Vec::with_capacity(self.steps.len());
+        for step in self.ordered_steps() {
+            let plans = step.plans(&randomizer)?;
+            steps.push(ExecutionPlanStep {
+                id: plans
+                    .first()
+                    .map_or_else(String::new, |plan| plan.id.clone()),
+                inputs: plans
+                    .iter()
+                    .map(|plan| PlanInputs {
+                        stdin: plan.stdin.clone(),
+                        env: plan.env.clone().into_iter().collect(),
+                        cwd: plan.cwd.clone(),
+                    })
+                    .collect(),
+                commands: plans.into_iter().map(|plan| plan.command).collect(),
+                state: serde_json::to_value(step.to_yaml())?,
+            });
         }
 
-        fn run_test(&self) -> Option<String> {
-            Some(format!(
-                "test -f {}",
-                self.location.join("test.txt").display()
-            ))
+        Ok(ExecutionPlan {
+            seed: self.randomizer.seed,
+            step_count: steps.len(),
+            shuffled: self.shuffle_steps,
+            step_count_range: self.step_count_range,
+            steps,
+        })
+    }
+
+    /// Exports the fully materialized plan of a run with the given seed to a portable
+    /// document, so it can be executed by another tool. See [`ExportedPlan`] for the schema.
+    ///
+    /// The randomizer of the runner is reset to `seed`, so a following [`Runner::run`] executes
+    /// exactly the exported commands.
+    ///
+    /// # Errors
+    ///
+    /// when could not prepare the plans or serialize the document
+    pub fn export_plan(&mut self, seed: u64, format: PlanFormat) -> Result<String> {
+        self.randomizer.reset(seed);
+        let randomizer = self.preview_randomizer()?;
+
+        let mut steps = Vec::with_capacity(self.steps.len());
+        for step in self.ordered_steps() {
+            let plans = step.plans(&randomizer)?;
+            steps.push(ExportedStep {
+                id: plans
+                    .first()
+                    .map_or_else(String::new, |plan| plan.id.clone()),
+                commands: plans.into_iter().map(|plan| plan.command).collect(),
+                expected_status: step.status_semantics().into_iter().collect(),
+                check: step.run_check(),
+                test: step.run_test(),
+            });
         }
 
-        fn to_yaml(&self) -> serde_yaml::Value {
-            serde_yaml::to_value(self).expect("serialize")
+        ExportedPlan {
+            version: EXPORT_VERSION,
+            seed,
+            steps,
         }
+        .to_document(format)
     }
 
-    impl StepTrait for TestStepTwo {
-        fn setup(&self) -> crate::errors::Result<()> {
-            Ok(std::fs::create_dir_all(&self.location)?)
-        }
+    /// Describes the steps and their [`StepTrait::dependencies`] as a Graphviz DOT digraph,
+    /// with an edge from each dependency to the step depending on it.
+    ///
+    /// Nodes are labeled with the id of the first plan of the step, and a dependency matches a
+    /// step when it names any of the step's plans. Edges that are part of a cycle, and the
+    /// steps on them, are colored red. Dependencies that match no step are drawn as dashed
+    /// nodes. The plans are drawn from a copy of the randomizer, so the runner's randomizer
+    /// is not advanced.
+    ///
+    /// # Errors
+    ///
+    /// when could not prepare the plans
+    pub fn dependency_dot(&self) -> Result<String> {
+        let randomizer = self.preview_randomizer()?;
+        let plan_ids = self
+            .steps
+            .iter()
+            .map(|step| {
+                Ok(step
+                    .plans(&randomizer)?
+                    .into_iter()
+                    .map(|plan| plan.id)
+                    .collect::<Vec<_>>())
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        fn plan(&self, randomizer: &Randomizer) -> Result<Plan> {
-            let eco_string = randomizer.string(StringDef::default()).to_string();
-            Ok(Plan::with_vars::<Self>(
-                format!(
-                    "cat {eco_string} >> {}",
-                    self.location.join("test.txt").display()
-                ),
-                HashMap::from([("foo".to_string(), "bar".to_string())]),
-            ))
+        let mut edges = Vec::new();
+        let mut missing = Vec::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            for dependency in step.dependencies() {
+                let sources: Vec<usize> = plan_ids
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, ids)| ids.contains(&dependency))
+                    .map(|(source, _)| source)
+                    .collect();
+                if sources.is_empty() {
+                    missing.push((dependency, index));
+                }
+                edges.extend(sources.into_iter().map(|source| (source, index)));
+            }
         }
 
-        fn is_success(
+        let in_cycle: Vec<bool> = edges
+            .iter()
+            .map(|&(source, target)| reaches(&edges, target, source))
+            .collect();
+
+        let mut output = vec!["digraph steps {".to_string()];
+        for (index, ids) in plan_ids.iter().enumerate() {
+            let id = ids.first().map_or("", String::as_str);
+            let on_cycle = edges
+                .iter()
+                .zip(&in_cycle)
+                .any(|(&(source, target), &cycle)| cycle && (source == index || target == index));
+            let color = if on_cycle { ", color=red" } else { "" };
+            output.push(format!("  step{index} [label={}{color}];", dot_quote(id)));
+        }
+        for (&(source, target), &cycle) in edges.iter().zip(&in_cycle) {
+            let color = if cycle { " [color=red]" } else { "" };
+            output.push(format!("  step{source} -> step{target}{color};"));
+        }
+        for (dependency, target) in missing {
+            let node = dot_quote(&format!("missing: {dependency}"));
+            output.push(format!("  {node} [style=dashed];"));
+            output.push(format!("  {node} -> step{target} [style=dashed];"));
+        }
+        output.push("}".to_string());
+
+        Ok(output.join("\n"))
+    }
+
+    /// Returns a clone of the steps of the runner, e.g. to run them again in another runner.
+    /// The clones are independent of the original steps, see [`step::StepClone`].
+    #[must_use]
+    pub fn clone_steps(&self) -> Vec<Box<dyn StepTrait>> {
+        self.steps.clone()
+    }
+
+    /// Executes the steps in the runner.
+    ///
+    /// Every run starts the random sequence over from the seed of the randomizer, so it
+    /// executes the commands shown by [`Runner::dump_plan`] and is reproduced by any run with
+    /// the seed printed in the banner. Each step is planned once and that plan is the one
+    /// executed. Seeds recorded before this behavior, when the executed plans were drawn after
+    /// the dump and each step was planned twice, give different commands, see the changelog.
+    ///
+    /// # Errors
+    /// On the first step that fails
+    pub fn run(&self) -> Result<()> {
+        let (report, outcome) = self.execute_run()?;
+        outcome?;
+        self.assert_final(&report)?;
+
+        self.log(Level::Info, "Execution plan is pass successfully".green());
+        Ok(())
+    }
+
+    /// Executes the steps like [`Runner::run`] and returns the report of every executed plan,
+    /// with failed steps reported as [`StepStatus::Failed`] instead of as an error.
+    ///
+    /// Combined with [`Runner::continue_on_error`], every step runs and all the failures are
+    /// returned. Otherwise the reports stop at the first failed step.
+    ///
+    /// # Errors
+    ///
+    /// On errors other than a failing, timed out or killed plan, check or test, e.g. when a step
+    /// can not be planned or a priming command fails, and when the final assertion fails.
+    pub fn run_all(&self) -> Result<Vec<StepReport>> {
+        self.run_report().map(|report| report.steps)
+    }
+
+    /// Executes the steps like [`Runner::run_all`] and returns the whole [`RunReport`], with the
+    /// seed, the elapsed time and the phase durations and output of every executed plan. The
+    /// report is serializable, e.g. to write it to disk or convert it to another format.
+    ///
+    /// The final assertion, if any, is invoked with the report once the steps ran.
+    ///
+    /// # Errors
+    ///
+    /// On errors other than a failing, timed out or killed plan, check or test, e.g. when a step
+    /// can not be planned or a priming command fails, and when the final assertion fails.
+    pub fn run_report(&self) -> Result<RunReport> {
+        let (report, outcome) = self.execute_run()?;
+        match outcome {
+            Err(err) if !is_step_failure(&err) => return Err(err),
+            _ => {}
+        }
+        self.assert_final(&report)?;
+        Ok(report)
+    }
+
+    /// Plans every step and prints the resolved commands together with the check and test
+    /// commands of the step, without running anything: neither the setup, the plans, the checks
+    /// nor the tests of the steps are executed, and neither are the warmup and priming
+    /// commands. This allows auditing destructive commands before running them.
+    ///
+    /// The printed commands are the ones a following [`Runner::run`] executes, and the
+    /// randomizer of the runner is not advanced.
+    ///
+    /// # Errors
+    ///
+    /// when could not prepare the plans
+    pub fn dry_run(&self) -> Result<()> {
+        if !self.quiet {
+            self.log(Level::Info, self.banner());
+        }
+
+        let randomizer = self.preview_randomizer()?;
+        for (index, step) in self.ordered_steps().into_iter().enumerate() {
+            let step_plans = step.plans(&randomizer)?;
+            let step_id = step_plans
+                .first()
+                .map_or_else(String::new, |plan| plan.id.clone());
+            self.log(
+                Level::Info,
+                format!("Step {}: {step_id}", index + 1).green(),
+            );
+            self.log(Level::Info, "Command:".bold());
+            for step_plan in &step_plans {
+                self.log(Level::Info, step_plan.command.as_str());
+            }
+            if let Some(check_command) = step.run_check() {
+                self.log(Level::Info, "Check:".bold());
+                self.log(Level::Info, check_command);
+            }
+            if let Some(test_command) = step.run_test() {
+                self.log(Level::Info, "Test:".bold());
+                self.log(Level::Info, test_command);
+            }
+        }
+
+        self.log(
+            Level::Info,
+            "Dry run finished, nothing was executed".green(),
+        );
+        Ok(())
+    }
+
+    /// Prints the banner and the plan, then executes every iteration. Returns the report along
+    /// with the outcome of the execution, so callers decide which errors fail the run.
+    fn execute_run(&self) -> Result<(RunReport, Result<()>)> {
+        if !self.quiet {
+            self.log(Level::Info, self.banner());
+        }
+        self.log(Level::Debug, self.dump_plan()?);
+        self.randomizer.reseed(self.randomizer.seed);
+        let started = Instant::now();
+        let mut report = RunReport::new(self.randomizer.seed);
+        let outcome = self.execute_iterations(&mut report);
+        report.elapsed = started.elapsed();
+        self.notify_new_failures(&report);
+        Ok((report, outcome))
+    }
+
+    /// Runs the steps once for every given seed and collects the failing seeds.
+    ///
+    /// The reproducer of a failing seed is the prefix of the plan up to and including the
+    /// failing step, since the steps after it are never needed to reproduce the failure. Across
+    /// the sweep, the reproducer with the fewest executed steps is kept as the minimal one and
+    /// reported at the end.
+    ///
+    /// Every seed draws the same sequence as [`Runner::run`] with a randomizer of that seed, so
+    /// a failing seed is reproduced by a run with it. The seed of the randomizer is restored
+    /// once the sweep finished.
+    pub fn sweep<I>(&mut self, seeds: I) -> SweepResult
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let mut result = SweepResult::default();
+        let original_seed = self.randomizer.seed;
+
+        for seed in seeds {
+            self.randomizer.reset(seed);
+            let mut report = RunReport::new(seed);
+            let outcome = self.execute_iterations(&mut report);
+            self.notify_new_failures(&report);
+            let outcome = outcome.and_then(|()| self.assert_final(&report));
+
+            if let Err(err) = outcome {
+                result.add_failure(Reproducer {
+                    seed,
+                    steps: report.steps.len(),
+                    error: err.to_string(),
+                });
+            } else {
+                result.passed.push(seed);
+            }
+        }
+        self.randomizer.reset(original_seed);
+
+        self.log(
+            Level::Info,
+            format!(
+                "Sweep finished: {} passed, {} failed",
+                result.passed.len(),
+                result.failures.len()
+            )
+            .yellow(),
+        );
+        if let Some(reproducer) = result.minimal_reproducer() {
+            self.log(
+                Level::Error,
+                format!(
+                    "Minimal reproducer: seed {} with {} steps",
+                    reproducer.seed, reproducer.steps
+                )
+                .red(),
+            );
+        }
+
+        result
+    }
+
+    /// Invokes the new failure callback, if any, for the failed steps of the report whose
+    /// fingerprint was not seen before.
+    fn notify_new_failures(&self, report: &RunReport) {
+        let Some(callback) = &self.on_new_failure else {
+            return;
+        };
+
+        for step in &report.steps {
+            if step.status != StepStatus::Failed {
+                continue;
+            }
+            let fingerprint = step.fingerprint();
+            if self
+                .seen_fingerprints
+                .borrow_mut()
+                .insert(fingerprint.clone())
+            {
+                callback(step, &fingerprint);
+            }
+        }
+    }
+
+    /// Invokes the final assertion, if any, with the report of a completed run.
+    fn assert_final(&self, report: &RunReport) -> Result<()> {
+        self.final_assertion
+            .as_ref()
+            .map_or(Ok(()), |assertion| assertion(report))
+    }
+
+    /// Runs the priming commands, without reporting them.
+    fn run_priming(&self) -> Result<()> {
+        for command in &self.priming_commands {
+            self.log(Level::Info, format!("Priming: {command}").yellow());
+            let output =
+                executer::run_sh_with(command, &self.exec_options(None)).map_err(|err| {
+                    // A priming command breaking its limits fails the run, not a step.
+                    match command_output(&err).cloned() {
+                        Some(command_output) => Error::StepError {
+                            kind: step::Kind::Priming,
+                            description: err.to_string(),
+                            command_output,
+                        },
+                        None => err,
+                    }
+                })?;
+            if output.status_code != Some(0) && self.fail_on_priming_error {
+                return Err(Error::StepError {
+                    kind: step::Kind::Priming,
+                    description: "priming command not finish with status code 0".to_string(),
+                    command_output: output,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the warmup step, if any, without reporting it.
+    fn run_warmup(&self) -> Result<()> {
+        let Some((step, times)) = &self.warmup else {
+            return Ok(());
+        };
+
+        let isolated;
+        let randomizer = if self.isolate_warmup {
+            isolated = Randomizer::with_seed(self.randomizer.seed);
+            &isolated
+        } else {
+            &self.randomizer
+        };
+
+        for attempt in 1..=*times {
+            let step_plan = step.plan(randomizer)?;
+            self.log(
+                Level::Info,
+                format!("Warmup {attempt}/{times}: {}", step_plan.id).yellow(),
+            );
+            let outcome = step.setup().and_then(|()| step_plan.execute());
+            let teardown = step.teardown();
+            outcome?;
+            teardown?;
+        }
+        Ok(())
+    }
+
+    /// Executes the plan against both programs of a differential run, recording a
+    /// [`Divergence`] when their outputs differ, and returns the output of `program_a`.
+    fn execute_differential(
+        &self,
+        plan: &Plan,
+        program_a: &str,
+        program_b: &str,
+        options: &executer::ExecOptions,
+        report: &mut RunReport,
+    ) -> Result<Output> {
+        let options = plan.exec_options(options);
+        let arguments = if plan.argv.is_empty() {
+            plan.command.clone()
+        } else {
+            plan.argv
+                .iter()
+                .map(|arg| executer::quote(arg))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let output_a = executer::run_sh_with(&format!("{program_a} {arguments}"), &options)?;
+        let output_b = executer::run_sh_with(&format!("{program_b} {arguments}"), &options)?;
+        self.log(
+            Level::Info,
+            format!(
+                "Execute plan finished in {:?} with {program_a} and {:?} with {program_b}",
+                output_a.duration, output_b.duration
+            )
+            .yellow(),
+        );
+
+        if Divergence::between(&output_a, &output_b) {
+            let divergence = Divergence {
+                id: plan.id.clone(),
+                command: plan.command.clone(),
+                output_a: output_a.clone(),
+                output_b,
+            };
+            self.log(Level::Error, divergence.to_string().red());
+            report.divergences.push(divergence);
+        }
+
+        Ok(output_a)
+    }
+
+    /// Executes the plan while tracing the paths it writes to, and fails when any of them is
+    /// outside of `allowed_paths`.
+    #[cfg(all(target_os = "linux", feature = "trace-paths"))]
+    fn execute_scoped(
+        &self,
+        plan: &Plan,
+        allowed_paths: &[PathBuf],
+        options: &executer::ExecOptions,
+    ) -> Result<Output> {
+        let options = plan.exec_options(options);
+        let (output, written_paths) = match plan.argv.split_first() {
+            Some((program, args)) => executer::run_argv_traced(program, args, &options)?,
+            None => executer::run_sh_traced(&plan.command, &options)?,
+        };
+
+        let Some(written_paths) = written_paths else {
+            self.log(
+                Level::Warn,
+                "strace is not available, allowed paths are not enforced".yellow(),
+            );
+            return Ok(output);
+        };
+
+        let allowed_paths: Vec<PathBuf> = allowed_paths
+            .iter()
+            .map(|allowed| crate::trace::normalize(allowed))
+            .collect();
+        let outside = written_paths.into_iter().find(|path| {
+            path != Path::new("/dev/null")
+                && !allowed_paths
+                    .iter()
+                    .any(|allowed| path.starts_with(allowed))
+        });
+        if let Some(path) = outside {
+            return Err(Error::AccessOutsideScope {
+                path,
+                command_output: output,
+            });
+        }
+        Ok(output)
+    }
+
+    /// Executes the plan without enforcing `allowed_paths`, since tracing is not available.
+    #[cfg(not(all(target_os = "linux", feature = "trace-paths")))]
+    fn execute_scoped(
+        &self,
+        plan: &Plan,
+        _allowed_paths: &[PathBuf],
+        options: &executer::ExecOptions,
+    ) -> Result<Output> {
+        self.log(
+            Level::Warn,
+            "tracing requires the trace-paths feature on Linux, allowed paths are not enforced"
+                .yellow(),
+        );
+        plan.execute_with(options)
+    }
+
+    /// Writes a line of human-readable output to the output sink. Write errors are ignored,
+    /// since the output is informational only. With the `tracing` feature, the line is also
+    /// emitted as a `tracing` event at the given level.
+    fn log(&self, level: Level, message: impl Into<ColoredString>) {
+        let message = message.into();
+        #[cfg(feature = "tracing")]
+        if !message.is_empty() {
+            let text: &str = &message;
+            match level {
+                Level::Debug => tracing::debug!("{text}"),
+                Level::Info => tracing::info!("{text}"),
+                Level::Warn => tracing::warn!("{text}"),
+                Level::Error => tracing::error!("{text}"),
+            }
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = level;
+        let message = self.paint(message);
+        let _ = writeln!(self.output.borrow_mut(), "{message}");
+    }
+
+    /// Renders `text` with its colors and styles when colors are enabled, as plain text
+    /// otherwise. See [`Runner::color`].
+    fn paint(&self, text: ColoredString) -> String {
+        if self.color {
+            text.to_string()
+        } else {
+            text.clear().to_string()
+        }
+    }
+
+    /// Returns the options the commands are executed with, for the step at `step_index` when
+    /// given.
+    fn exec_options(&self, step_index: Option<usize>) -> executer::ExecOptions {
+        #[cfg(not(target_os = "linux"))]
+        let _ = step_index;
+        executer::ExecOptions {
+            #[cfg(unix)]
+            limits: self.resource_limits,
+            #[cfg(target_os = "linux")]
+            cpu_affinity: step_index.and_then(|index| self.step_affinity(index)),
+            timeout: self.step_timeout,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the CPUs the commands of the step at `index` are pinned to, when the affinity
+    /// is randomized.
+    #[cfg(target_os = "linux")]
+    fn step_affinity(&self, index: usize) -> Option<Vec<usize>> {
+        if !self.randomize_affinity {
+            return None;
+        }
+        let cpus = executer::available_cpus().ok()?;
+        if cpus.is_empty() {
+            return None;
+        }
+
+        let randomizer = self.randomizer.child(&format!("affinity/{index}"));
+        let count = randomizer.number_between(1, u32::try_from(cpus.len()).unwrap_or(u32::MAX));
+        let mut selected: Vec<usize> = randomizer
+            .shuffle(&cpus)
+            .into_iter()
+            .take(count as usize)
+            .collect();
+        selected.sort_unstable();
+        Some(selected)
+    }
+
+    /// Executes a single plan of a step and reports it, returning whether the runner should
+    /// continue with the rest of the step.
+    fn execute_plan(
+        &self,
+        step: &dyn StepTrait,
+        step_plan: Plan,
+        options: &executer::ExecOptions,
+        report: &mut RunReport,
+        context: &mut RunContext,
+    ) -> Result<bool> {
+        if step_plan.command.len() > self.max_command_length {
+            return Err(Error::CommandTooLong {
+                id: step_plan.id,
+                length: step_plan.command.len(),
+                limit: self.max_command_length,
+            });
+        }
+
+        self.log(Level::Info, "Execute plan...".yellow());
+        let result = match (&self.differential, step.allowed_paths()) {
+            (Some((program_a, program_b)), _) => {
+                self.execute_differential(&step_plan, program_a, program_b, options, report)
+            }
+            (None, Some(allowed_paths)) => self.execute_scoped(&step_plan, &allowed_paths, options),
+            (None, None) => step_plan.execute_with(options),
+        };
+        let result = match result {
+            Ok(result) => result,
+            Err(err) => {
+                if let Some(output) = command_output(&err) {
+                    report.steps.push(StepReport {
+                        id: step_plan.id,
+                        command: step_plan.command,
+                        status: StepStatus::Failed,
+                        output: Some(output.clone()),
+                        durations: PhaseDurations {
+                            plan: Some(output.duration),
+                            ..PhaseDurations::default()
+                        },
+                    });
+                    if let Some(plan_report) = report.steps.last() {
+                        self.notify(|observer| observer.on_plan(plan_report));
+                    }
+                }
+                return Err(err);
+            }
+        };
+        // A differential run reports the duration of both programs itself.
+        if self.differential.is_none() {
+            self.log(
+                Level::Info,
+                format!("Execute plan finished in {:?}", result.duration).yellow(),
+            );
+        }
+        let is_success = step.is_success(&result, &step_plan.ctx);
+        report.steps.push(StepReport {
+            id: step_plan.id,
+            command: step_plan.command,
+            status: match is_success {
+                Ok(true) => StepStatus::Passed,
+                Ok(false) => StepStatus::Skipped,
+                Err(_) => StepStatus::Failed,
+            },
+            output: Some(result.clone()),
+            durations: PhaseDurations {
+                plan: Some(result.duration),
+                ..PhaseDurations::default()
+            },
+        });
+        if let Some(plan_report) = report.steps.last() {
+            self.notify(|observer| observer.on_plan(plan_report));
+        }
+
+        let is_success = is_success.map_err(|err| Error::StepError {
+            kind: step::Kind::Plan,
+            description: step
+                .failure_details(&result, &step_plan.ctx)
+                .map_or_else(|| err.to_string(), |details| format!("{err}\n{details}")),
+            command_output: result.clone(),
+        })?;
+
+        step.update_context(&result, context);
+        Ok(is_success)
+    }
+
+    /// Generates the plans of a step, attaching the values recorded while planning it to each
+    /// of them.
+    fn plan_step(&self, step: &dyn StepTrait) -> Result<Vec<Plan>> {
+        self.randomizer.take_provenance();
+        let mut step_plans = step.plans(&self.randomizer)?;
+        let provenance = self.randomizer.take_provenance();
+        for step_plan in &mut step_plans {
+            step_plan.provenance.clone_from(&provenance);
+        }
+        Ok(step_plans)
+    }
+
+    /// Executes the steps once per iteration, and returns the first error once all the
+    /// iterations completed.
+    fn execute_iterations(&self, report: &mut RunReport) -> Result<()> {
+        let mut outcome = Ok(());
+        for _ in 0..self.iterations {
+            let iteration = self.execute_steps(report);
+            if outcome.is_ok() {
+                outcome = iteration;
+            }
+        }
+        outcome
+    }
+
+    /// Executes the steps and collects the report of each executed plan.
+    fn execute_steps(&self, report: &mut RunReport) -> Result<()> {
+        self.run_warmup()?;
+        self.run_priming()?;
+        let mut context = RunContext::default();
+        let mut first_failure = None;
+        let mut succeeded = HashSet::new();
+
+        for (index, step) in self.ordered_steps().into_iter().enumerate() {
+            self.check_disk_budget()?;
+            let step_plans = self.plan_step(step)?;
+            let step_id = step_plans
+                .first()
+                .map_or_else(String::new, |plan| plan.id.clone());
+            let plan_ids: Vec<String> = step_plans.iter().map(|plan| plan.id.clone()).collect();
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!("step", id = %step_id, index).entered();
+
+            if index < self.start_from {
+                self.log(Level::Info, format!("Skip step: {step_id}").yellow());
+                // The steps before the starting one are assumed to have run before.
+                succeeded.extend(plan_ids);
+                continue;
+            }
+
+            if let Some(dependency) = step
+                .dependencies()
+                .into_iter()
+                .find(|dependency| !succeeded.contains(dependency))
+            {
+                self.log(
+                    Level::Info,
+                    format!("Skip step: {step_id}: dependency {dependency} did not succeed")
+                        .yellow(),
+                );
+                Self::report_skipped(report, step_plans);
+                self.notify(|observer| observer.on_step_finish(&step_id, StepStatus::Skipped));
+                continue;
+            }
+
+            if !step.should_run(&context) {
+                self.log(
+                    Level::Info,
+                    format!("Skip step: {step_id}: run condition not met").yellow(),
+                );
+                Self::report_skipped(report, step_plans);
+                self.notify(|observer| observer.on_step_finish(&step_id, StepStatus::Skipped));
+                continue;
+            }
+
+            self.log(Level::Info, "");
+            self.log(Level::Info, format!("Run step: {step_id}").yellow());
+            self.log(Level::Info, "");
+
+            self.notify(|observer| observer.on_step_start(&step_id, index));
+            let reported = report.steps.len();
+            let outcome =
+                self.execute_step_with_retries(step, index, step_plans, report, &mut context);
+            let statuses = || report.steps[reported..].iter().map(|plan| plan.status);
+            let status = if outcome.is_err() || statuses().any(|s| s == StepStatus::Failed) {
+                StepStatus::Failed
+            } else if statuses().all(|s| s == StepStatus::Passed) {
+                StepStatus::Passed
+            } else {
+                StepStatus::Skipped
+            };
+            self.notify(|observer| observer.on_step_finish(&step_id, status));
+            if status == StepStatus::Passed {
+                succeeded.extend(plan_ids);
+            }
+            match outcome {
+                Err(err) if self.continue_on_error && is_step_failure(&err) => {
+                    self.log(
+                        Level::Error,
+                        format!("Step failed, continuing: {err}").red(),
+                    );
+                    first_failure.get_or_insert(err);
+                }
+                Err(err) => {
+                    self.log(Level::Error, format!("Step failed: {err}").red());
+                    return Err(err);
+                }
+                Ok(()) => {}
+            }
+        }
+
+        self.check_disk_budget()?;
+        self.clean_isolated_workdir()?;
+        first_failure.map_or(Ok(()), Err)
+    }
+
+    /// Invokes `event` on every observer, in the order they were added.
+    fn notify(&self, event: impl Fn(&dyn RunnerObserver)) {
+        for observer in &self.observers {
+            event(observer.as_ref());
+        }
+    }
+
+    /// Reports every plan of a step that is not executed as skipped.
+    fn report_skipped(report: &mut RunReport, step_plans: Vec<Plan>) {
+        report
+            .steps
+            .extend(step_plans.into_iter().map(|step_plan| StepReport {
+                id: step_plan.id,
+                command: step_plan.command,
+                status: StepStatus::Skipped,
+                output: None,
+                durations: PhaseDurations::default(),
+            }));
+    }
+
+    /// Executes a step, planning and executing it again up to [`StepTrait::retries`] more
+    /// times while a plan is not successful or the step fails. Only the reports of the last
+    /// attempt are kept. The step is torn down after every attempt, and a teardown error is
+    /// reported unless the attempt already failed.
+    fn execute_step_with_retries(
+        &self,
+        step: &dyn StepTrait,
+        index: usize,
+        step_plans: Vec<Plan>,
+        report: &mut RunReport,
+        context: &mut RunContext,
+    ) -> Result<()> {
+        let attempts = step.retries().saturating_add(1);
+        let mut step_plans = step_plans;
+        for attempt in 1..=attempts {
+            let reported = report.steps.len();
+            let outcome = self.execute_step(step, index, step_plans, report, context);
+            let outcome = match (outcome, step.teardown()) {
+                (Ok(_), Err(err)) => Err(err),
+                (outcome, Err(err)) => {
+                    self.log(Level::Error, format!("Teardown failed: {err}").red());
+                    outcome
+                }
+                (outcome, Ok(())) => outcome,
+            };
+            let should_retry = match &outcome {
+                Ok(completed) => !completed,
+                Err(err) => is_step_failure(err),
+            };
+            if !should_retry || attempt == attempts {
+                return match outcome {
+                    Err(err) if attempts > 1 && is_step_failure(&err) => {
+                        Err(Error::RetriesExhausted {
+                            attempts,
+                            source: Box::new(err),
+                        })
+                    }
+                    Ok(false) if attempts > 1 => {
+                        let command_output = report
+                            .steps
+                            .last()
+                            .and_then(|plan| plan.output.clone())
+                            .unwrap_or_default();
+                        report.mark_last_failed();
+                        Err(Error::RetriesExhausted {
+                            attempts,
+                            source: Box::new(Error::StepError {
+                                kind: step::Kind::Plan,
+                                description: "plan not successful".to_string(),
+                                command_output,
+                            }),
+                        })
+                    }
+                    outcome => outcome.map(|_| ()),
+                };
+            }
+
+            report.steps.truncate(reported);
+            self.log(
+                Level::Warn,
+                format!("Retry step: attempt {}/{attempts}", attempt + 1).yellow(),
+            );
+            step_plans = self.plan_step(step)?;
+        }
+        Ok(())
+    }
+
+    /// Sets up a step and executes its plans, check and test. Returns `false` when a plan was
+    /// not successful, in which case the rest of the step is skipped.
+    fn execute_step(
+        &self,
+        step: &dyn StepTrait,
+        index: usize,
+        step_plans: Vec<Plan>,
+        report: &mut RunReport,
+        context: &mut RunContext,
+    ) -> Result<bool> {
+        self.reset_workdir()?;
+        step.setup()?;
+        let options = self.exec_options(Some(index));
+        let step_id = step_plans
+            .first()
+            .map_or_else(String::new, |plan| plan.id.clone());
+        for step_plan in step_plans {
+            if !self.execute_plan(step, step_plan, &options, report, context)? {
+                return Ok(false);
+            }
+        }
+
+        if let Some(check_command) = step.run_check() {
+            self.log(Level::Info, "Execute check...".yellow());
+            let output = executer::run_sh_with(&check_command, &options)
+                .inspect_err(|_| report.mark_last_failed())?;
+            self.log(
+                Level::Info,
+                format!("Execute check finished in {:?}", output.duration).yellow(),
+            );
+            self.notify(|observer| observer.on_check(&step_id, &check_command, &output));
+            if let Some(durations) = report.last_durations() {
+                durations.check = Some(output.duration);
+            }
+            if output.status_code != Some(0) {
+                report.mark_last_failed();
+                return Err(Error::StepError {
+                    kind: step::Kind::Check,
+                    description: "check not finish with status code 0".to_string(),
+                    command_output: output,
+                });
+            }
+        }
+
+        if let Some(test_command) = step.run_test() {
+            self.log(Level::Info, "Execute test...".yellow());
+            let output = executer::run_sh_with(&test_command, &options)
+                .inspect_err(|_| report.mark_last_failed())?;
+            self.log(
+                Level::Info,
+                format!("Execute tests finished in {:?}", output.duration).yellow(),
+            );
+            self.notify(|observer| observer.on_test(&step_id, &test_command, &output));
+            if let Some(durations) = report.last_durations() {
+                durations.test = Some(output.duration);
+            }
+            if output.status_code != Some(0) {
+                report.mark_last_failed();
+                return Err(Error::StepError {
+                    kind: step::Kind::Test,
+                    description: "test command not finish with status code 0".to_string(),
+                    command_output: output,
+                });
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Replaces the working directory with a fresh copy of the isolation directory, if step
+    /// isolation is enabled.
+    fn reset_workdir(&self) -> Result<()> {
+        let (Some(workdir), Some(initial_dir)) = (&self.workdir, &self.isolation_dir) else {
+            return Ok(());
+        };
+
+        if workdir.exists() {
+            std::fs::remove_dir_all(workdir)?;
+        }
+        copy_dir(initial_dir, workdir)
+    }
+
+    /// Removes the working directory, if step isolation is enabled.
+    fn clean_isolated_workdir(&self) -> Result<()> {
+        if let (Some(workdir), Some(_)) = (&self.workdir, &self.isolation_dir) {
+            if workdir.exists() {
+                std::fs::remove_dir_all(workdir)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails when the working directory grew beyond the configured disk budget.
+    fn check_disk_budget(&self) -> Result<()> {
+        let (Some(workdir), Some(limit)) = (&self.workdir, self.max_disk_bytes) else {
+            return Ok(());
+        };
+
+        let used = dir_size(workdir)?;
+        if used > limit {
+            return Err(Error::DiskBudgetExceeded { used, limit });
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether `err` is the failure of a plan, check or test of a step, which is recorded
+/// in the report of the step: the command failed, timed out, was killed for exceeding its
+/// resource limits or wrote outside of its allowed paths.
+const fn is_step_failure(err: &Error) -> bool {
+    match err {
+        Error::StepError { kind, .. } => {
+            matches!(
+                kind,
+                step::Kind::Plan | step::Kind::Check | step::Kind::Test
+            )
+        }
+        Error::RetriesExhausted { .. } => true,
+        err => command_output(err).is_some(),
+    }
+}
+
+/// Returns the output of the command an execution error is about: the partial output of a
+/// timed out command, or the output of a command that broke its limits or its scope.
+const fn command_output(err: &Error) -> Option<&Output> {
+    match err {
+        Error::Timeout { partial_output, .. } => Some(partial_output),
+        Error::AccessOutsideScope { command_output, .. } => Some(command_output),
+        #[cfg(unix)]
+        Error::ResourceLimitExceeded { command_output, .. } => Some(command_output),
+        _ => None,
+    }
+}
+
+/// Returns whether `to` can be reached from `from` following the directed `edges`.
+fn reaches(edges: &[(usize, usize)], from: usize, to: usize) -> bool {
+    let mut visited = vec![from];
+    let mut pending = vec![from];
+    while let Some(node) = pending.pop() {
+        if node == to {
+            return true;
+        }
+        for &(source, target) in edges {
+            if source == node && !visited.contains(&target) {
+                visited.push(target);
+                pending.push(target);
+            }
+        }
+    }
+    false
+}
+
+/// Quotes `value` as a DOT string.
+fn dot_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Recursively copies the directory `from` to `to`, creating `to`.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the total size in bytes of the files under `path`, without following symlinks.
+fn dir_size(path: &Path) -> Result<u64> {
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.path().symlink_metadata()?;
+        if metadata.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::{collections::HashMap, path::PathBuf};
+
+    use serde::{Deserialize, Serialize};
+    use step::{Outcome, PlanCtx, RunContext};
+
+    use super::*;
+    use crate::{executer::Output, generator::StringDef, report::StepStats, step::Plan};
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct TestStepOne {
+        location: PathBuf,
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct TestStepTwo {
+        location: PathBuf,
+    }
+
+    impl StepTrait for TestStepOne {
+        fn setup(&self) -> crate::errors::Result<()> {
+            Ok(std::fs::create_dir_all(&self.location)?)
+        }
+
+        fn plan(&self, randomizer: &Randomizer) -> Result<Plan> {
+            let eco_string = randomizer.string(StringDef::default()).to_string();
+            Ok(Plan::with_vars::<Self>(
+                format!(
+                    "echo {eco_string} >> {}",
+                    self.location.join("test.txt").display()
+                ),
+                HashMap::from([("foo".to_string(), "bar".to_string())]),
+            ))
+        }
+
+        fn is_success(
+            &self,
+            execution_result: &Output,
+            plan_ctx: &PlanCtx,
+        ) -> Result<bool, &'static str> {
+            if let Some(foo_var) = plan_ctx.vars.get("foo") {
+                if foo_var != "bar" {
+                    return Err("foo value should be equal to var");
+                }
+            } else {
+                return Err("foo plan ctx var not found");
+            };
+
+            if execution_result.status_code == Some(0) {
+                Ok(true)
+            } else {
+                Err("status code should be 0")
+            }
+        }
+
+        fn run_check(&self) -> Option<String> {
+            Some(format!(
+                "test -f {}",
+                self.location.join("test.txt").display()
+            ))
+        }
+
+        fn run_test(&self) -> Option<String> {
+            Some(format!(
+                "test -f {}",
+                self.location.join("test.txt").display()
+            ))
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    impl StepTrait for TestStepTwo {
+        fn setup(&self) -> crate::errors::Result<()> {
+            Ok(std::fs::create_dir_all(&self.location)?)
+        }
+
+        fn plan(&self, randomizer: &Randomizer) -> Result<Plan> {
+            let eco_string = randomizer.string(StringDef::default()).to_string();
+            Ok(Plan::with_vars::<Self>(
+                format!(
+                    "cat {eco_string} >> {}",
+                    self.location.join("test.txt").display()
+                ),
+                HashMap::from([("foo".to_string(), "bar".to_string())]),
+            ))
+        }
+
+        fn is_success(
+            &self,
+            execution_result: &Output,
+            _plan_ctx: &PlanCtx,
+        ) -> Result<bool, &'static str> {
+            if execution_result.status_code == Some(1) {
+                Ok(true)
+            } else {
+                Err("status code should be 1")
+            }
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn can_run() {
+        let base_location = std::env::temp_dir().join("crazy-train");
+        let location_step_1 = base_location.join("step-1");
+        let location_step_2 = base_location.join("step-2");
+
+        let step_one = TestStepOne {
+            location: location_step_1,
+        };
+        let step_two = TestStepTwo {
+            location: location_step_2,
+        };
+        let randomaizer = Randomizer::with_seed(42);
+        let runner = new(vec![Box::new(step_one), Box::new(step_two)]).randomizer(randomaizer);
+
+        assert!(runner.run().is_ok());
+    }
+
+    #[test]
+    fn run_report_records_phase_durations() {
+        let base_location = std::env::temp_dir().join("crazy-train-run-report");
+        let steps: Vec<Box<dyn StepTrait>> = vec![
+            Box::new(TestStepOne {
+                location: base_location.join("step-1"),
+            }),
+            Box::new(TestStepTwo {
+                location: base_location.join("step-2"),
+            }),
+        ];
+        let runner = new(steps).randomizer(Randomizer::with_seed(42));
+
+        let report = runner.run_report().unwrap();
+        assert_eq!(report.seed, 42);
+        assert_eq!(report.steps.len(), 2);
+        assert!(report.elapsed >= report.steps[0].output.as_ref().unwrap().duration);
+
+        let first = &report.steps[0];
+        assert_eq!(first.status, StepStatus::Passed);
+        assert!(first.durations.plan.is_some());
+        assert!(first.durations.check.is_some());
+        assert!(first.durations.test.is_some());
+        let second = &report.steps[1];
+        assert!(second.durations.plan.is_some());
+        assert_eq!(second.durations.check, None);
+        assert_eq!(second.durations.test, None);
+
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["seed"].as_u64(), Some(42));
+        assert_eq!(
+            value["steps"][0]["command"].as_str(),
+            Some(first.command.as_str())
+        );
+
+        let _ = std::fs::remove_dir_all(&base_location);
+    }
+
+    #[test]
+    fn can_start_from_index() {
+        let base_location = std::env::temp_dir().join("crazy-train-start-from");
+        let steps = || -> Vec<Box<dyn StepTrait>> {
+            (0..4)
+                .map(|_| {
+                    Box::new(TestStepOne {
+                        location: base_location.clone(),
+                    }) as Box<dyn StepTrait>
+                })
+                .collect()
+        };
+        let executed_commands = |runner: &Runner| {
+            let _ = std::fs::remove_dir_all(&base_location);
+            assert!(runner.run().is_ok());
+            std::fs::read_to_string(base_location.join("test.txt"))
+                .unwrap()
+                .lines()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        let full_run = executed_commands(&new(steps()).randomizer(Randomizer::with_seed(42)));
+        let partial_run = executed_commands(
+            &new(steps())
+                .randomizer(Randomizer::with_seed(42))
+                .start_from(2),
+        );
+
+        assert_eq!(full_run.len(), 4);
+        assert_eq!(partial_run, full_run[2..]);
+    }
+
+    #[test]
+    fn final_assertion_can_fail_the_run() {
+        let base_location = std::env::temp_dir().join("crazy-train-final-assertion");
+        let steps = || -> Vec<Box<dyn StepTrait>> {
+            (0..3)
+                .map(|_| {
+                    Box::new(TestStepOne {
+                        location: base_location.clone(),
+                    }) as Box<dyn StepTrait>
+                })
+                .collect()
+        };
+        let successful_steps = |report: &RunReport| {
+            report
+                .steps
+                .iter()
+                .filter(|step| step.status == StepStatus::Passed)
+                .count()
+        };
+
+        let runner = new(steps()).final_assertion(move |report| {
+            if successful_steps(report) == 3 {
+                Ok(())
+            } else {
+                Err(Error::Any("expected 3 successful steps".to_string()))
+            }
+        });
+        assert!(runner.run().is_ok());
+
+        let runner = new(steps()).final_assertion(move |report| {
+            if successful_steps(report) == 4 {
+                Ok(())
+            } else {
+                Err(Error::Any("expected 4 successful steps".to_string()))
+            }
+        });
+        assert!(matches!(
+            runner.run(),
+            Err(Error::Any(message)) if message == "expected 4 successful steps"
+        ));
+    }
+
+    #[test]
+    fn run_report_keeps_errors_outside_of_the_steps() {
+        let runner = new(vec![Box::new(ExitCodeStep { code: 0 })])
+            .priming_commands(vec!["exit 3".to_string()]);
+        assert!(matches!(
+            runner.run_report(),
+            Err(Error::StepError {
+                kind: step::Kind::Priming,
+                ..
+            })
+        ));
+
+        let runner = new(vec![Box::new(FixedCommandStep {
+            command: "exit 4".to_string(),
+        }) as Box<dyn StepTrait>])
+        .final_assertion(|report| {
+            if report
+                .steps
+                .iter()
+                .all(|step| step.status == StepStatus::Passed)
+            {
+                Ok(())
+            } else {
+                Err(Error::Any("expected every step to pass".to_string()))
+            }
+        });
+        assert!(matches!(
+            runner.run_all(),
+            Err(Error::Any(message)) if message == "expected every step to pass"
+        ));
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct FailOnSeedStep {
+        failing_seed: u64,
+    }
+
+    impl StepTrait for FailOnSeedStep {
+        fn plan(&self, randomizer: &Randomizer) -> Result<Plan> {
+            let command = if randomizer.seed == self.failing_seed {
+                "exit 1"
+            } else {
+                "exit 0"
+            };
+            Ok(Plan::new::<Self>(command))
+        }
+
+        fn is_success(
+            &self,
+            execution_result: &Output,
+            _plan_ctx: &PlanCtx,
+        ) -> Result<bool, &'static str> {
+            if execution_result.status_code == Some(0) {
+                Ok(true)
+            } else {
+                Err("status code should be 0")
+            }
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn sweep_keeps_minimal_reproducer() {
+        let mut runner = new(vec![
+            Box::new(FailOnSeedStep { failing_seed: 1 }),
+            Box::new(FailOnSeedStep { failing_seed: 3 }),
+            Box::new(FailOnSeedStep { failing_seed: 2 }),
+            Box::new(FailOnSeedStep { failing_seed: 2 }),
+        ]);
+
+        let result = runner.sweep([2, 3, 4, 1, 5]);
+
+        assert_eq!(result.passed, vec![4, 5]);
+        assert_eq!(
+            result
+                .failures
+                .iter()
+                .map(|reproducer| (reproducer.seed, reproducer.steps))
+                .collect::<Vec<_>>(),
+            vec![(2, 3), (3, 2), (1, 1)]
+        );
+        let minimal = result.minimal_reproducer().unwrap();
+        assert_eq!(minimal.seed, 1);
+        assert_eq!(minimal.steps, 1);
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct FailOnDrawStep;
+
+    impl StepTrait for FailOnDrawStep {
+        fn plan(&self, randomizer: &Randomizer) -> Result<Plan> {
+            let command = format!("exit {}", u32::from(randomizer.number_between(0, 3) == 0));
+            Ok(Plan::new::<Self>(command))
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn sweep_seeds_reproduce_with_run() {
+        let steps = || -> Vec<Box<dyn StepTrait>> {
+            vec![
+                Box::new(FailOnDrawStep),
+                Box::new(FailOnDrawStep),
+                Box::new(FailOnDrawStep),
+            ]
+        };
+        let mut runner = new(steps()).randomizer(Randomizer::with_seed(42));
+
+        let result = runner.sweep(0..20);
+        assert!(!result.passed.is_empty());
+        assert!(!result.failures.is_empty());
+        assert_eq!(runner.randomizer.seed, 42);
+        assert_eq!(runner.randomizer.current_seed(), 42);
+
+        for seed in &result.passed {
+            let runner = new(steps()).randomizer(Randomizer::with_seed(*seed));
+            assert!(runner.run().is_ok(), "seed {seed} should pass");
+        }
+        for reproducer in &result.failures {
+            let runner = new(steps()).randomizer(Randomizer::with_seed(reproducer.seed));
+            let report = runner.run_report().unwrap();
+            assert_eq!(
+                report.steps.len(),
+                reproducer.steps,
+                "seed {}",
+                reproducer.seed
+            );
+            assert_eq!(report.steps.last().unwrap().status, StepStatus::Failed);
+        }
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct CreateDbStep {}
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct MigrateStep {}
+
+    impl StepTrait for CreateDbStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>("exit 0"))
+        }
+
+        fn is_success(
+            &self,
+            _execution_result: &Output,
+            _plan_ctx: &PlanCtx,
+        ) -> Result<bool, &'static str> {
+            Ok(true)
+        }
+
+        fn update_context(&self, _execution_result: &Output, ctx: &mut RunContext) {
+            ctx.vars
+                .insert("db_created".to_string(), "true".to_string());
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    impl StepTrait for MigrateStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>("exit 0"))
+        }
+
+        fn is_success(
+            &self,
+            _execution_result: &Output,
+            _plan_ctx: &PlanCtx,
+        ) -> Result<bool, &'static str> {
+            Ok(true)
+        }
+
+        fn should_run(&self, ctx: &RunContext) -> bool {
+            ctx.vars
+                .get("db_created")
+                .is_some_and(|created| created == "true")
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn skips_steps_with_unmet_run_condition() {
+        let runner = new(vec![
+            Box::new(MigrateStep {}),
+            Box::new(CreateDbStep {}),
+            Box::new(MigrateStep {}),
+        ]);
+        let mut report = RunReport::new(42);
+
+        assert!(runner.execute_steps(&mut report).is_ok());
+        assert_eq!(
+            report
+                .steps
+                .iter()
+                .map(|step| step.status)
+                .collect::<Vec<_>>(),
+            vec![StepStatus::Skipped, StepStatus::Passed, StepStatus::Passed]
+        );
+        assert!(report.steps[0].output.is_none());
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct WriteFileStep {
+        location: PathBuf,
+        bytes: usize,
+    }
+
+    impl StepTrait for WriteFileStep {
+        fn setup(&self) -> crate::errors::Result<()> {
+            Ok(std::fs::create_dir_all(&self.location)?)
+        }
+
+        fn plan(&self, randomizer: &Randomizer) -> Result<Plan> {
+            let file_name = randomizer.string(StringDef::default()).to_string();
+            Ok(Plan::new::<Self>(format!(
+                "head -c {} /dev/zero > {}",
+                self.bytes,
+                self.location.join(file_name).display()
+            )))
+        }
+
+        fn is_success(
+            &self,
+            execution_result: &Output,
+            _plan_ctx: &PlanCtx,
+        ) -> Result<bool, &'static str> {
+            if execution_result.status_code == Some(0) {
+                Ok(true)
+            } else {
+                Err("status code should be 0")
+            }
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn aborts_when_disk_budget_exceeded() {
+        let location = std::env::temp_dir().join("crazy-train-disk-budget");
+        let _ = std::fs::remove_dir_all(&location);
+        let steps = || -> Vec<Box<dyn StepTrait>> {
+            vec![
+                Box::new(WriteFileStep {
+                    location: location.clone(),
+                    bytes: 100,
+                }),
+                Box::new(WriteFileStep {
+                    location: location.clone(),
+                    bytes: 20_000,
+                }),
+            ]
+        };
+
+        let runner = new(steps()).workdir(&location).max_disk_bytes(1_000_000);
+        assert!(runner.run().is_ok());
+
+        let _ = std::fs::remove_dir_all(&location);
+        let runner = new(steps()).workdir(&location).max_disk_bytes(10_000);
+        assert!(matches!(
+            runner.run(),
+            Err(Error::DiskBudgetExceeded { used, limit: 10_000 }) if used >= 20_000
+        ));
+    }
+
+    /// A step failing on every third execution.
+    #[derive(Clone, Serialize, Deserialize)]
+    struct FlakyStep {
+        #[serde(skip)]
+        executions: std::cell::Cell<u32>,
+    }
+
+    impl StepTrait for FlakyStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>("true"))
+        }
+
+        fn is_success(
+            &self,
+            _execution_result: &Output,
+            _plan_ctx: &PlanCtx,
+        ) -> std::result::Result<bool, &'static str> {
+            self.executions.set(self.executions.get() + 1);
+            if self.executions.get() == 3 {
+                self.executions.set(0);
+                Err("flaky failure")
+            } else {
+                Ok(true)
+            }
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    /// A step failing its first `failures` attempts, or not succeeding when `unsuccessful` is
+    /// set, recording the command of each attempt.
+    #[derive(Clone, Serialize, Deserialize)]
+    struct RetriedStep {
+        failures: usize,
+        unsuccessful: bool,
+        retries: u32,
+        #[serde(skip)]
+        commands: std::rc::Rc<RefCell<Vec<String>>>,
+    }
+
+    impl StepTrait for RetriedStep {
+        fn plan(&self, randomizer: &Randomizer) -> Result<Plan> {
+            let name = randomizer.string(StringDef::default()).to_string();
+            Ok(Plan::new::<Self>(format!("echo {name}")))
+        }
+
+        fn is_success(
+            &self,
+            execution_result: &Output,
+            _plan_ctx: &PlanCtx,
+        ) -> std::result::Result<bool, &'static str> {
+            let mut commands = self.commands.borrow_mut();
+            commands.push(execution_result.stdout.clone());
+            if commands.len() > self.failures {
+                Ok(true)
+            } else if self.unsuccessful {
+                Ok(false)
+            } else {
+                Err("flaky failure")
+            }
+        }
+
+        fn retries(&self) -> u32 {
+            self.retries
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn retries_flaky_steps_with_new_plans() {
+        let step = RetriedStep {
+            failures: 2,
+            unsuccessful: false,
+            retries: 2,
+            commands: std::rc::Rc::default(),
+        };
+        let commands = step.commands.clone();
+        let runner = new(vec![Box::new(step)]).randomizer(Randomizer::with_seed(42));
+
+        let reports = runner.run_all().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].status, StepStatus::Passed);
+        let commands = commands.borrow();
+        assert_eq!(commands.len(), 3);
+        assert_ne!(commands[0], commands[1]);
+        assert_ne!(commands[1], commands[2]);
+    }
+
+    #[test]
+    fn retries_exhausted_reports_attempts() {
+        let step = RetriedStep {
+            failures: 3,
+            unsuccessful: false,
+            retries: 2,
+            commands: std::rc::Rc::default(),
+        };
+        let commands = step.commands.clone();
+
+        assert!(matches!(
+            new(vec![Box::new(step)]).run(),
+            Err(Error::RetriesExhausted { attempts: 3, source })
+                if matches!(*source, Error::StepError { kind: step::Kind::Plan, .. })
+        ));
+        assert_eq!(commands.borrow().len(), 3);
+    }
+
+    #[test]
+    fn retries_exhausted_when_no_attempt_succeeds() {
+        let step = || RetriedStep {
+            failures: 3,
+            unsuccessful: true,
+            retries: 2,
+            commands: std::rc::Rc::default(),
+        };
+        let unsuccessful = step();
+        let commands = unsuccessful.commands.clone();
+
+        assert!(matches!(
+            new(vec![Box::new(unsuccessful)]).run(),
+            Err(Error::RetriesExhausted { attempts: 3, source })
+                if matches!(*source, Error::StepError { kind: step::Kind::Plan, .. })
+        ));
+        assert_eq!(commands.borrow().len(), 3);
+
+        let reports = new(vec![Box::new(step())]).run_all().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].status, StepStatus::Failed);
+    }
+
+    /// A step creating a file in its setup and removing it in its teardown.
+    #[derive(Clone, Serialize, Deserialize)]
+    struct TempFileStep {
+        path: PathBuf,
+        command: String,
+    }
+
+    impl StepTrait for TempFileStep {
+        fn setup(&self) -> Result<()> {
+            Ok(std::fs::write(&self.path, "temporary")?)
+        }
+
+        fn teardown(&self) -> Result<()> {
+            Ok(std::fs::remove_file(&self.path)?)
+        }
+
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>(format!(
+                "test -f {} && {}",
+                self.path.display(),
+                self.command
+            )))
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn teardown_runs_after_passing_and_failing_steps() {
+        let location = std::env::temp_dir().join("crazy-train-teardown");
+        std::fs::create_dir_all(&location).unwrap();
+        let step = |name: &str, command: &str| TempFileStep {
+            path: location.join(name),
+            command: command.to_string(),
+        };
+
+        let passing = step("passing.txt", "true");
+        assert!(new(vec![Box::new(passing.clone())]).run().is_ok());
+        assert!(!passing.path.exists());
+
+        let failing = step("failing.txt", "false");
+        let never_run = step("never-run.txt", "true");
+        assert!(
+            new(vec![Box::new(failing.clone()), Box::new(never_run.clone())])
+                .run()
+                .is_err()
+        );
+        assert!(!failing.path.exists());
+        assert!(!never_run.path.exists());
+
+        let _ = std::fs::remove_dir_all(&location);
+    }
+
+    /// An output sink shared with the test.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::rc::Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn output_sink_receives_run_output() {
+        let buffer = SharedBuffer::default();
+        let runner = new(vec![Box::new(CreateDbStep {})])
+            .randomizer(Randomizer::with_seed(42))
+            .output_sink(Box::new(buffer.clone()));
+
+        assert!(runner.run().is_ok());
+
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        assert!(output.contains("Execution Plan Dump"));
+        assert!(output.contains("Seed"));
+        assert!(output.contains("Run step:"));
+        assert!(output.contains("Execution plan is pass successfully"));
+    }
+
+    #[test]
+    fn failing_step_is_logged_as_error() {
+        let buffer = SharedBuffer::default();
+        let runner = new(vec![Box::new(FixedCommandStep {
+            command: "exit 3".to_string(),
+        })])
+        .color(false)
+        .output_sink(Box::new(buffer.clone()));
+
+        assert!(runner.run().is_err());
+
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        assert!(output.contains("Step failed: "));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn emits_tracing_events_at_their_level() {
+        use std::sync::{Arc, Mutex};
+        use tracing::{field::Field, span, Event, Metadata};
+
+        type Events = Arc<Mutex<Vec<(tracing::Level, String)>>>;
+
+        struct Message(String);
+
+        impl tracing::field::Visit for Message {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "message" {
+                    self.0 = format!("{value:?}");
+                }
+            }
+        }
+
+        struct Recorder(Events);
+
+        impl tracing::Subscriber for Recorder {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+                span::Id::from_u64(1)
+            }
+
+            fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+            fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut message = Message(String::new());
+                event.record(&mut message);
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push((*event.metadata().level(), message.0));
+            }
+
+            fn enter(&self, _span: &span::Id) {}
+
+            fn exit(&self, _span: &span::Id) {}
+        }
+
+        let events = Events::default();
+        let buffer = SharedBuffer::default();
+        let runner = new(vec![Box::new(FixedCommandStep {
+            command: "exit 3".to_string(),
+        })])
+        .color(false)
+        .output_sink(Box::new(buffer.clone()));
+
+        let result = tracing::subscriber::with_default(Recorder(events.clone()), || runner.run());
+        assert!(result.is_err());
+
+        let events = std::mem::take(&mut *events.lock().unwrap());
+        assert!(events
+            .iter()
+            .any(|(level, message)| *level == tracing::Level::INFO
+                && message.starts_with("Run step: ")));
+        assert!(events
+            .iter()
+            .any(|(level, message)| *level == tracing::Level::ERROR
+                && message.starts_with("Step failed: ")));
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        assert!(output.contains("Step failed: "));
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct DestructiveStep {
+        location: PathBuf,
+    }
+
+    impl StepTrait for DestructiveStep {
+        fn setup(&self) -> Result<()> {
+            Ok(std::fs::create_dir_all(&self.location)?)
+        }
+
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>(format!(
+                "touch {}; exit 1",
+                self.location.join("plan.txt").display()
+            )))
+        }
+
+        fn run_check(&self) -> Option<String> {
+            Some(format!(
+                "touch {}",
+                self.location.join("check.txt").display()
+            ))
+        }
+
+        fn run_test(&self) -> Option<String> {
+            Some(format!(
+                "touch {}",
+                self.location.join("test.txt").display()
+            ))
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn dry_run_executes_nothing() {
+        let location = std::env::temp_dir().join("crazy-train-dry-run");
+        let _ = std::fs::remove_dir_all(&location);
+        let buffer = SharedBuffer::default();
+        let runner = new(vec![Box::new(DestructiveStep {
+            location: location.clone(),
+        })])
+        .color(false)
+        .output_sink(Box::new(buffer.clone()));
+
+        assert!(runner.dry_run().is_ok());
+        assert!(!location.exists());
+
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        assert!(output.contains("Step 1: "));
+        assert!(output.contains(&format!(
+            "touch {}; exit 1",
+            location.join("plan.txt").display()
+        )));
+        assert!(output.contains(&format!("touch {}", location.join("check.txt").display())));
+        assert!(output.contains(&format!("touch {}", location.join("test.txt").display())));
+        assert!(runner.run().is_err());
+        let _ = std::fs::remove_dir_all(&location);
+    }
+
+    #[test]
+    fn dry_run_prints_the_commands_run_executes() {
+        let location = std::env::temp_dir().join("crazy-train-dry-run-commands");
+        let buffer = SharedBuffer::default();
+        let runner = new(vec![
+            Box::new(TestStepOne {
+                location: location.clone(),
+            }),
+            Box::new(TestStepOne {
+                location: location.clone(),
+            }),
+        ])
+        .randomizer(Randomizer::with_seed(42))
+        .warmup(
+            Box::new(TestStepOne {
+                location: location.clone(),
+            }),
+            2,
+        )
+        .color(false)
+        .quiet(true)
+        .output_sink(Box::new(buffer.clone()));
+
+        let commands = || -> Vec<String> {
+            runner
+                .run_report()
+                .unwrap()
+                .steps
+                .into_iter()
+                .map(|step| step.command)
+                .collect()
+        };
+        let executed = commands();
+        buffer.0.borrow_mut().clear();
+        runner.dry_run().unwrap();
+        runner.dry_run().unwrap();
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+
+        let printed: Vec<&str> = output
+            .lines()
+            .filter(|line| line.starts_with("echo "))
+            .collect();
+        assert_eq!(printed, [executed.clone(), executed.clone()].concat());
+        assert_eq!(commands(), executed);
+        let _ = std::fs::remove_dir_all(&location);
+    }
+
+    #[test]
+    fn continue_on_error_runs_every_step() {
+        let steps = || -> Vec<Box<dyn StepTrait>> {
+            vec![
+                Box::new(FixedCommandStep {
+                    command: "exit 4".to_string(),
+                }),
+                Box::new(ExitCodeStep { code: 0 }),
+                Box::new(CreateDbStep {}),
+            ]
+        };
+
+        let mut report = RunReport::new(42);
+        let result = new(steps())
+            .continue_on_error(true)
+            .execute_steps(&mut report);
+        assert!(matches!(
+            result,
+            Err(Error::StepError {
+                kind: step::Kind::Plan,
+                ..
+            })
+        ));
+        assert_eq!(
+            report
+                .steps
+                .iter()
+                .map(|step| step.status)
+                .collect::<Vec<_>>(),
+            vec![StepStatus::Failed, StepStatus::Failed, StepStatus::Passed]
+        );
+
+        let outcomes = new(steps()).continue_on_error(true).run_all().unwrap();
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[0].output.as_ref().unwrap().status_code, Some(4));
+
+        let outcomes = new(steps()).run_all().unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].status, StepStatus::Failed);
+    }
+
+    #[test]
+    fn cloned_steps_run_independently() {
+        let runner = new(vec![Box::new(FlakyStep {
+            executions: std::cell::Cell::default(),
+        })])
+        .iterations(2);
+        let fresh_steps = runner.clone_steps();
+
+        let mut report = RunReport::new(42);
+        assert!(runner.execute_iterations(&mut report).is_ok());
+
+        // The original step failed on its third execution, the clone taken before the run
+        // starts from scratch.
+        let mut report = RunReport::new(42);
+        assert!(runner.execute_steps(&mut report).is_err());
+        let mut report = RunReport::new(42);
+        assert!(new(fresh_steps)
+            .iterations(2)
+            .execute_iterations(&mut report)
+            .is_ok());
+    }
+
+    #[test]
+    fn per_step_stats_accumulate_across_iterations() {
+        let runner = new(vec![
+            Box::new(FlakyStep {
+                executions: std::cell::Cell::default(),
+            }),
+            Box::new(ExitCodeStep { code: 2 }),
+        ])
+        .iterations(7);
+        let mut report = RunReport::new(42);
+
+        assert!(runner.execute_iterations(&mut report).is_err());
+
+        let stats = report.per_step_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats[&Plan::new::<FlakyStep>("").id],
+            StepStats {
+                runs: 7,
+                passed: 5,
+                failed: 2,
+                skipped: 0,
+            }
+        );
+        assert_eq!(
+            stats[&Plan::new::<ExitCodeStep>("").id],
+            StepStats {
+                runs: 5,
+                passed: 0,
+                failed: 0,
+                skipped: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn max_command_length_rejects_oversized_commands() {
+        let oversized = || {
+            vec![Box::new(FixedCommandStep {
+                command: format!("echo {}", "x".repeat(200)),
+            }) as Box<dyn StepTrait>]
+        };
+
+        let mut report = RunReport::new(42);
+        let result = new(oversized())
+            .max_command_length(100)
+            .execute_steps(&mut report);
+        assert!(matches!(
+            result,
+            Err(Error::CommandTooLong {
+                length: 205,
+                limit: 100,
+                ..
+            })
+        ));
+        assert!(report.steps.is_empty());
+
+        let mut report = RunReport::new(42);
+        assert!(new(oversized()).execute_steps(&mut report).is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn randomize_affinity_pins_steps_to_seeded_cpus() {
+        let runner = new(vec![Box::new(FixedCommandStep {
+            command: "grep Cpus_allowed_list /proc/self/status".to_string(),
+        })])
+        .randomizer(Randomizer::with_seed(42))
+        .randomize_affinity(true);
+        let mut report = RunReport::new(42);
+
+        assert!(runner.execute_steps(&mut report).is_ok());
+
+        let expected = runner.step_affinity(0).expect("affinity");
+        assert_eq!(runner.step_affinity(0), Some(expected.clone()));
+        let stdout = &report.steps[0].output.as_ref().unwrap().stdout;
+        let allowed: Vec<usize> = stdout
+            .trim()
+            .trim_start_matches("Cpus_allowed_list:")
+            .trim()
+            .split(',')
+            .flat_map(|range| {
+                let (start, end) = range.split_once('-').unwrap_or((range, range));
+                start.parse::<usize>().unwrap()..=end.parse::<usize>().unwrap()
+            })
+            .collect();
+        assert_eq!(allowed, expected);
+
+        assert_eq!(new(Vec::new()).step_affinity(0), None);
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct FuzzedInputStep {}
+
+    impl StepTrait for FuzzedInputStep {
+        fn plan(&self, randomizer: &Randomizer) -> Result<Plan> {
+            Ok(randomizer.argv_and_stdin(4, 3).into_plan::<Self>(
+                r#"f() { for arg in "$@"; do printf '[%s]' "$arg"; done; echo; cat; }; f"#,
+            ))
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn argv_and_stdin_reach_the_command() {
+        let input = |seed| Randomizer::with_seed(seed).argv_and_stdin(4, 3);
+        assert_eq!(input(42), input(42));
+        assert!((0..20).any(|seed| !input(seed).args.is_empty() && !input(seed).stdin.is_empty()));
+
+        for seed in 0..20 {
+            let runner =
+                new(vec![Box::new(FuzzedInputStep {})]).randomizer(Randomizer::with_seed(seed));
+            let mut report = RunReport::new(seed);
+            assert!(runner.execute_steps(&mut report).is_ok());
+
+            let expected = input(seed);
+            let args: String = expected
+                .args
+                .iter()
+                .flat_map(|arg| ["[", arg, "]"])
+                .collect();
+            assert_eq!(
+                report.steps[0].output.as_ref().unwrap().stdout,
+                format!("{args}\n{}", expected.stdin)
+            );
+        }
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct ExitCodeStep {
+        code: i32,
+    }
+
+    impl StepTrait for ExitCodeStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>(format!("exit {}", self.code)))
+        }
+
+        fn status_semantics(&self) -> HashMap<i32, Outcome> {
+            HashMap::from([
+                (0, Outcome::Success),
+                (1, Outcome::Failure),
+                (2, Outcome::Skip),
+            ])
+        }
+
+        fn run_check(&self) -> Option<String> {
+            Some("exit 1".to_string())
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn status_semantics_skip_is_not_fatal() {
+        let runner = new(vec![
+            Box::new(ExitCodeStep { code: 2 }),
+            Box::new(CreateDbStep {}),
+        ]);
+        let mut report = RunReport::new(42);
+
+        assert!(runner.execute_steps(&mut report).is_ok());
+        assert_eq!(
+            report
+                .steps
+                .iter()
+                .map(|step| step.status)
+                .collect::<Vec<_>>(),
+            vec![StepStatus::Skipped, StepStatus::Passed]
+        );
+
+        for code in [1, 3] {
+            let runner = new(vec![Box::new(ExitCodeStep { code })]);
+            assert!(matches!(
+                runner.run(),
+                Err(Error::StepError {
+                    kind: step::Kind::Plan,
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn warmup_runs_are_not_reported() {
+        let base_location = std::env::temp_dir().join("crazy-train-warmup");
+        let warmup_location = base_location.join("warmup");
+        let step_location = base_location.join("steps");
+        let _ = std::fs::remove_dir_all(&base_location);
+
+        let runner = new(vec![Box::new(TestStepOne {
+            location: step_location,
+        })])
+        .randomizer(Randomizer::with_seed(42))
+        .warmup(
+            Box::new(TestStepOne {
+                location: warmup_location.clone(),
+            }),
+            3,
+        )
+        .isolate_warmup(true);
+        let mut report = RunReport::new(42);
+
+        assert!(runner.execute_steps(&mut report).is_ok());
+        let warmup_lines = std::fs::read_to_string(warmup_location.join("test.txt")).unwrap();
+        assert_eq!(warmup_lines.lines().count(), 3);
+        assert_eq!(report.steps.len(), 1);
+
+        let echoed = report.steps[0].command.split_whitespace().nth(1).unwrap();
+        assert_eq!(Some(echoed), warmup_lines.lines().next());
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct FixedCommandStep {
+        command: String,
+    }
+
+    impl StepTrait for FixedCommandStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>(self.command.clone()))
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn differential_reports_divergences() {
+        let location = std::env::temp_dir().join("crazy-train-differential");
+        std::fs::create_dir_all(&location).unwrap();
+        let program_a = location.join("a.sh");
+        let program_b = location.join("b.sh");
+        std::fs::write(&program_a, "echo \"$1\"\n").unwrap();
+        std::fs::write(
+            &program_b,
+            "if [ \"$1\" = b ]; then echo B; else echo \"$1\"; fi\n",
+        )
+        .unwrap();
+
+        let steps: Vec<Box<dyn StepTrait>> = ["a", "b", "c"]
+            .into_iter()
+            .map(|command| {
+                Box::new(FixedCommandStep {
+                    command: command.to_string(),
+                }) as Box<dyn StepTrait>
+            })
+            .collect();
+        let buffer = SharedBuffer::default();
+        let runner = new(steps)
+            .differential(
+                format!("sh {}", program_a.display()),
+                format!("sh {}", program_b.display()),
+            )
+            .color(false)
+            .output_sink(Box::new(buffer.clone()));
+        let mut report = RunReport::new(42);
+
+        assert!(runner.execute_steps(&mut report).is_ok());
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        assert_eq!(
+            output
+                .lines()
+                .filter(|line| line.contains(&format!("with sh {}", program_b.display())))
+                .count(),
+            3
+        );
+        assert_eq!(report.steps.len(), 3);
+        assert_eq!(report.divergences.len(), 1);
+        assert_eq!(report.divergences[0].command, "b");
+        assert_eq!(report.divergences[0].output_a.stdout, "b\n");
+        assert_eq!(report.divergences[0].output_b.stdout, "B\n");
+
+        let runner = new(vec![Box::new(ArgvStep {
+            program: "b; echo injected".to_string(),
+            args: vec!["$HOME".to_string()],
+        })])
+        .differential(
+            format!("sh {}", program_a.display()),
+            format!("sh {}", program_b.display()),
+        );
+        let mut report = RunReport::new(42);
+        assert!(runner.execute_steps(&mut report).is_ok());
+        assert!(report.divergences.is_empty());
+        assert_eq!(
+            report.steps[0].output.as_ref().unwrap().stdout,
+            "b; echo injected\n"
+        );
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct ArgvStep {
+        program: String,
+        args: Vec<String>,
+    }
+
+    impl StepTrait for ArgvStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::with_argv::<Self>(
+                self.program.clone(),
+                self.args.clone(),
+            ))
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct CreateFilesStep {
+        location: PathBuf,
+        count: usize,
+    }
+
+    impl StepTrait for CreateFilesStep {
+        fn setup(&self) -> crate::errors::Result<()> {
+            Ok(std::fs::create_dir_all(&self.location)?)
+        }
+
+        fn plan(&self, randomizer: &Randomizer) -> Result<Plan> {
+            Ok(self.plans(randomizer)?.remove(0))
+        }
+
+        fn plans(&self, _randomizer: &Randomizer) -> Result<Vec<Plan>> {
+            Ok((0..self.count)
+                .map(|index| {
+                    Plan::new::<Self>(format!(
+                        "touch {}",
+                        self.location.join(format!("file-{index}")).display()
+                    ))
+                })
+                .collect())
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn executes_every_plan_of_a_step() {
+        let location = std::env::temp_dir().join("crazy-train-multiple-plans");
+        let _ = std::fs::remove_dir_all(&location);
+        let runner = new(vec![Box::new(CreateFilesStep {
+            location: location.clone(),
+            count: 3,
+        })]);
+        let mut report = RunReport::new(42);
+
+        assert!(runner.execute_steps(&mut report).is_ok());
+        assert_eq!(report.steps.len(), 3);
+        assert!(report
+            .steps
+            .iter()
+            .all(|step| step.status == StepStatus::Passed));
+        for index in 0..3 {
+            assert!(location.join(format!("file-{index}")).exists());
+        }
+    }
+
+    #[test]
+    fn banner_contains_seed_and_reproduce_command() {
+        let runner = new(vec![])
+            .randomizer(Randomizer::with_seed(1234))
+            .reproduce_command("FUZZ_SEED={seed} cargo test fuzz");
+
+        let banner = runner.banner();
+        assert!(banner.contains("1234"));
+        assert!(banner.contains("FUZZ_SEED=1234 cargo test fuzz"));
+        assert!(new(vec![])
+            .randomizer(Randomizer::with_seed(7))
+            .banner()
+            .contains("cargo run -- --seed 7"));
+    }
+
+    #[test]
+    fn export_plan_round_trips() {
+        let location = std::env::temp_dir().join("crazy-train-export");
+        let mut runner = new(vec![
+            Box::new(CreateFilesStep {
+                location: location.clone(),
+                count: 2,
+            }),
+            Box::new(TestStepOne {
+                location: location.clone(),
+            }),
+        ]);
+
+        for format in [PlanFormat::Json, PlanFormat::Yaml] {
+            let document = runner.export_plan(42, format).unwrap();
+            let plan = ExportedPlan::from_document(&document, format).unwrap();
+
+            assert_eq!(plan.version, EXPORT_VERSION);
+            assert_eq!(plan.seed, 42);
+            assert_eq!(plan.steps.len(), 2);
+            assert_eq!(
+                plan.steps[0].commands,
+                vec![
+                    format!("touch {}", location.join("file-0").display()),
+                    format!("touch {}", location.join("file-1").display()),
+                ]
+            );
+            assert_eq!(
+                plan.steps[0].expected_status,
+                std::collections::BTreeMap::from([(0, Outcome::Success)])
+            );
+            assert_eq!(
+                plan.steps[1].check,
+                Some(format!("test -f {}", location.join("test.txt").display()))
+            );
+            assert_eq!(runner.export_plan(42, format).unwrap(), document);
+
+            let exported: Vec<String> = plan
+                .steps
+                .into_iter()
+                .flat_map(|step| step.commands)
+                .collect();
+            let executed: Vec<String> = runner
+                .run_report()
+                .unwrap()
+                .steps
+                .into_iter()
+                .map(|step| step.command)
+                .collect();
+            assert_eq!(executed, exported);
+        }
+
+        let _ = std::fs::remove_dir_all(&location);
+    }
+
+    #[test]
+    fn dump_plan_json_describes_steps() {
+        let location = std::env::temp_dir().join("crazy-train-dump-json");
+        let runner = new(vec![Box::new(CreateFilesStep {
+            location: location.clone(),
+            count: 2,
+        })])
+        .randomizer(Randomizer::with_seed(42));
+
+        let document = runner.dump_plan_json().unwrap();
+        let plan: ExecutionPlan = serde_json::from_str(&document).unwrap();
+
+        assert_eq!(plan.seed, 42);
+        assert_eq!(plan.step_count, 1);
+        assert_eq!(
+            plan.steps[0].commands,
+            vec![
+                format!("touch {}", location.join("file-0").display()),
+                format!("touch {}", location.join("file-1").display()),
+            ]
+        );
+        assert_eq!(
+            plan.steps[0].state,
+            serde_json::to_value(runner.steps[0].to_yaml()).unwrap()
+        );
+        assert!(!document.contains('\x1b'));
+    }
+
+    #[test]
+    fn color_disabled_emits_plain_text() {
+        let buffer = SharedBuffer::default();
+        let runner = new(vec![Box::new(FixedCommandStep {
+            command: "echo ok".to_string(),
+        })])
+        .output_sink(Box::new(buffer.clone()))
+        .color(false);
+
+        let plan = runner.dump_plan().unwrap();
+        assert!(plan.contains("Execution Plan Dump"));
+        assert!(!plan.contains("\x1b["));
+        assert!(!runner.banner().contains("\x1b["));
+
+        runner.run().unwrap();
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        assert!(output.contains("Execute plan..."));
+        assert!(!output.contains("\x1b["));
+    }
+
+    #[test]
+    fn saved_plan_replays_identical_commands() {
+        let location = std::env::temp_dir().join("crazy-train-saved-plan");
+        std::fs::create_dir_all(&location).unwrap();
+        let steps = |count| -> Vec<Box<dyn StepTrait>> {
+            vec![
+                Box::new(CreateFilesStep {
+                    location: location.clone(),
+                    count,
+                }),
+                Box::new(TestStepOne {
+                    location: location.clone(),
+                }),
+                Box::new(TestStepOne {
+                    location: location.clone(),
+                }),
+            ]
+        };
+        let executed = |runner: &Runner| -> Vec<String> {
+            let report = runner.run_report().unwrap();
+            report.steps.into_iter().map(|step| step.command).collect()
+        };
+
+        for file in ["plan.json", "plan.yaml"] {
+            let path = location.join(file);
+            let runner = new(steps(2)).randomizer(Randomizer::with_seed(7));
+            runner.save_plan(&path).unwrap();
+            let document = std::fs::read_to_string(&path).unwrap();
+            let saved = ExecutionPlan::from_document(&document, PlanFormat::from_path(&path));
+            let saved: Vec<String> = saved
+                .unwrap()
+                .steps
+                .into_iter()
+                .flat_map(|step| step.commands)
+                .collect();
+            assert_eq!(saved.len(), 4);
+
+            let replay = Runner::from_plan(&path, steps(2)).unwrap();
+            assert_eq!(replay.randomizer.seed, 7);
+            assert_eq!(executed(&replay), saved);
+            assert_eq!(executed(&runner), saved);
+
+            assert!(matches!(
+                Runner::from_plan(&path, steps(3)),
+                Err(Error::PlanMismatch { index: 0 })
+            ));
+        }
+
+        let _ = std::fs::remove_dir_all(&location);
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct EnvStep {
+        value: String,
+    }
+
+    impl StepTrait for EnvStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>("echo $VALUE").with_env("VALUE", self.value.clone()))
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn saved_plan_replays_inputs_and_configuration() {
+        let location = std::env::temp_dir().join("crazy-train-saved-plan-inputs");
+        std::fs::create_dir_all(&location).unwrap();
+        let path = location.join("plan.yaml");
+        let env_step = |value: &str| -> Box<dyn StepTrait> {
+            Box::new(EnvStep {
+                value: value.to_string(),
+            })
+        };
+
+        new(vec![env_step("a")]).save_plan(&path).unwrap();
+        assert!(Runner::from_plan(&path, vec![env_step("a")]).is_ok());
+        assert!(matches!(
+            Runner::from_plan(&path, vec![env_step("b")]),
+            Err(Error::PlanMismatch { index: 0 })
+        ));
+
+        let randomizer = || Randomizer::with_seed(3).path_base("/srv");
+        let configured = new(vec![Box::new(TestStepOne {
+            location: location.clone(),
+        })])
+        .randomizer(randomizer());
+        configured.save_plan(&path).unwrap();
+        let replay = new(configured.clone_steps())
+            .randomizer(Randomizer::with_seed(99).path_base("/srv"))
+            .load_plan(&path)
+            .unwrap();
+        assert_eq!(replay.randomizer.seed, 3);
+        let paths =
+            |randomizer: &Randomizer| (0..10).map(|_| randomizer.path_any()).collect::<Vec<_>>();
+        assert_eq!(paths(&replay.randomizer.restarted()), paths(&randomizer()));
+        assert_ne!(paths(&Randomizer::with_seed(3)), paths(&randomizer()));
+
+        let _ = std::fs::remove_dir_all(&location);
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct ScopedStep {
+        scope: PathBuf,
+        command: String,
+    }
+
+    impl StepTrait for ScopedStep {
+        fn setup(&self) -> crate::errors::Result<()> {
+            Ok(std::fs::create_dir_all(&self.scope)?)
+        }
+
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>(self.command.clone()))
+        }
+
+        fn allowed_paths(&self) -> Option<Vec<PathBuf>> {
+            Some(vec![self.scope.clone()])
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[cfg(all(target_os = "linux", feature = "trace-paths"))]
+    #[test]
+    #[ignore = "requires strace"]
+    fn fails_when_writing_outside_allowed_paths() {
+        let location = std::env::temp_dir().join("crazy-train-scoped");
+        let scope = location.join("scope");
+        let _ = std::fs::remove_dir_all(&location);
+
+        let inside = new(vec![Box::new(ScopedStep {
+            scope: scope.clone(),
+            command: format!("echo ok > {}", scope.join("inside").display()),
+        })]);
+        assert!(inside.run().is_ok());
+
+        let outside = location.join("outside");
+        let escaping = new(vec![Box::new(ScopedStep {
+            scope: scope.clone(),
+            command: format!(
+                "echo ok > {} && echo ko > {}",
+                scope.join("inside").display(),
+                outside.display()
+            ),
+        })]);
+        assert!(matches!(
+            escaping.run(),
+            Err(Error::AccessOutsideScope { path, .. }) if path == outside
+        ));
+
+        let escaping = new(vec![Box::new(ScopedArgvStep {
+            scope,
+            argv: vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                format!("echo ko > {}", outside.display()),
+            ],
+        })]);
+        assert!(matches!(
+            escaping.run(),
+            Err(Error::AccessOutsideScope { path, .. }) if path == outside
+        ));
+    }
+
+    #[cfg(all(target_os = "linux", feature = "trace-paths"))]
+    #[derive(Clone, Serialize, Deserialize)]
+    struct ScopedArgvStep {
+        scope: PathBuf,
+        argv: Vec<String>,
+    }
+
+    #[cfg(all(target_os = "linux", feature = "trace-paths"))]
+    impl StepTrait for ScopedArgvStep {
+        fn setup(&self) -> crate::errors::Result<()> {
+            Ok(std::fs::create_dir_all(&self.scope)?)
+        }
+
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::with_argv::<Self>(
+                self.argv[0].clone(),
+                self.argv[1..].to_vec(),
+            ))
+        }
+
+        fn allowed_paths(&self) -> Option<Vec<PathBuf>> {
+            Some(vec![self.scope.clone()])
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[cfg(not(all(target_os = "linux", feature = "trace-paths")))]
+    #[test]
+    fn allowed_paths_are_not_enforced_without_tracing() {
+        let location = std::env::temp_dir().join("crazy-train-unscoped");
+        let runner = new(vec![Box::new(ScopedStep {
+            scope: location.join("scope"),
+            command: format!("echo ko > {}", location.join("outside").display()),
+        })]);
+        assert!(runner.run().is_ok());
+    }
+
+    #[test]
+    fn attaches_provenance_to_plans() {
+        let location = std::env::temp_dir().join("crazy-train-provenance");
+        let step = TestStepOne { location };
+        let runner = new(vec![]).randomizer(Randomizer::with_seed(42).record_provenance(true));
+
+        let plans = runner.plan_step(&step).unwrap();
+        assert_eq!(plans.len(), 1);
+        let provenance = &plans[0].provenance;
+        assert_eq!(provenance.len(), 1);
+        assert_eq!(provenance[0].kind, "string");
+        assert_eq!(provenance[0].draw, 0);
+        assert!(plans[0]
+            .command
+            .starts_with(&format!("echo {} >>", provenance[0].value)));
+
+        let next = runner.plan_step(&step).unwrap();
+        assert_eq!(next[0].provenance.len(), 1);
+        assert!(next[0].provenance[0].draw > 0);
+        assert!(next[0].command.contains(&next[0].provenance[0].value));
+
+        let runner = new(vec![]).randomizer(Randomizer::with_seed(42));
+        assert!(runner.plan_step(&step).unwrap()[0].provenance.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn kills_commands_exceeding_resource_limits() {
+        let run = |limits, command: &str| {
+            new(vec![Box::new(FixedCommandStep {
+                command: command.to_string(),
+            })])
+            .resource_limits(limits)
+            .run()
+        };
+
+        let memory = executer::RLimits {
+            address_space: Some(64 * 1024 * 1024),
+            ..Default::default()
+        };
+        assert!(run(memory, "echo ok").is_ok());
+        assert!(matches!(
+            run(memory, "x=$(head -c 200000000 /dev/zero | tr '\\0' a); echo done"),
+            Err(Error::ResourceLimitExceeded { limits, .. }) if limits == memory
+        ));
+
+        let cpu = executer::RLimits {
+            cpu_seconds: Some(1),
+            ..Default::default()
+        };
+        assert!(matches!(
+            run(cpu, "while :; do :; done"),
+            Err(Error::ResourceLimitExceeded { limits, .. }) if limits == cpu
+        ));
+        for signal in ["SEGV", "KILL"] {
+            assert!(matches!(
+                run(cpu, &format!("kill -{signal} $$")),
+                Err(Error::StepError { .. })
+            ));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn step_timeout_kills_hanging_commands() {
+        let runner = new(vec![Box::new(FixedCommandStep {
+            command: "sleep 10".to_string(),
+        })])
+        .step_timeout(Duration::from_secs(1));
+
+        let started = Instant::now();
+        assert!(matches!(runner.run(), Err(Error::Timeout { .. })));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let runner = new(vec![Box::new(HangingCheckStep)]).step_timeout(Duration::from_secs(1));
+        let started = Instant::now();
+        assert!(matches!(runner.run(), Err(Error::Timeout { .. })));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timed_out_plans_are_reported_and_tolerated() {
+        let steps = || -> Vec<Box<dyn StepTrait>> {
+            ["echo started; sleep 10", "echo ok"]
+                .into_iter()
+                .map(|command| {
+                    Box::new(FixedCommandStep {
+                        command: command.to_string(),
+                    }) as Box<dyn StepTrait>
+                })
+                .collect()
+        };
+
+        let report = new(steps())
+            .step_timeout(Duration::from_secs(1))
+            .continue_on_error(true)
+            .run_report()
+            .unwrap();
+        let statuses: Vec<StepStatus> = report.steps.iter().map(|step| step.status).collect();
+        assert_eq!(statuses, vec![StepStatus::Failed, StepStatus::Passed]);
+        assert_eq!(report.steps[0].output.as_ref().unwrap().stdout, "started\n");
+        assert!(report.steps[0].durations.plan.is_some());
+
+        let reports = new(steps())
+            .step_timeout(Duration::from_secs(1))
+            .run_all()
+            .unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].status, StepStatus::Failed);
+
+        let reports = new(vec![Box::new(HangingCheckStep)])
+            .step_timeout(Duration::from_secs(1))
+            .run_all()
+            .unwrap();
+        assert_eq!(reports[0].status, StepStatus::Failed);
+
+        let runner = new(vec![Box::new(HangingPlanStep { retries: 1 })])
+            .step_timeout(Duration::from_millis(200));
+        assert!(matches!(
+            runner.run(),
+            Err(Error::RetriesExhausted { attempts: 2, source })
+                if matches!(*source, Error::Timeout { .. })
+        ));
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct HangingCheckStep;
+
+    impl StepTrait for HangingCheckStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>("true"))
+        }
+
+        fn run_check(&self) -> Option<String> {
+            Some("sleep 10".to_string())
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct HangingPlanStep {
+        retries: u32,
+    }
+
+    impl StepTrait for HangingPlanStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>("sleep 10"))
+        }
+
+        fn retries(&self) -> u32 {
+            self.retries
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct NamedStep {
+        id: String,
+        command: String,
+        dependencies: Vec<String>,
+    }
+
+    impl NamedStep {
+        fn boxed(id: &str, dependencies: &[&str]) -> Box<dyn StepTrait> {
+            Self::with_command(id, "true", dependencies)
+        }
+
+        fn with_command(id: &str, command: &str, dependencies: &[&str]) -> Box<dyn StepTrait> {
+            Box::new(Self {
+                id: id.to_string(),
+                command: command.to_string(),
+                dependencies: dependencies.iter().map(ToString::to_string).collect(),
+            })
+        }
+    }
+
+    impl StepTrait for NamedStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            let mut plan = Plan::new::<Self>(self.command.clone());
+            plan.id.clone_from(&self.id);
+            Ok(plan)
+        }
+
+        fn dependencies(&self) -> Vec<String> {
+            self.dependencies.clone()
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct CheckedStep;
+
+    impl StepTrait for CheckedStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            let mut plan = Plan::new::<Self>("echo planned");
+            plan.id = "checked".to_string();
+            Ok(plan)
+        }
+
+        fn run_check(&self) -> Option<String> {
+            Some("echo checked".to_string())
+        }
+
+        fn run_test(&self) -> Option<String> {
+            Some("echo tested".to_string())
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    struct RecordingObserver(std::rc::Rc<RefCell<Vec<String>>>);
+
+    impl RunnerObserver for RecordingObserver {
+        fn on_step_start(&self, id: &str, index: usize) {
+            self.0.borrow_mut().push(format!("start {id} {index}"));
+        }
+
+        fn on_plan(&self, report: &StepReport) {
+            let stdout = report
+                .output
+                .as_ref()
+                .map_or("", |output| output.stdout.trim());
+            self.0.borrow_mut().push(format!(
+                "plan {} {} {:?} {stdout}",
+                report.id, report.command, report.status
+            ));
+        }
+
+        fn on_check(&self, id: &str, command: &str, output: &Output) {
+            let stdout = output.stdout.trim();
+            self.0
+                .borrow_mut()
+                .push(format!("check {id} {command} {stdout}"));
+        }
+
+        fn on_test(&self, id: &str, command: &str, output: &Output) {
+            let stdout = output.stdout.trim();
+            self.0
+                .borrow_mut()
+                .push(format!("test {id} {command} {stdout}"));
+        }
+
+        fn on_step_finish(&self, id: &str, status: StepStatus) {
+            self.0.borrow_mut().push(format!("finish {id} {status:?}"));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn observer_sees_the_step_lifecycle() {
+        let events = std::rc::Rc::new(RefCell::new(Vec::new()));
+        let runner = new(vec![
+            Box::new(CheckedStep),
+            NamedStep::with_command("generate", "exit 1", &[]),
+            NamedStep::boxed("migrate", &["generate"]),
+        ])
+        .continue_on_error(true)
+        .observer(Box::new(RecordingObserver(events.clone())));
+
+        assert!(runner.run().is_err());
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                "start checked 0",
+                "plan checked echo planned Passed planned",
+                "check checked echo checked checked",
+                "test checked echo tested tested",
+                "finish checked Passed",
+                "start generate 1",
+                "plan generate exit 1 Failed ",
+                "finish generate Failed",
+                "finish migrate Skipped",
+            ]
+        );
+    }
+
+    #[test]
+    fn steps_are_skipped_when_a_dependency_fails() {
+        let runner = new(vec![
+            NamedStep::with_command("generate", "exit 1", &[]),
+            NamedStep::boxed("migrate", &["generate"]),
+            NamedStep::boxed("lint", &[]),
+            NamedStep::boxed("seed", &["migrate"]),
+            NamedStep::boxed("serve", &["lint"]),
+        ])
+        .continue_on_error(true);
+
+        let statuses: Vec<_> = runner
+            .run_all()
+            .unwrap()
+            .into_iter()
+            .map(|step| (step.id, step.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("generate".to_string(), StepStatus::Failed),
+                ("migrate".to_string(), StepStatus::Skipped),
+                ("lint".to_string(), StepStatus::Passed),
+                ("seed".to_string(), StepStatus::Skipped),
+                ("serve".to_string(), StepStatus::Passed),
+            ]
+        );
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct BuildStep;
+
+    impl StepTrait for BuildStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            let mut plan = Plan::new::<Self>("true");
+            plan.id = "build".to_string();
+            Ok(plan)
+        }
+
+        fn plans(&self, randomizer: &Randomizer) -> Result<Vec<Plan>> {
+            let mut package = self.plan(randomizer)?;
+            package.id = "package".to_string();
+            Ok(vec![self.plan(randomizer)?, package])
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("serialize")
+        }
+    }
+
+    #[test]
+    fn dependencies_match_every_plan_and_skipped_steps() {
+        let steps = || {
+            vec![
+                NamedStep::boxed("generate", &[]),
+                Box::new(BuildStep),
+                NamedStep::boxed("publish", &["package"]),
+                NamedStep::boxed("deploy", &["generate", "build"]),
+            ]
+        };
+        let statuses = |runner: Runner| -> Vec<_> {
+            runner
+                .run_all()
+                .unwrap()
+                .into_iter()
+                .map(|step| (step.id, step.status))
+                .collect()
+        };
+
+        assert_eq!(
+            statuses(new(steps())),
+            vec![
+                ("generate".to_string(), StepStatus::Passed),
+                ("build".to_string(), StepStatus::Passed),
+                ("package".to_string(), StepStatus::Passed),
+                ("publish".to_string(), StepStatus::Passed),
+                ("deploy".to_string(), StepStatus::Passed),
+            ]
+        );
+        assert_eq!(
+            statuses(new(steps()).start_from(2)),
+            vec![
+                ("publish".to_string(), StepStatus::Passed),
+                ("deploy".to_string(), StepStatus::Passed),
+            ]
+        );
+    }
+
+    #[test]
+    fn dependency_dot_describes_dag() {
+        let runner = new(vec![
+            NamedStep::boxed("create", &[]),
+            NamedStep::boxed("migrate", &["create"]),
+            NamedStep::boxed("seed", &["create", "migrate"]),
+            NamedStep::boxed("serve", &["config"]),
+        ]);
+
+        let dot = runner.dependency_dot().unwrap();
+        assert!(dot.starts_with("digraph steps {"));
+        assert!(dot.contains("step0 [label=\"create\"];"));
+        assert!(dot.contains("step1 [label=\"migrate\"];"));
+        assert!(dot.contains("step2 [label=\"seed\"];"));
+        assert!(dot.contains("step0 -> step1;"));
+        assert!(dot.contains("step0 -> step2;"));
+        assert!(dot.contains("step1 -> step2;"));
+        assert!(dot.contains("\"missing: config\" -> step3 [style=dashed];"));
+        assert!(!dot.contains("red"));
+    }
+
+    #[test]
+    fn dependency_dot_matches_every_plan_without_drawing() {
+        let runner = new(vec![
+            Box::new(TestStepOne {
+                location: std::env::temp_dir(),
+            }),
+            Box::new(BuildStep),
+            NamedStep::boxed("publish", &["package"]),
+        ])
+        .randomizer(Randomizer::with_seed(42));
+
+        let dot = runner.dependency_dot().unwrap();
+        assert!(dot.contains("step1 [label=\"build\"];"));
+        assert!(dot.contains("step1 -> step2;"));
+        assert!(!dot.contains("missing"));
+        assert_eq!(
+            runner.randomizer.number_between(0, 1_000_000),
+            Randomizer::with_seed(42).number_between(0, 1_000_000)
+        );
+    }
+
+    #[test]
+    fn dependency_dot_marks_cycles() {
+        let runner = new(vec![
+            NamedStep::boxed("a", &["c"]),
+            NamedStep::boxed("b", &["a"]),
+            NamedStep::boxed("c", &["b"]),
+            NamedStep::boxed("d", &["a"]),
+        ]);
+
+        let dot = runner.dependency_dot().unwrap();
+        assert!(dot.contains("step2 -> step0 [color=red];"));
+        assert!(dot.contains("step0 -> step1 [color=red];"));
+        assert!(dot.contains("step1 -> step2 [color=red];"));
+        assert!(dot.contains("step0 -> step3;"));
+        assert!(dot.contains("step3 [label=\"d\"];"));
+        assert!(dot.contains("step0 [label=\"a\", color=red];"));
+    }
+
+    #[test]
+    fn shuffled_steps_order_is_deterministic() {
+        let shuffled = |seed| {
+            let steps = ["a", "b", "c", "d", "e", "f"]
+                .iter()
+                .map(|id| NamedStep::boxed(id, &[]))
+                .collect();
+            new(steps)
+                .randomizer(Randomizer::with_seed(seed))
+                .shuffle_steps(true)
+                .color(false)
+        };
+        let order = |runner: &Runner| {
+            runner
+                .execution_plan()
+                .unwrap()
+                .steps
+                .into_iter()
+                .map(|step| step.id)
+                .collect::<Vec<_>>()
+        };
+
+        let runner = shuffled(42);
+        assert_eq!(order(&runner), vec!["e", "f", "d", "c", "b", "a"]);
+        assert_eq!(order(&runner), order(&shuffled(42)));
+        assert_ne!(order(&runner), order(&shuffled(7)));
+        assert!(runner.dump_plan().unwrap().contains("Step 1: e"));
+        assert_eq!(
+            order(&shuffled(42).shuffle_steps(false)),
+            vec!["a", "b", "c", "d", "e", "f"]
+        );
+    }
+
+    #[test]
+    fn random_step_count_is_deterministic() {
+        let runner = |seed| {
+            let steps = ["a", "b", "c", "d", "e", "f"]
+                .iter()
+                .map(|id| NamedStep::boxed(id, &[]))
+                .collect();
+            new(steps)
+                .randomizer(Randomizer::with_seed(seed))
+                .random_step_count(2, 4)
+        };
+        let executed = |runner: &Runner| {
+            runner
+                .execution_plan()
+                .unwrap()
+                .steps
+                .into_iter()
+                .map(|step| step.id)
+                .collect::<Vec<_>>()
+        };
+
+        let counts: Vec<usize> = (0..30).map(|seed| executed(&runner(seed)).len()).collect();
+        assert!(counts.iter().all(|count| (2..=4).contains(count)));
+        assert!((2..=4).all(|count| counts.contains(&count)));
+        assert_eq!(
+            counts,
+            (0..30)
+                .map(|seed| executed(&runner(seed)).len())
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(executed(&runner(42)), vec!["a", "b", "c"]);
+        assert_eq!(
+            executed(&runner(42).shuffle_steps(true)).len(),
+            executed(&runner(42)).len()
+        );
+        assert_eq!(executed(&runner(42).random_step_count(10, 12)).len(), 6);
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct AssertingStep {}
+
+    impl StepTrait for AssertingStep {
+        fn plan(&self, _randomizer: &Randomizer) -> Result<Plan> {
+            Ok(Plan::new::<Self>("echo starting"))
+        }
+
+        fn is_success(
             &self,
             execution_result: &Output,
             _plan_ctx: &PlanCtx,
         ) -> Result<bool, &'static str> {
-            if execution_result.status_code == Some(1) {
-                Ok(true)
-            } else {
-                Err("status code should be 1")
-            }
+            crate::step_assert!(
+                execution_result.stdout.contains("ready"),
+                "server should be ready"
+            );
+            Ok(true)
         }
 
         fn to_yaml(&self) -> serde_yaml::Value {
@@ -265,20 +3923,120 @@ mod tests {
     }
 
     #[test]
-    fn can_run() {
-        let base_location = std::env::temp_dir().join("crazy-train");
-        let location_step_1 = base_location.join("step-1");
-        let location_step_2 = base_location.join("step-2");
+    fn step_assert_reports_command_context() {
+        let error = new(vec![Box::new(AssertingStep {})])
+            .run()
+            .unwrap_err()
+            .to_string();
 
-        let step_one = TestStepOne {
-            location: location_step_1,
+        assert!(error.contains("server should be ready"));
+        assert!(error.contains("execution_result.stdout.contains(\"ready\")"));
+        assert!(error.contains("command: sh -c 'echo starting'"));
+        assert!(error.contains("stdout: starting"));
+    }
+
+    #[test]
+    fn priming_commands_run_before_steps() {
+        let location = std::env::temp_dir().join("crazy-train-priming");
+        let _ = std::fs::remove_dir_all(&location);
+        std::fs::create_dir_all(&location).unwrap();
+        let log = location.join("log.txt");
+        let step = |name: &str| -> Vec<Box<dyn StepTrait>> {
+            vec![Box::new(FixedCommandStep {
+                command: format!("echo {name} >> {}", log.display()),
+            })]
         };
-        let step_two = TestStepTwo {
-            location: location_step_2,
+
+        let runner = new(step("step")).priming_commands(vec![
+            format!("echo first >> {}", log.display()),
+            format!("echo second >> {}", log.display()),
+        ]);
+        assert!(runner.run().is_ok());
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "first\nsecond\nstep\n"
+        );
+
+        std::fs::remove_file(&log).unwrap();
+        let runner = new(step("step")).priming_commands(vec!["exit 3".to_string()]);
+        let mut report = RunReport::new(42);
+        assert!(matches!(
+            runner.execute_steps(&mut report),
+            Err(Error::StepError {
+                kind: step::Kind::Priming,
+                ..
+            })
+        ));
+        assert!(report.steps.is_empty());
+        assert!(!log.exists());
+
+        let runner = new(step("step"))
+            .priming_commands(vec!["exit 3".to_string()])
+            .fail_on_priming_error(false);
+        assert!(runner.run().is_ok());
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "step\n");
+    }
+
+    #[test]
+    fn notifies_each_new_failure_once() {
+        let location = std::env::temp_dir().join("crazy-train-new-failure");
+        std::fs::create_dir_all(&location).unwrap();
+        let message = location.join("message.txt");
+        let notified = std::rc::Rc::new(RefCell::new(Vec::new()));
+
+        let runner = new(vec![Box::new(FixedCommandStep {
+            command: format!("cat {} >&2; exit 1", message.display()),
+        })])
+        .on_new_failure({
+            let notified = notified.clone();
+            move |step, fingerprint| {
+                assert_eq!(step.status, StepStatus::Failed);
+                notified.borrow_mut().push(fingerprint.to_string());
+            }
+        });
+
+        for content in [
+            "error: timeout after 12s",
+            "error: timeout after 30s",
+            "error: timeout after 12s",
+            "error: permission denied",
+        ] {
+            std::fs::write(&message, content).unwrap();
+            assert!(runner.run().is_err());
+        }
+
+        let notified = notified.borrow();
+        assert_eq!(notified.len(), 2);
+        assert_ne!(notified[0], notified[1]);
+    }
+
+    #[test]
+    fn isolates_steps_from_each_other() {
+        let location = std::env::temp_dir().join("crazy-train-isolation");
+        let initial_dir = location.join("initial");
+        let workdir = location.join("workdir");
+        let _ = std::fs::remove_dir_all(&location);
+        std::fs::create_dir_all(initial_dir.join("nested")).unwrap();
+        std::fs::write(initial_dir.join("nested").join("data.txt"), "data").unwrap();
+
+        let data = workdir.join("nested").join("data.txt");
+        let steps = || -> Vec<Box<dyn StepTrait>> {
+            vec![
+                Box::new(FixedCommandStep {
+                    command: format!("rm {}", data.display()),
+                }),
+                Box::new(FixedCommandStep {
+                    command: format!("test -f {}", data.display()),
+                }),
+            ]
         };
-        let randomaizer = Randomizer::with_seed(42);
-        let runner = new(vec![Box::new(step_one), Box::new(step_two)]).randomizer(randomaizer);
 
+        let runner = new(steps()).workdir(&workdir).isolate_steps(&initial_dir);
         assert!(runner.run().is_ok());
+        assert!(!workdir.exists());
+        assert!(initial_dir.join("nested").join("data.txt").exists());
+
+        copy_dir(&initial_dir, &workdir).unwrap();
+        assert!(new(steps()).workdir(&workdir).run().is_err());
     }
 }
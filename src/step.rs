@@ -1,14 +1,19 @@
 //! This module defines the `StepTrait` trait, which outlines the necessary methods that
 //! any step in the execution process must implement. It also defines the `Plan` struct,
-//! which encapsulates a command to be executed as part of a step.
+//! which encapsulates a command to be executed as part of a step, and the [`snapshot_step`]
+//! helper comparing the output of a command with a snapshot file.
 //!
 
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
 
 use crate::{
     errors,
     executer::{self, Output},
-    randomizer::Randomizer,
+    randomizer::{Provenance, Randomizer},
 };
 
 /// Enum representing the different types of steps that can be executed.
@@ -18,37 +23,175 @@ pub enum Kind {
     Plan,
     Check,
     Test,
+    Priming,
+}
+
+/// The semantic meaning of a command's exit status code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// The command succeeded and the runner continues with the step's check and test.
+    Success,
+    /// The command did not fail, but the step's check and test are skipped.
+    Skip,
+    /// The command failed.
+    Failure,
 }
 
 /// A trait that defines the behavior required for steps in the execution process.
+///
+/// Steps must be `Clone` and `'static`, so [`StepClone`] can duplicate a boxed step, e.g. to
+/// replay or re-run the steps of a runner with [`crate::Runner::clone_steps`]. Deriving
+/// `Clone` is enough, [`StepClone`] is implemented for every such step.
 #[allow(clippy::module_name_repetitions)]
-pub trait StepTrait {
+pub trait StepTrait: StepClone {
     /// Prepares the setup by creating necessary directories and performing initialization steps.
     ///
     /// # Errors
     ///
     /// Returns an error if the setup fails, such as when it is unable to create the required directory.
+    #[allow(clippy::result_large_err)]
     fn setup(&self) -> errors::Result<()> {
         Ok(())
     }
+
+    /// Cleans up what [`StepTrait::setup`] and the plans created, e.g. removes temporary
+    /// directories, so artifacts do not accumulate across runs.
+    ///
+    /// The runner tears a step down after each of its executions, whatever the outcome: when
+    /// the setup, a plan, the check or the test fails, teardown still runs before the runner
+    /// stops on the failure. It also runs after each warmup execution and each retry attempt.
+    /// Steps that are skipped are not set up, and not torn down.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cleanup fails. It fails the run unless the step already failed,
+    /// in which case the step's error is reported.
+    #[allow(clippy::result_large_err)]
+    fn teardown(&self) -> errors::Result<()> {
+        Ok(())
+    }
     /// Generates a plan for execution.
     ///
     /// # Errors
     ///
     /// when could not prepare the plan
+    #[allow(clippy::result_large_err)]
     fn plan(&self, randomizer: &Randomizer) -> errors::Result<Plan>;
 
+    /// Generates the plans of the step, executed in order. Each plan goes through
+    /// [`StepTrait::is_success`] and is reported as its own entry of the run report, under the
+    /// id of the plan. When a plan is not successful the remaining plans of the step are not
+    /// executed, and the check and test of the step run once after its last plan.
+    ///
+    /// By default, the step yields the single plan returned by [`StepTrait::plan`].
+    ///
+    /// # Errors
+    ///
+    /// when could not prepare the plans
+    #[allow(clippy::result_large_err)]
+    fn plans(&self, randomizer: &Randomizer) -> errors::Result<Vec<Plan>> {
+        Ok(vec![self.plan(randomizer)?])
+    }
+
+    /// Maps the exit status codes of the plan command to their semantic [`Outcome`]. Codes
+    /// missing from the map are treated as [`Outcome::Failure`].
+    ///
+    /// By default only status code 0 is a success.
+    fn status_semantics(&self) -> HashMap<i32, Outcome> {
+        HashMap::from([(0, Outcome::Success)])
+    }
+
     /// Determines if the execution result indicates success for this step.
     ///
     /// the bool result point if the runner should continue to the next steps or not.
     ///
+    /// By default, when the plan expects its stdout to match a pattern, see
+    /// [`Plan::expect_stdout_regex`], the step succeeds only when it does, regardless of the
+    /// status code. Otherwise the status code is translated with
+    /// [`StepTrait::status_semantics`].
+    ///
     /// # Errors
     /// When plan result parsing is not the expected behavior.
     fn is_success(
         &self,
         execution_result: &Output,
         plan_ctx: &PlanCtx,
-    ) -> Result<bool, &'static str>;
+    ) -> Result<bool, &'static str> {
+        if let Some(pattern) = &plan_ctx.expect_stdout {
+            return if pattern.is_match(&execution_result.stdout) {
+                Ok(true)
+            } else {
+                Err("stdout does not match the expected pattern")
+            };
+        }
+
+        let outcome = execution_result
+            .status_code
+            .and_then(|code| self.status_semantics().get(&code).copied());
+
+        match outcome {
+            Some(Outcome::Success) => Ok(true),
+            Some(Outcome::Skip) => Ok(false),
+            Some(Outcome::Failure) | None => Err("status code is mapped to a failure"),
+        }
+    }
+
+    /// Describes why [`StepTrait::is_success`] failed beyond its static message, e.g. with a
+    /// diff of the expected and the actual output. The runner appends the details to the
+    /// description of the [`Error::StepError`](crate::Error::StepError) it reports.
+    ///
+    /// By default, there are no details.
+    fn failure_details(&self, _execution_result: &Output, _plan_ctx: &PlanCtx) -> Option<String> {
+        None
+    }
+
+    /// Determines whether the step should run, based on the context shared by the previous
+    /// steps of the run. When `false`, the step is skipped and reported as such.
+    fn should_run(&self, _ctx: &RunContext) -> bool {
+        true
+    }
+
+    /// Updates the context shared with the following steps of the run. Invoked after the
+    /// execution result was accepted by [`StepTrait::is_success`].
+    fn update_context(&self, _execution_result: &Output, _ctx: &mut RunContext) {}
+
+    /// Returns the ids of the plans of the steps this step depends on, i.e. the steps that must
+    /// run before it. Dependencies are rendered by [`crate::Runner::dependency_dot`].
+    ///
+    /// The runner skips the step, reporting it as skipped, unless every dependency is the id of
+    /// any plan of a step that ran earlier in the same run with all of its plans passing. Steps
+    /// skipped by [`crate::Runner::start_from`] count as passed, since they are assumed to have
+    /// run before.
+    ///
+    /// By default, a step has no dependencies.
+    fn dependencies(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Optionally returns the paths the plan commands are allowed to write under. When set, the
+    /// commands are traced and the step fails with [`errors::Error::AccessOutsideScope`] when
+    /// they write to any other path. `/dev/null` is always allowed.
+    ///
+    /// Tracing requires the `trace-paths` feature, Linux and `strace`. Otherwise the allowlist
+    /// is not enforced and a warning is printed instead.
+    fn allowed_paths(&self) -> Option<Vec<PathBuf>> {
+        None
+    }
+
+    /// Returns how many more times the runner attempts the step when it does not pass, e.g.
+    /// because the command under test is flaky. Before each attempt the step is set up and
+    /// planned again, so new random inputs are generated.
+    ///
+    /// A step is attempted again when one of its plans is not successful, i.e.
+    /// [`StepTrait::is_success`] returns `Ok(false)` or an error, or when its check or test
+    /// fails. When the last attempt fails, the runner reports
+    /// [`errors::Error::RetriesExhausted`] with the number of attempts.
+    ///
+    /// By default, a step is not retried.
+    fn retries(&self) -> u32 {
+        0
+    }
 
     /// Optionally returns a command to run as a check after the execution of the plan.
     fn run_check(&self) -> Option<String> {
@@ -64,17 +207,101 @@ pub trait StepTrait {
     fn to_yaml(&self) -> serde_yaml::Value;
 }
 
+/// Clones a step behind a trait object, which `Clone` itself can not do.
+///
+/// Implemented for every step that is `Clone`, so it never needs to be implemented by hand.
+#[allow(clippy::module_name_repetitions)]
+pub trait StepClone {
+    /// Returns a boxed clone of the step.
+    fn clone_box(&self) -> Box<dyn StepTrait>;
+}
+
+impl<T> StepClone for T
+where
+    T: StepTrait + Clone + 'static,
+{
+    fn clone_box(&self) -> Box<dyn StepTrait> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn StepTrait> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Returns an error from [`StepTrait::is_success`] when the condition does not hold.
+///
+/// The error describes the message together with the failed condition and its location. The
+/// runner reports it along with the command that was executed and its output, so the
+/// assertion does not need to format them.
+///
+/// # Example
+///
+/// ```rust
+/// use crazy_train::{executer::Output, step::PlanCtx, step_assert};
+/// fn is_success(output: &Output, _plan_ctx: &PlanCtx) -> Result<bool, &'static str> {
+///     step_assert!(output.status_code == Some(0), "command should succeed");
+///     Ok(true)
+/// }
+///
+/// let error = is_success(&Output::default(), &PlanCtx::default()).unwrap_err();
+/// assert!(error.starts_with("command should succeed (assertion `output.status_code == Some(0)` failed at"));
+/// ```
+#[macro_export]
+macro_rules! step_assert {
+    ($condition:expr, $message:literal $(,)?) => {
+        if !$condition {
+            return Err(concat!(
+                $message,
+                " (assertion `",
+                stringify!($condition),
+                "` failed at ",
+                file!(),
+                ":",
+                line!(),
+                ")"
+            ));
+        }
+    };
+}
+
 /// A struct that represents a plan for executing a command as part of a step.
 #[derive(Debug, Clone)]
 pub struct Plan {
     pub id: String,
     pub command: String,
+    /// The commands run one after the other, see [`Plan::with_commands`]. Empty for a plan
+    /// with a single command.
+    pub commands: Vec<String>,
+    /// The program and the arguments run without a shell, see [`Plan::with_argv`]. Empty for a
+    /// shell command.
+    pub argv: Vec<String>,
     pub ctx: PlanCtx,
+    /// The generated values used while planning, recorded when the randomizer has
+    /// [`Randomizer::record_provenance`] enabled.
+    pub provenance: Vec<Provenance>,
+    /// Data written to the stdin of the command, see [`Plan::with_stdin`].
+    pub stdin: Option<String>,
+    /// Environment variables the command runs with, see [`Plan::with_env`].
+    pub env: HashMap<String, String>,
+    /// The directory the command runs in, see [`Plan::with_cwd`].
+    pub cwd: Option<PathBuf>,
 }
 
 #[derive(Default, Debug, Clone)]
 pub struct PlanCtx {
     pub vars: HashMap<String, String>,
+    /// The pattern stdout must match for the plan to succeed, see
+    /// [`Plan::expect_stdout_regex`].
+    pub expect_stdout: Option<Regex>,
+}
+
+/// Context shared between the steps of a single run.
+#[derive(Default, Debug, Clone)]
+pub struct RunContext {
+    pub vars: HashMap<String, String>,
 }
 
 impl Plan {
@@ -83,8 +310,121 @@ impl Plan {
     /// # Errors
     ///
     /// on shell command failure.
+    #[allow(clippy::result_large_err)]
     pub fn execute(&self) -> errors::Result<executer::Output> {
-        executer::run_sh(&self.command)
+        self.execute_with(&executer::ExecOptions::default())
+    }
+
+    /// Executes the command defined in the plan with the given options.
+    ///
+    /// # Errors
+    ///
+    /// on shell command failure.
+    #[allow(clippy::result_large_err)]
+    pub fn execute_with(
+        &self,
+        options: &executer::ExecOptions,
+    ) -> errors::Result<executer::Output> {
+        let options = self.exec_options(options);
+        match self.argv.split_first() {
+            Some((program, args)) => executer::run_argv_with(program, args, &options),
+            None => executer::run_sh_with(&self.command, &options),
+        }
+    }
+
+    /// Executes the commands of the plan one after the other, see [`Plan::with_commands`],
+    /// stopping at the first command exiting with a non-zero status code. A plan with a single
+    /// command runs just that command.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::{step::Plan, Error};
+    /// struct Edit;
+    /// let plan = Plan::with_commands::<Edit>(vec!["echo a".to_string(), "false".to_string()]);
+    /// # #[cfg(unix)]
+    /// assert!(matches!(plan.execute_all(), Err(Error::CommandFailed { index: 1, .. })));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [`errors::Error::CommandFailed`] with the outputs gathered so far when a command exits
+    /// with a non-zero status code, or the error of a command that could not be run.
+    #[allow(clippy::result_large_err)]
+    pub fn execute_all(&self) -> errors::Result<Vec<executer::Output>> {
+        let options = self.exec_options(&executer::ExecOptions::default());
+        let commands = if self.commands.is_empty() {
+            std::slice::from_ref(&self.command)
+        } else {
+            self.commands.as_slice()
+        };
+
+        let mut outputs = Vec::with_capacity(commands.len());
+        for (index, command) in commands.iter().enumerate() {
+            let output = if self.commands.is_empty() {
+                self.execute()?
+            } else {
+                executer::run_sh_with(command, &options)?
+            };
+            let failed = output.status_code != Some(0);
+            outputs.push(output);
+            if failed {
+                return Err(errors::Error::CommandFailed { index, outputs });
+            }
+        }
+        Ok(outputs)
+    }
+
+    /// Returns the given options with the stdin, the environment variables and the working
+    /// directory of the plan.
+    pub(crate) fn exec_options(&self, options: &executer::ExecOptions) -> executer::ExecOptions {
+        let mut options = options.clone();
+        if self.stdin.is_some() {
+            options.stdin.clone_from(&self.stdin);
+        }
+        if self.cwd.is_some() {
+            options.cwd.clone_from(&self.cwd);
+        }
+        options.env.extend(
+            self.env
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        options
+    }
+
+    /// Writes `stdin` to the stdin of the command when the plan is executed.
+    #[must_use]
+    pub fn with_stdin(mut self, stdin: impl Into<String>) -> Self {
+        self.stdin = Some(stdin.into());
+        self
+    }
+
+    /// Sets the environment variable `key` to `value` for the command, overriding the
+    /// inherited one. The environment of the current process is left untouched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::step::Plan;
+    /// struct Serve;
+    /// let plan = Plan::new::<Serve>("echo $APP_ENV").with_env("APP_ENV", "test");
+    /// # #[cfg(unix)]
+    /// assert_eq!(plan.execute().unwrap().stdout, "test\n");
+    /// ```
+    #[must_use]
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Runs the command in the directory `cwd` instead of the current directory. Executing
+    /// the plan fails with [`errors::Error::WorkingDirectoryNotFound`] when the directory does
+    /// not exist, e.g. because the setup of the step did not create it.
+    #[must_use]
+    pub fn with_cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(cwd.into());
+        self
     }
 
     #[must_use]
@@ -92,7 +432,75 @@ impl Plan {
         Self {
             id: std::any::type_name::<T>().to_string(),
             command: command.into(),
+            commands: Vec::new(),
+            argv: Vec::new(),
             ctx: PlanCtx::default(),
+            provenance: Vec::new(),
+            stdin: None,
+            env: HashMap::new(),
+            cwd: None,
+        }
+    }
+
+    /// Creates a plan running `commands` one after the other, stopping at the first failing
+    /// one. The [`Plan::command`] of the plan runs every command in its own subshell on its own
+    /// lines and exits with the status of the first failing one, so the runner and
+    /// [`Plan::execute`] run them with the same semantics as [`Plan::execute_all`]: a comment
+    /// or a `cd` in one command does not affect the next one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::step::Plan;
+    /// struct Edit;
+    /// let plan = Plan::with_commands::<Edit>(vec![
+    ///     "cd / # leave".to_string(),
+    ///     "echo $PWD | grep -qv '^/$'".to_string(),
+    /// ]);
+    /// # #[cfg(unix)]
+    /// assert_eq!(plan.execute().unwrap().status_code, Some(0));
+    /// # #[cfg(unix)]
+    /// assert_eq!(plan.execute_all().unwrap().len(), 2);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// When `commands` is empty.
+    #[must_use]
+    pub fn with_commands<T>(commands: Vec<String>) -> Self {
+        assert!(!commands.is_empty(), "a plan needs at least one command");
+        let command = commands
+            .iter()
+            .map(|command| format!("(\n{command}\n)"))
+            .collect::<Vec<_>>()
+            .join(" || exit\n");
+        Self {
+            commands,
+            ..Self::new::<T>(command)
+        }
+    }
+
+    /// Creates a plan running `program` directly with the arguments `args`, without a shell,
+    /// so every argument reaches the program verbatim, see [`executer::run_argv`]. The
+    /// [`Plan::command`] of the plan renders the program with its quoted arguments.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::step::Plan;
+    /// struct Echo;
+    /// let plan = Plan::with_argv::<Echo>("echo", vec!["a b".to_string(), ";".to_string()]);
+    /// assert_eq!(plan.command, "echo 'a b' ';'");
+    /// # #[cfg(unix)]
+    /// assert_eq!(plan.execute().unwrap().stdout, "a b ;\n");
+    /// ```
+    #[must_use]
+    pub fn with_argv<T>(program: impl Into<String>, args: Vec<String>) -> Self {
+        let program = program.into();
+        let command = executer::render_argv(&program, &args);
+        Self {
+            argv: std::iter::once(program).chain(args).collect(),
+            ..Self::new::<T>(command)
         }
     }
 
@@ -101,7 +509,407 @@ impl Plan {
         Self {
             id: std::any::type_name::<T>().to_string(),
             command: command.into(),
-            ctx: PlanCtx { vars },
+            commands: Vec::new(),
+            argv: Vec::new(),
+            ctx: PlanCtx {
+                vars,
+                ..Default::default()
+            },
+            provenance: Vec::new(),
+            stdin: None,
+            env: HashMap::new(),
+            cwd: None,
+        }
+    }
+
+    /// Judges the plan by its stdout instead of its status code: with the default
+    /// [`StepTrait::is_success`], the plan succeeds only when stdout matches `pattern`,
+    /// whatever the status code.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use crazy_train::step::Plan;
+    /// struct Deploy;
+    /// let plan = Plan::new::<Deploy>("deploy --dry-run").expect_stdout_regex(r"^OK\b");
+    /// assert!(plan.is_ok());
+    /// assert!(Plan::new::<Deploy>("deploy").expect_stdout_regex("(").is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// When `pattern` is not a valid regular expression.
+    #[allow(clippy::result_large_err)]
+    pub fn expect_stdout_regex(mut self, pattern: &str) -> errors::Result<Self> {
+        self.ctx.expect_stdout = Some(Regex::new(pattern)?);
+        Ok(self)
+    }
+}
+
+/// The environment variable enabling the update mode of [`snapshot_step`].
+pub const UPDATE_SNAPSHOTS_ENV: &str = "UPDATE_SNAPSHOTS";
+
+/// A step succeeding when the stdout of its command matches a snapshot file.
+///
+/// See [`snapshot_step`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotStep {
+    command: String,
+    snapshot_path: PathBuf,
+    update: bool,
+}
+
+/// Creates a step running `command` and comparing its stdout with the contents of the
+/// snapshot file at `snapshot_path`.
+///
+/// Both are normalized before comparing: line endings become `\n`, trailing whitespace is
+/// trimmed from each line and trailing empty lines are ignored. When they differ, the actual
+/// stdout is written next to the snapshot with a `.new` extension and the plan fails, with the
+/// unified diff between the two in the description of the error, see
+/// [`StepTrait::failure_details`].
+///
+/// When the `UPDATE_SNAPSHOTS` environment variable is set to `1` while the step is created,
+/// the snapshot file is rewritten with the actual stdout instead.
+#[must_use]
+pub fn snapshot_step(
+    command: impl Into<String>,
+    snapshot_path: impl Into<PathBuf>,
+) -> SnapshotStep {
+    SnapshotStep {
+        command: command.into(),
+        snapshot_path: snapshot_path.into(),
+        update: update_requested(std::env::var(UPDATE_SNAPSHOTS_ENV).ok().as_deref()),
+    }
+}
+
+/// Returns whether the value of [`UPDATE_SNAPSHOTS_ENV`] enables the update mode.
+fn update_requested(value: Option<&str>) -> bool {
+    value == Some("1")
+}
+
+/// Normalizes line endings, trailing whitespace and trailing empty lines of a snapshot.
+fn normalize_snapshot(content: &str) -> String {
+    let mut lines: Vec<&str> = content.lines().map(str::trim_end).collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines.iter().flat_map(|line| [line, "\n"]).collect()
+}
+
+impl SnapshotStep {
+    /// Returns the path the actual stdout is written to on a mismatch.
+    fn new_snapshot_path(&self) -> PathBuf {
+        let mut path = self.snapshot_path.clone().into_os_string();
+        path.push(".new");
+        PathBuf::from(path)
+    }
+}
+
+impl StepTrait for SnapshotStep {
+    fn plan(&self, _randomizer: &Randomizer) -> errors::Result<Plan> {
+        Ok(Plan::new::<Self>(self.command.clone()))
+    }
+
+    fn is_success(
+        &self,
+        execution_result: &Output,
+        _plan_ctx: &PlanCtx,
+    ) -> Result<bool, &'static str> {
+        if execution_result.status_code != Some(0) {
+            return Err("snapshot command not finish with status code 0");
+        }
+
+        let actual = normalize_snapshot(&execution_result.stdout);
+        let new_snapshot_path = self.new_snapshot_path();
+        let _ = std::fs::remove_file(&new_snapshot_path);
+
+        if self.update {
+            if let Some(parent) = self.snapshot_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|_| "could not create snapshot directory")?;
+            }
+            std::fs::write(&self.snapshot_path, actual).map_err(|_| "could not write snapshot")?;
+            return Ok(true);
+        }
+
+        let expected =
+            std::fs::read_to_string(&self.snapshot_path).map_err(|_| "could not read snapshot")?;
+        let expected = normalize_snapshot(&expected);
+        if expected == actual {
+            return Ok(true);
+        }
+
+        std::fs::write(new_snapshot_path, actual).map_err(|_| "could not write new snapshot")?;
+        Err("stdout does not match the snapshot, see the .new file next to it")
+    }
+
+    /// Returns the unified diff between the snapshot and the actual stdout written next to it.
+    fn failure_details(&self, _execution_result: &Output, _plan_ctx: &PlanCtx) -> Option<String> {
+        let new_snapshot_path = self.new_snapshot_path();
+        let expected = normalize_snapshot(&std::fs::read_to_string(&self.snapshot_path).ok()?);
+        let actual = std::fs::read_to_string(&new_snapshot_path).ok()?;
+        let diff = TextDiff::from_lines(&expected, &actual)
+            .unified_diff()
+            .context_radius(3)
+            .header(
+                &self.snapshot_path.display().to_string(),
+                &new_snapshot_path.display().to_string(),
+            )
+            .to_string();
+        Some(diff.trim_end().to_string())
+    }
+
+    fn to_yaml(&self) -> serde_yaml::Value {
+        serde_yaml::to_value(self).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{runner, Error};
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct KeywordStep {
+        command: String,
+    }
+
+    impl StepTrait for KeywordStep {
+        fn plan(&self, _randomizer: &Randomizer) -> errors::Result<Plan> {
+            Plan::new::<Self>(self.command.clone()).expect_stdout_regex(r"status: (ok|passed)\b")
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).unwrap_or_default()
+        }
+    }
+
+    #[test]
+    fn expect_stdout_regex_ignores_status_code() {
+        let step = KeywordStep {
+            command: "echo working; echo status: passed; exit 3".to_string(),
+        };
+        assert!(runner::new(vec![Box::new(step)]).run().is_ok());
+    }
+
+    #[test]
+    fn expect_stdout_regex_fails_without_match() {
+        let step = KeywordStep {
+            command: "echo working; echo status: okay".to_string(),
+        };
+        let result = runner::new(vec![Box::new(step)]).run();
+        assert!(matches!(
+            result,
+            Err(Error::StepError {
+                kind: Kind::Plan,
+                description,
+                ..
+            }) if description == "stdout does not match the expected pattern"
+        ));
+    }
+
+    #[test]
+    fn expect_stdout_regex_rejects_invalid_pattern() {
+        let result = Plan::new::<KeywordStep>("true").expect_stdout_regex("[unclosed");
+        assert!(matches!(result, Err(Error::Regex(_))));
+    }
+
+    fn snapshot(name: &str, content: &str) -> PathBuf {
+        let location = std::env::temp_dir().join("crazy-train-snapshots");
+        std::fs::create_dir_all(&location).unwrap();
+        let path = location.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn snapshot_step_matches() {
+        let path = snapshot("match.snap", "hello  \r\nworld\n\n");
+        let step = SnapshotStep {
+            command: "printf 'hello\\nworld\\n'".to_string(),
+            snapshot_path: path,
+            update: false,
+        };
+        assert!(runner::new(vec![Box::new(step)]).run().is_ok());
+    }
+
+    #[test]
+    fn snapshot_step_reports_diff_on_mismatch() {
+        let path = snapshot("mismatch.snap", "hello\nworld\n");
+        let step = SnapshotStep {
+            command: "printf 'hello\\nthere\\n'".to_string(),
+            snapshot_path: path.clone(),
+            update: false,
+        };
+
+        let result = runner::new(vec![Box::new(step.clone())]).run();
+        let Err(Error::StepError {
+            kind: Kind::Plan,
+            description,
+            ..
+        }) = result
+        else {
+            panic!("expected a plan failure, got {result:?}");
+        };
+        assert!(description.starts_with("stdout does not match the snapshot, "));
+        assert!(description.contains("\n-world\n+there"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello\nworld\n");
+        assert_eq!(
+            std::fs::read_to_string(step.new_snapshot_path()).unwrap(),
+            "hello\nthere\n"
+        );
+
+        let output = Output {
+            status_code: Some(0),
+            stdout: "hello\nthere\n".to_string(),
+            ..Default::default()
+        };
+        assert!(!step
+            .is_success(&output, &PlanCtx::default())
+            .unwrap_err()
+            .contains("there"));
+    }
+
+    #[test]
+    fn snapshot_step_updates_snapshot() {
+        let path = snapshot("update.snap", "outdated\n");
+        let step = SnapshotStep {
+            command: "echo fresh".to_string(),
+            snapshot_path: path.clone(),
+            update: true,
+        };
+
+        assert!(runner::new(vec![Box::new(step)]).run().is_ok());
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "fresh\n");
+        assert!(update_requested(Some("1")));
+        assert!(!update_requested(Some("0")));
+        assert!(!update_requested(None));
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct ProjectStep {
+        location: PathBuf,
+    }
+
+    impl StepTrait for ProjectStep {
+        fn setup(&self) -> errors::Result<()> {
+            std::fs::create_dir_all(&self.location)?;
+            Ok(())
         }
+
+        fn plan(&self, _randomizer: &Randomizer) -> errors::Result<Plan> {
+            Ok(Plan::new::<Self>("echo created > created.txt").with_cwd(&self.location))
+        }
+
+        fn is_success(
+            &self,
+            execution_result: &Output,
+            _plan_ctx: &PlanCtx,
+        ) -> Result<bool, &'static str> {
+            Ok(execution_result.status_code == Some(0))
+        }
+
+        fn to_yaml(&self) -> serde_yaml::Value {
+            serde_yaml::to_value(self).expect("to yaml")
+        }
+    }
+
+    #[test]
+    fn plan_runs_in_its_working_directory() {
+        let location = std::env::temp_dir().join("crazy-train-plan-cwd");
+        let _ = std::fs::remove_dir_all(&location);
+
+        let step = ProjectStep {
+            location: location.clone(),
+        };
+        assert!(runner::new(vec![Box::new(step)]).run().is_ok());
+        assert!(location.join("created.txt").exists());
+
+        let missing = Plan::new::<ProjectStep>("true").with_cwd(location.join("missing"));
+        assert!(matches!(
+            missing.execute(),
+            Err(Error::WorkingDirectoryNotFound { .. })
+        ));
+
+        let _ = std::fs::remove_dir_all(&location);
+    }
+
+    #[test]
+    fn execute_all_stops_at_first_failing_command() {
+        let location = std::env::temp_dir().join("crazy-train-execute-all");
+        let _ = std::fs::remove_dir_all(&location);
+        std::fs::create_dir_all(&location).unwrap();
+
+        let plan = Plan::with_commands::<ProjectStep>(vec![
+            "echo first".to_string(),
+            "echo second; exit 3".to_string(),
+            "touch third.txt".to_string(),
+        ])
+        .with_cwd(&location);
+
+        let Err(Error::CommandFailed { index, outputs }) = plan.execute_all() else {
+            panic!("expected the second command to fail");
+        };
+        assert_eq!(index, 1);
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].stdout, "first\n");
+        assert_eq!(outputs[1].status_code, Some(3));
+        assert!(!location.join("third.txt").exists());
+
+        let status = plan.execute().unwrap().status_code;
+        assert_eq!(status, Some(3));
+        assert!(!location.join("third.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&location);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commands_do_not_leak_into_each_other() {
+        let plan = Plan::with_commands::<ProjectStep>(vec![
+            "echo a # note".to_string(),
+            "cd /".to_string(),
+            "echo b; pwd".to_string(),
+        ])
+        .with_cwd(std::env::temp_dir());
+
+        let joined = plan.execute().unwrap();
+        let outputs = plan.execute_all().unwrap();
+        assert_eq!(
+            joined.stdout,
+            outputs
+                .iter()
+                .map(|output| output.stdout.as_str())
+                .collect::<String>()
+        );
+        assert!(joined.stdout.starts_with("a\nb\n"));
+        assert_ne!(joined.stdout, "a\nb\n/\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "a plan needs at least one command")]
+    fn commands_must_not_be_empty() {
+        let _ = Plan::with_commands::<ProjectStep>(Vec::new());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn argv_plan_passes_arguments_verbatim() {
+        let argument = "two words; $HOME *".to_string();
+        let plan = Plan::with_argv::<KeywordStep>("printf", vec!["<%s>".to_string(), argument]);
+        assert_eq!(plan.execute().unwrap().stdout, "<two words; $HOME *>");
+
+        let output = executer::run_argv(
+            "sh",
+            &[
+                "-c".to_string(),
+                "echo $#".to_string(),
+                "sh".to_string(),
+                "a b".to_string(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(output.stdout, "1\n");
     }
 }
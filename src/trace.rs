@@ -0,0 +1,422 @@
+//! This module extracts the paths a command wrote to from the output of `strace`, see
+//! [`run_sh_traced`](crate::executer::run_sh_traced).
+//!
+//! The trace is expected to come from `strace -f -y -s 4096`: every line is prefixed with the
+//! PID of the traced process, file descriptors are followed by the path they refer to, e.g.
+//! `3</tmp/dir>`, and path strings are not truncated. Relative paths are resolved against the
+//! directory file descriptor of the syscall, or against the working directory of the process,
+//! which is tracked across `chdir`, `fchdir` and the processes it spawns.
+
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+/// The syscalls that must be traced for [`written_paths`].
+pub const TRACED_SYSCALLS: &str = "open,openat,openat2,creat,mkdir,mkdirat,mknod,mknodat,\
+unlink,unlinkat,rmdir,rename,renameat,renameat2,link,linkat,symlink,symlinkat,truncate,chdir,\
+fchdir,clone,clone3,fork,vfork,execve";
+
+/// Returns whether the trace contains a successful `execve`, i.e. whether `strace` ran the
+/// traced command. When `strace` can not trace, e.g. when `ptrace` is denied in a container,
+/// it still creates the trace file but never runs the command.
+pub fn executed(trace: &str) -> bool {
+    trace
+        .lines()
+        .filter_map(|line| Syscall::parse(split_pid(line).1))
+        .any(|syscall| syscall.name == "execve" && syscall.result == 0)
+}
+
+/// Returns every path written by a successful syscall of the trace, i.e. files opened for
+/// writing, created, truncated, removed or renamed, including the destination of a rename,
+/// links and special files created, and directories created or removed. The paths are absolute and normalized, see [`normalize`].
+pub fn written_paths(trace: &str, cwd: &Path) -> Vec<PathBuf> {
+    let mut cwds: HashMap<u32, PathBuf> = HashMap::new();
+    let mut unfinished: HashMap<u32, String> = HashMap::new();
+    let mut paths = Vec::new();
+
+    for line in trace.lines() {
+        let (pid, call) = split_pid(line);
+
+        // With several processes, a syscall may be split around the syscalls of others.
+        let call = if let Some(start) = call.strip_suffix(" <unfinished ...>") {
+            unfinished.insert(pid, start.to_string());
+            continue;
+        } else if let Some(rest) = call.strip_prefix("<... ") {
+            let Some(start) = unfinished.remove(&pid) else {
+                continue;
+            };
+            let Some((_, rest)) = rest.split_once(" resumed>") else {
+                continue;
+            };
+            format!("{start}{rest}")
+        } else {
+            call.to_string()
+        };
+
+        let Some(syscall) = Syscall::parse(&call) else {
+            continue;
+        };
+        if syscall.result < 0 {
+            continue;
+        }
+        let process_cwd = cwds.get(&pid).cloned().unwrap_or_else(|| cwd.to_path_buf());
+        let resolve = |dirfd: Option<&str>, path: &str| -> Option<PathBuf> {
+            let base = match dirfd {
+                None => Some(process_cwd.clone()),
+                Some(dirfd) if dirfd.starts_with("AT_FDCWD") => {
+                    Some(fd_path(dirfd).unwrap_or_else(|| process_cwd.clone()))
+                }
+                Some(dirfd) => fd_path(dirfd),
+            };
+            Some(match (base, unquote(path)) {
+                (Some(base), Some(path)) => normalize(&base.join(path)),
+                // An unresolvable path is kept verbatim, so it never matches an allowed path.
+                _ => PathBuf::from(path),
+            })
+        };
+        let args = &syscall.args;
+        let arg = |index: usize| args.get(index).map(String::as_str);
+
+        match syscall.name {
+            "open" if !opens_for_writing(arg(1)) => {}
+            "openat" if !opens_for_writing(arg(2)) => {}
+            "openat2" if !opens_for_writing(arg(2).and_then(open_how_flags)) => {}
+            "open" | "creat" | "mkdir" | "mknod" | "unlink" | "rmdir" | "truncate" => {
+                paths.extend(arg(0).and_then(|path| resolve(None, path)));
+            }
+            "openat" | "openat2" | "mkdirat" | "mknodat" | "unlinkat" => {
+                paths.extend(arg(1).and_then(|path| resolve(arg(0), path)));
+            }
+            // The target of a link is not written, only the link itself.
+            "link" | "symlink" => {
+                paths.extend(arg(1).and_then(|path| resolve(None, path)));
+            }
+            "linkat" => {
+                paths.extend(arg(3).and_then(|path| resolve(arg(2), path)));
+            }
+            "symlinkat" => {
+                paths.extend(arg(2).and_then(|path| resolve(arg(1), path)));
+            }
+            "rename" => {
+                paths.extend(arg(0).and_then(|path| resolve(None, path)));
+                paths.extend(arg(1).and_then(|path| resolve(None, path)));
+            }
+            "renameat" | "renameat2" => {
+                paths.extend(arg(1).and_then(|path| resolve(arg(0), path)));
+                paths.extend(arg(3).and_then(|path| resolve(arg(2), path)));
+            }
+            "chdir" => {
+                if let Some(path) = arg(0).and_then(|path| resolve(None, path)) {
+                    cwds.insert(pid, path);
+                }
+            }
+            "fchdir" => {
+                if let Some(path) = arg(0).and_then(fd_path) {
+                    cwds.insert(pid, normalize(&path));
+                }
+            }
+            "clone" | "clone3" | "fork" | "vfork" => {
+                if let Ok(child) = u32::try_from(syscall.result) {
+                    cwds.insert(child, process_cwd.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    paths
+}
+
+/// Splits a trace line into the PID of the process and its syscall, using PID 0 when the line
+/// has no PID.
+fn split_pid(line: &str) -> (u32, &str) {
+    match line.split_once(' ') {
+        Some((pid, call)) => pid.parse().map_or((0, line), |pid| (pid, call)),
+        None => (0, line),
+    }
+}
+
+/// Normalizes `path` lexically: `.` components are removed and `..` components remove the
+/// component before them, without following symbolic links. A `..` at the root stays at the
+/// root.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(component),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// A completed syscall of a trace line.
+struct Syscall<'a> {
+    name: &'a str,
+    args: Vec<String>,
+    result: i64,
+}
+
+impl<'a> Syscall<'a> {
+    /// Parses a line like `openat(AT_FDCWD</tmp>, "out", O_WRONLY) = 3`, without its PID.
+    fn parse(line: &'a str) -> Option<Self> {
+        let (name, rest) = line.split_once('(')?;
+        if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+            return None;
+        }
+        let (args, end) = split_args(rest)?;
+        let result = rest[end..]
+            .trim_start_matches(')')
+            .trim_start()
+            .strip_prefix('=')?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()?;
+        Some(Self { name, args, result })
+    }
+}
+
+/// Splits the arguments of a syscall up to its closing parenthesis, keeping strings, nested
+/// structures and the paths of file descriptors in one piece. Returns the arguments and the
+/// offset of the closing parenthesis.
+fn split_args(rest: &str) -> Option<(Vec<String>, usize)> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (offset, ch) in rest.char_indices() {
+        if in_string {
+            current.push(ch);
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match ch {
+            '"' => {
+                in_string = true;
+                current.push(ch);
+            }
+            '(' | '[' | '{' | '<' => {
+                depth += 1;
+                current.push(ch);
+            }
+            ')' if depth == 0 => {
+                if !current.trim().is_empty() {
+                    args.push(current.trim().to_string());
+                }
+                return Some((args, offset));
+            }
+            ')' | ']' | '}' | '>' => {
+                depth = depth.saturating_sub(1);
+                current.push(ch);
+            }
+            ',' if depth == 0 => args.push(std::mem::take(&mut current).trim().to_string()),
+            _ => current.push(ch),
+        }
+    }
+    None
+}
+
+/// Returns whether the flags of an `open` call, e.g. `O_WRONLY|O_CREAT`, open for writing.
+fn opens_for_writing(flags: Option<&str>) -> bool {
+    flags.is_some_and(|flags| {
+        flags
+            .split('|')
+            .any(|flag| matches!(flag.trim(), "O_WRONLY" | "O_RDWR" | "O_CREAT" | "O_TRUNC"))
+    })
+}
+
+/// Returns the flags of the `open_how` argument of an `openat2` call, e.g. `O_WRONLY|O_CREAT`
+/// for `{flags=O_WRONLY|O_CREAT, mode=0644, resolve=0}`.
+fn open_how_flags(open_how: &str) -> Option<&str> {
+    open_how
+        .strip_prefix('{')?
+        .split(',')
+        .find_map(|field| field.trim().strip_prefix("flags="))
+}
+
+/// Returns the path of a file descriptor argument decoded by `strace -y`, e.g. `/tmp` for
+/// `3</tmp>` or `AT_FDCWD</tmp>`.
+fn fd_path(arg: &str) -> Option<PathBuf> {
+    let start = arg.find('<')? + 1;
+    let end = arg.rfind('>')?;
+    (start <= end).then(|| PathBuf::from(&arg[start..end]))
+}
+
+/// Returns the content of a quoted `strace` string argument, resolving its escapes. Returns
+/// `None` when the argument is not a complete string, e.g. a truncated one.
+fn unquote(arg: &str) -> Option<String> {
+    let inner = arg.strip_prefix('"')?.strip_suffix('"')?;
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut chars = inner.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(ch.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        match chars.next()? {
+            'n' => bytes.push(b'\n'),
+            't' => bytes.push(b'\t'),
+            'r' => bytes.push(b'\r'),
+            'v' => bytes.push(0x0b),
+            'f' => bytes.push(0x0c),
+            'x' => {
+                let digits: String = (0..2).filter_map(|_| chars.next()).collect();
+                bytes.push(u8::from_str_radix(&digits, 16).ok()?);
+            }
+            digit @ '0'..='7' => {
+                let mut value = digit.to_digit(8)?;
+                for _ in 0..2 {
+                    match chars.peek().and_then(|next| next.to_digit(8)) {
+                        Some(next) => {
+                            value = value * 8 + next;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                bytes.push(u8::try_from(value).ok()?);
+            }
+            other => {
+                let mut buffer = [0; 4];
+                bytes.extend_from_slice(other.encode_utf8(&mut buffer).as_bytes());
+            }
+        }
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn paths(trace: &str) -> Vec<PathBuf> {
+        written_paths(trace, Path::new("/work"))
+    }
+
+    #[test]
+    fn keeps_only_successful_writes() {
+        let trace = r#"100 openat(AT_FDCWD</work>, "/tmp/out", O_WRONLY|O_CREAT|O_TRUNC, 0666) = 3
+100 openat(AT_FDCWD</work>, "/etc/ld.so.cache", O_RDONLY|O_CLOEXEC) = 3
+100 mkdir("dir", 0777) = -1 EEXIST (File exists)
+100 unlink("old") = 0
+100 +++ exited with 0 +++"#;
+        assert_eq!(
+            paths(trace),
+            vec![PathBuf::from("/tmp/out"), PathBuf::from("/work/old")]
+        );
+    }
+
+    #[test]
+    fn checks_both_paths_of_a_rename() {
+        let trace = r#"100 rename("scope/a", "/etc/passwd") = 0
+100 renameat2(AT_FDCWD</work>, "scope/b", 3</outside>, "c", RENAME_NOREPLACE) = 0"#;
+        assert_eq!(
+            paths(trace),
+            vec![
+                PathBuf::from("/work/scope/a"),
+                PathBuf::from("/etc/passwd"),
+                PathBuf::from("/work/scope/b"),
+                PathBuf::from("/outside/c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn normalizes_parent_components() {
+        assert_eq!(
+            paths(r#"100 openat(AT_FDCWD</work>, "scope/../../outside", O_WRONLY) = 3"#),
+            vec![PathBuf::from("/outside")]
+        );
+        assert_eq!(normalize(Path::new("/../a/./b/..")), PathBuf::from("/a"));
+    }
+
+    #[test]
+    fn tracks_the_working_directory_of_each_process() {
+        let trace = r#"100 chdir("/tmp") = 0
+100 clone(child_stack=NULL, flags=CLONE_CHILD_SETTID|SIGCHLD, child_tidptr=0x7f) = 101
+101 openat(AT_FDCWD, "a", O_WRONLY|O_CREAT, 0666 <unfinished ...>
+100 chdir("sub") = 0
+101 <... openat resumed>) = 3
+100 creat("b", 0644) = 4
+100 fchdir(5</var/data>) = 0
+100 mkdir("c", 0777) = 0
+100 mkdirat(6</srv>, "d", 0777) = 0"#;
+        assert_eq!(
+            paths(trace),
+            vec![
+                PathBuf::from("/tmp/a"),
+                PathBuf::from("/tmp/sub/b"),
+                PathBuf::from("/var/data/c"),
+                PathBuf::from("/srv/d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_links_truncations_and_special_files() {
+        let trace = r#"100 openat2(AT_FDCWD</work>, "a", {flags=O_WRONLY|O_CREAT, mode=0644}, 24) = 3
+100 openat2(AT_FDCWD</work>, "/etc/hosts", {flags=O_RDONLY, resolve=0}, 24) = 4
+100 link("/etc/passwd", "b") = 0
+100 linkat(AT_FDCWD</work>, "c", 3</outside>, "d", 0) = 0
+100 symlink("/etc", "/outside/e") = 0
+100 symlinkat("/etc", 4</srv>, "f") = 0
+100 truncate("/etc/shadow", 0) = 0
+100 mknod("fifo", S_IFIFO|0644) = 0
+100 mknodat(AT_FDCWD</tmp>, "g", S_IFIFO|0644) = 0"#;
+        assert_eq!(
+            paths(trace),
+            vec![
+                PathBuf::from("/work/a"),
+                PathBuf::from("/work/b"),
+                PathBuf::from("/outside/d"),
+                PathBuf::from("/outside/e"),
+                PathBuf::from("/srv/f"),
+                PathBuf::from("/etc/shadow"),
+                PathBuf::from("/work/fifo"),
+                PathBuf::from("/tmp/g"),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_whether_the_command_ran() {
+        let trace = r#"100 execve("/bin/sh", ["sh", "-c", "true"], 0x7ffd /* 20 vars */) = 0
+100 +++ exited with 0 +++"#;
+        assert!(executed(trace));
+        assert!(!executed(
+            r#"100 execve("/missing", ["missing"], 0x7ffd) = -1 ENOENT (No such file or directory)"#
+        ));
+        assert!(!executed(""));
+    }
+
+    #[test]
+    fn resolves_escaped_paths() {
+        assert_eq!(
+            paths(r#"100 unlink("it's \"quoted\"\303\251, ok") = 0"#),
+            vec![PathBuf::from("/work/it's \"quoted\"é, ok")]
+        );
+        assert_eq!(
+            paths(r#"100 unlink("truncated"...) = 0"#),
+            vec![PathBuf::from(r#""truncated"..."#)]
+        );
+    }
+}